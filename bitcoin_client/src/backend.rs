@@ -0,0 +1,99 @@
+use std::fmt;
+
+use async_trait::async_trait;
+use bitcoin::{Address, Amount, Block, BlockHash, OutPoint, ScriptBuf, Transaction, Txid};
+
+use crate::{error::Result, WalletClient};
+
+/// Transaction together with its position in the chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxInfo {
+    pub tx: Transaction,
+    /// `None` while the transaction is in the mempool.
+    pub block_hash: Option<BlockHash>,
+    /// Zero while the transaction is in the mempool.
+    pub confirmations: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Utxo {
+    pub outpoint: OutPoint,
+    pub amount: Amount,
+    pub script_pubkey: ScriptBuf,
+    pub confirmations: u32,
+}
+
+/// Operations on a Bitcoin node the bridge relies upon. Implemented by [`WalletClient`] for a
+/// real node, and by [`MockBitcoinBackend`](crate::mock::MockBitcoinBackend) for tests.
+#[async_trait]
+pub trait BitcoinBackend: fmt::Debug + Send + Sync + 'static {
+    /// Broadcasts a hex-encoded transaction.
+    async fn post_tx(&self, tx: String) -> Result<Txid>;
+
+    async fn get_tx(&self, tx_id: Txid) -> Result<Transaction>;
+
+    async fn get_tx_info(&self, tx_id: Txid) -> Result<TxInfo>;
+
+    async fn get_block_count(&self) -> Result<u64>;
+
+    async fn get_block_hash(&self, height: u64) -> Result<BlockHash>;
+
+    async fn get_block(&self, block_hash: &BlockHash) -> Result<Block>;
+
+    /// Lists unspent outputs of `address` with at least `min_confirmation` (1 by default)
+    /// confirmations.
+    async fn get_unspent(
+        &self,
+        address: &Address,
+        min_confirmation: Option<usize>,
+    ) -> Result<Vec<Utxo>>;
+}
+
+#[async_trait]
+impl BitcoinBackend for WalletClient {
+    async fn post_tx(&self, tx: String) -> Result<Txid> {
+        self.node().post_tx(tx).await
+    }
+
+    async fn get_tx(&self, tx_id: Txid) -> Result<Transaction> {
+        self.node().get_tx(tx_id).await
+    }
+
+    async fn get_tx_info(&self, tx_id: Txid) -> Result<TxInfo> {
+        let info = self.node().get_tx_info(tx_id).await?;
+        Ok(TxInfo {
+            tx: info.transaction()?,
+            block_hash: info.blockhash,
+            confirmations: info.confirmations.unwrap_or(0),
+        })
+    }
+
+    async fn get_block_count(&self) -> Result<u64> {
+        self.node().get_block_count().await
+    }
+
+    async fn get_block_hash(&self, height: u64) -> Result<BlockHash> {
+        self.node().get_block_hash(height).await
+    }
+
+    async fn get_block(&self, block_hash: &BlockHash) -> Result<Block> {
+        self.node().get_block(block_hash).await
+    }
+
+    async fn get_unspent(
+        &self,
+        address: &Address,
+        min_confirmation: Option<usize>,
+    ) -> Result<Vec<Utxo>> {
+        let entries = WalletClient::get_unspent(self, address, min_confirmation).await?;
+        Ok(entries
+            .into_iter()
+            .map(|entry| Utxo {
+                outpoint: OutPoint::new(entry.txid, entry.vout),
+                amount: entry.amount,
+                script_pubkey: entry.script_pub_key,
+                confirmations: entry.confirmations,
+            })
+            .collect())
+    }
+}
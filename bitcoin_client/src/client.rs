@@ -0,0 +1,457 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use bitcoin::{consensus::deserialize, Address, Block, BlockHash, Transaction, Txid};
+use bitcoincore_rpc::json::{
+    GetBlockchainInfoResult, GetRawTransactionResult, ListUnspentResultEntry,
+};
+use rand::Rng;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error::{BitcoinRpcError, Result, RPC_VERIFY_ALREADY_IN_CHAIN};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Retries of calls failing with a transient error, see [`BitcoinRpcError::is_transient()`].
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential backoff with full jitter.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .min(self.max_backoff);
+        backoff.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+/// A single call in a [`BitcoinRpcClient::batch()`].
+#[derive(Debug, Clone)]
+pub struct RpcRequest {
+    pub method: &'static str,
+    pub params: Vec<Value>,
+}
+
+impl RpcRequest {
+    pub fn new(method: &'static str, params: Vec<Value>) -> Self {
+        Self { method, params }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct RequestBody<'a> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'a str,
+    params: &'a [Value],
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseBody {
+    id: Option<u64>,
+    result: Option<Value>,
+    error: Option<ErrorBody>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    code: i32,
+    message: String,
+}
+
+impl ResponseBody {
+    fn into_result<T: DeserializeOwned>(self) -> Result<T> {
+        if let Some(err) = self.error {
+            return Err(BitcoinRpcError::Rpc {
+                code: err.code,
+                message: err.message,
+            });
+        }
+        Ok(serde_json::from_value(self.result.unwrap_or(Value::Null))?)
+    }
+}
+
+/// Async JSON-RPC client of a Bitcoin Core node.
+///
+/// Cloning is cheap; clones share the underlying HTTP connection pool.
+#[derive(Debug, Clone)]
+pub struct BitcoinRpcClient {
+    http: reqwest::Client,
+    url: String,
+    user: String,
+    password: String,
+    timeout: Duration,
+    retry_policy: RetryPolicy,
+    next_id: Arc<AtomicU64>,
+}
+
+impl BitcoinRpcClient {
+    /// Creates a client of the node at `url`, e.g. "http://127.0.0.1:18443". Wallet calls go
+    /// through [`Self::wallet()`] instead.
+    pub fn new(url: &str, user: &str, password: &str) -> Result<Self> {
+        let http = reqwest::Client::builder().build()?;
+        Ok(Self {
+            http,
+            url: url.trim_end_matches('/').to_string(),
+            user: user.to_string(),
+            password: password.to_string(),
+            timeout: DEFAULT_TIMEOUT,
+            retry_policy: RetryPolicy::default(),
+            next_id: Arc::default(),
+        })
+    }
+
+    /// Sets the timeout of a single attempt of a call.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Returns a client of the node's wallet `name`, as required by wallet RPC methods.
+    pub fn wallet(&self, name: &str) -> WalletClient {
+        let mut inner = self.clone();
+        inner.url = format!("{}/wallet/{name}", self.url);
+        WalletClient { inner }
+    }
+
+    pub async fn call<T: DeserializeOwned>(&self, method: &str, params: &[Value]) -> Result<T> {
+        let body = RequestBody {
+            jsonrpc: "1.0",
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            method,
+            params,
+        };
+        let response: ResponseBody = self.send_with_retries(&body).await?;
+        response.into_result()
+    }
+
+    /// Sends `requests` as a single JSON-RPC batch. The outer error is returned if the batch as a
+    /// whole fails; otherwise, results are returned in the order of `requests`.
+    pub async fn batch(&self, requests: &[RpcRequest]) -> Result<Vec<Result<Value>>> {
+        if requests.is_empty() {
+            return Ok(vec![]);
+        }
+        let first_id = self
+            .next_id
+            .fetch_add(requests.len() as u64, Ordering::Relaxed);
+        let body: Vec<_> = requests
+            .iter()
+            .zip(first_id..)
+            .map(|(request, id)| RequestBody {
+                jsonrpc: "1.0",
+                id,
+                method: request.method,
+                params: &request.params,
+            })
+            .collect();
+        let responses: Vec<ResponseBody> = self.send_with_retries(&body).await?;
+
+        // Responses to a batch may come in any order.
+        let mut results: Vec<Option<Result<Value>>> = requests.iter().map(|_| None).collect();
+        for response in responses {
+            let idx = response
+                .id
+                .and_then(|id| id.checked_sub(first_id))
+                .filter(|&idx| idx < requests.len() as u64)
+                .ok_or_else(|| {
+                    BitcoinRpcError::InvalidResponse(format!(
+                        "unexpected id {:?} in batch response",
+                        response.id
+                    ))
+                })?;
+            results[idx as usize] = Some(response.into_result());
+        }
+        results
+            .into_iter()
+            .map(|result| {
+                result.ok_or_else(|| {
+                    BitcoinRpcError::InvalidResponse("missing response in batch".to_string())
+                })
+            })
+            .collect()
+    }
+
+    async fn send_with_retries<B: Serialize, R: DeserializeOwned>(&self, body: &B) -> Result<R> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match self.send(body).await {
+                Err(err) if err.is_transient() && attempt <= self.retry_policy.max_retries => {
+                    let backoff = self.retry_policy.backoff(attempt);
+                    logs::warn!(
+                        "bitcoin RPC call failed (attempt {attempt}), retrying in {backoff:?}: {err}"
+                    );
+                    tokio::time::sleep(backoff).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn send<B: Serialize, R: DeserializeOwned>(&self, body: &B) -> Result<R> {
+        let response = self
+            .http
+            .post(&self.url)
+            .basic_auth(&self.user, Some(&self.password))
+            .timeout(self.timeout)
+            .json(body)
+            .send()
+            .await?;
+        let status = response.status();
+        let bytes = response.bytes().await?;
+        // Bitcoin Core reports RPC errors with a non-200 status and a JSON-RPC body,
+        // so the body takes precedence over the status.
+        serde_json::from_slice(&bytes).map_err(|err| {
+            if status.is_success() {
+                err.into()
+            } else {
+                BitcoinRpcError::Http {
+                    status,
+                    body: String::from_utf8_lossy(&bytes).into_owned(),
+                }
+            }
+        })
+    }
+
+    /// Broadcasts `tx`. A transaction that is already mined counts as broadcast, e.g. when the
+    /// node accepted an earlier attempt whose response timed out.
+    pub async fn post_tx(&self, tx: String) -> Result<Txid> {
+        match self.call("sendrawtransaction", &[json!(tx)]).await {
+            Err(BitcoinRpcError::Rpc {
+                code: RPC_VERIFY_ALREADY_IN_CHAIN,
+                ..
+            }) => {
+                let tx: Transaction = deserialize(&hex::decode(&tx)?)?;
+                Ok(tx.txid())
+            }
+            result => result,
+        }
+    }
+
+    pub async fn get_tx(&self, tx_id: Txid) -> Result<Transaction> {
+        let tx_hex: String = self.call("getrawtransaction", &[json!(tx_id)]).await?;
+        Ok(deserialize(&hex::decode(tx_hex)?)?)
+    }
+
+    pub async fn get_tx_info(&self, tx_id: Txid) -> Result<GetRawTransactionResult> {
+        self.call("getrawtransaction", &[json!(tx_id), json!(true)])
+            .await
+    }
+
+    pub async fn get_block_count(&self) -> Result<u64> {
+        self.call("getblockcount", &[]).await
+    }
+
+    pub async fn get_block_hash(&self, height: u64) -> Result<BlockHash> {
+        self.call("getblockhash", &[json!(height)]).await
+    }
+
+    pub async fn get_block(&self, block_hash: &BlockHash) -> Result<Block> {
+        let block_hex: String = self
+            .call("getblock", &[json!(block_hash), json!(0)])
+            .await?;
+        Ok(deserialize(&hex::decode(block_hex)?)?)
+    }
+
+    pub async fn get_block_header(&self, block_hash: &BlockHash) -> Result<bitcoin::block::Header> {
+        let header_hex: String = self
+            .call("getblockheader", &[json!(block_hash), json!(false)])
+            .await?;
+        Ok(deserialize(&hex::decode(header_hex)?)?)
+    }
+
+    pub async fn get_blockchain_info(&self) -> Result<GetBlockchainInfoResult> {
+        self.call("getblockchaininfo", &[]).await
+    }
+}
+
+/// Client of a single wallet loaded into the node; see [`BitcoinRpcClient::wallet()`].
+#[derive(Debug, Clone)]
+pub struct WalletClient {
+    inner: BitcoinRpcClient,
+}
+
+impl WalletClient {
+    /// Client of the node the wallet is loaded into.
+    pub fn node(&self) -> &BitcoinRpcClient {
+        &self.inner
+    }
+
+    /// Lists unspent outputs of `address`, including unsafe ones.
+    pub async fn get_unspent(
+        &self,
+        address: &Address,
+        min_confirmation: Option<usize>,
+    ) -> Result<Vec<ListUnspentResultEntry>> {
+        self.inner
+            .call(
+                "listunspent",
+                &[
+                    json!(min_confirmation.unwrap_or(1)),
+                    json!(9_999_999),
+                    json!([address]),
+                    json!(true),
+                ],
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use bitcoin::hashes::Hash;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Serves `responses` (status, body) in order, one per connection.
+    async fn serve(responses: Vec<(u16, String)>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let served = Arc::new(AtomicUsize::new(0));
+        let served_clone = served.clone();
+        tokio::spawn(async move {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                read_request(&mut stream).await;
+                served_clone.fetch_add(1, Ordering::SeqCst);
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\ncontent-type: application/json\r\n\
+                     content-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, served)
+    }
+
+    async fn read_request(stream: &mut tokio::net::TcpStream) {
+        let mut request = vec![];
+        let mut buf = [0; 4096];
+        loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some(header_end) = text.find("\r\n\r\n") {
+                let content_length = text[..header_end]
+                    .lines()
+                    .find_map(|line| {
+                        line.to_lowercase()
+                            .strip_prefix("content-length: ")?
+                            .parse()
+                            .ok()
+                    })
+                    .unwrap_or(0);
+                if request.len() >= header_end + 4 + content_length || n == 0 {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn client(url: &str) -> BitcoinRpcClient {
+        BitcoinRpcClient::new(url, "test", "1234")
+            .unwrap()
+            .with_retry_policy(RetryPolicy {
+                max_retries: 2,
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(1),
+            })
+    }
+
+    #[tokio::test]
+    async fn transient_errors_are_retried() {
+        let (url, served) = serve(vec![
+            (503, "Work queue depth exceeded".to_string()),
+            (200, r#"{"result":101,"error":null,"id":0}"#.to_string()),
+        ])
+        .await;
+
+        assert_eq!(client(&url).get_block_count().await.unwrap(), 101);
+        assert_eq!(served.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn rpc_errors_are_not_retried() {
+        let body = r#"{"result":null,"error":{"code":-5,"message":"No such mempool or blockchain transaction"},"id":0}"#;
+        let (url, served) = serve(vec![(500, body.to_string())]).await;
+
+        let err = client(&url).get_tx(Txid::all_zeros()).await.unwrap_err();
+        assert!(
+            matches!(err, BitcoinRpcError::Rpc { code: -5, .. }),
+            "{err}"
+        );
+        assert_eq!(served.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn posting_mined_transactions_succeeds() {
+        let tx = Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![bitcoin::TxIn::default()],
+            output: vec![],
+        };
+        let body = r#"{"result":null,"error":{"code":-27,"message":"Transaction already in block chain"},"id":0}"#;
+        let (url, _) = serve(vec![(500, body.to_string())]).await;
+
+        let txid = client(&url)
+            .post_tx(bitcoin::consensus::encode::serialize_hex(&tx))
+            .await
+            .unwrap();
+        assert_eq!(txid, tx.txid());
+    }
+
+    #[tokio::test]
+    async fn batch_results_are_matched_by_id() {
+        let body = r#"[
+            {"result":null,"error":{"code":-8,"message":"Block height out of range"},"id":1},
+            {"result":"00","error":null,"id":0}
+        ]"#;
+        let (url, _) = serve(vec![(200, body.to_string())]).await;
+
+        let requests = [
+            RpcRequest::new("getblockhash", vec![json!(0)]),
+            RpcRequest::new("getblockhash", vec![json!(1_000)]),
+        ];
+        let results = client(&url).batch(&requests).await.unwrap();
+        assert_eq!(results[0].as_ref().unwrap(), &json!("00"));
+        assert!(matches!(
+            results[1],
+            Err(BitcoinRpcError::Rpc { code: -8, .. })
+        ));
+    }
+}
@@ -0,0 +1,82 @@
+use reqwest::StatusCode;
+
+pub type Result<T> = std::result::Result<T, BitcoinRpcError>;
+
+/// Bitcoin Core returns this while it is still loading the block index.
+const RPC_IN_WARMUP: i32 = -28;
+/// Bitcoin Core returns these when it refuses a submitted transaction.
+pub(crate) const RPC_DESERIALIZATION_ERROR: i32 = -22;
+pub(crate) const RPC_VERIFY_ERROR: i32 = -25;
+pub(crate) const RPC_VERIFY_REJECTED: i32 = -26;
+/// Bitcoin Core returns this when a submitted transaction is already mined.
+pub(crate) const RPC_VERIFY_ALREADY_IN_CHAIN: i32 = -27;
+/// Bitcoin Core returns this for transactions it doesn't know, among others.
+pub(crate) const RPC_INVALID_ADDRESS_OR_KEY: i32 = -5;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BitcoinRpcError {
+    #[error("transport error: {0}")]
+    Transport(#[from] reqwest::Error),
+    #[error("HTTP error {status}: {body}")]
+    Http { status: StatusCode, body: String },
+    #[error("RPC error {code}: {message}")]
+    Rpc { code: i32, message: String },
+    #[error("invalid response: {0}")]
+    InvalidResponse(String),
+    #[error("failed decoding response: {0}")]
+    Decode(String),
+}
+
+impl BitcoinRpcError {
+    /// Whether the call may succeed if retried as is.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Transport(err) => err.is_timeout() || err.is_connect() || err.is_request(),
+            Self::Http { status, .. } => matches!(
+                *status,
+                StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ),
+            Self::Rpc { code, .. } => *code == RPC_IN_WARMUP,
+            Self::InvalidResponse(_) | Self::Decode(_) => false,
+        }
+    }
+
+    /// Whether the node refused a submitted transaction, e.g. because it is malformed or spends
+    /// missing outputs. Transactions that are already mined aren't refused: `post_tx` returns
+    /// their txid.
+    pub fn is_tx_rejection(&self) -> bool {
+        matches!(
+            self,
+            Self::Rpc { code, .. } if matches!(
+                *code,
+                RPC_DESERIALIZATION_ERROR | RPC_VERIFY_ERROR | RPC_VERIFY_REJECTED
+            )
+        )
+    }
+
+    /// Whether the node knows no transaction with the requested txid, neither in the mempool nor
+    /// in the chain.
+    pub fn is_unknown_tx(&self) -> bool {
+        matches!(self, Self::Rpc { code, .. } if *code == RPC_INVALID_ADDRESS_OR_KEY)
+    }
+}
+
+impl From<serde_json::Error> for BitcoinRpcError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Decode(err.to_string())
+    }
+}
+
+impl From<bitcoin::consensus::encode::Error> for BitcoinRpcError {
+    fn from(err: bitcoin::consensus::encode::Error) -> Self {
+        Self::Decode(err.to_string())
+    }
+}
+
+impl From<hex::FromHexError> for BitcoinRpcError {
+    fn from(err: hex::FromHexError) -> Self {
+        Self::Decode(err.to_string())
+    }
+}
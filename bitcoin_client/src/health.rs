@@ -0,0 +1,162 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bitcoin::BlockHash;
+use health_check::{Health, HealthStatus, HealthUpdater, ReactiveHealthCheck};
+use serde::Serialize;
+use tokio::sync::watch;
+
+use crate::{BitcoinRpcClient, BitcoinRpcError};
+
+#[derive(Debug, Serialize)]
+struct BitcoinHealthDetails {
+    tip_height: u64,
+    best_block_hash: BlockHash,
+    initial_block_download: bool,
+    seconds_since_last_block: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct BitcoinErrorDetails {
+    error: String,
+}
+
+/// Periodically polls the Bitcoin node and reports its chain tip as the `bitcoin_node` component
+/// on the health server.
+///
+/// The node is reported as not ready while it is unreachable, still in initial block download,
+/// or, if `max_block_age` is set, when its tip is older than that.
+#[derive(Debug)]
+pub struct BitcoinHealthTask {
+    client: BitcoinRpcClient,
+    interval: Duration,
+    max_block_age: Option<Duration>,
+    health_updater: HealthUpdater,
+}
+
+impl BitcoinHealthTask {
+    pub fn new(
+        client: BitcoinRpcClient,
+        interval: Duration,
+        max_block_age: Option<Duration>,
+    ) -> (Self, ReactiveHealthCheck) {
+        let (health_check, health_updater) = ReactiveHealthCheck::new("bitcoin_node");
+        let this = Self {
+            client,
+            interval,
+            max_block_age,
+            health_updater,
+        };
+        (this, health_check)
+    }
+
+    pub async fn run(self, mut stop_receiver: watch::Receiver<bool>) -> anyhow::Result<()> {
+        let mut timer = tokio::time::interval(self.interval);
+        loop {
+            tokio::select! {
+                _ = timer.tick() => {}
+                _ = stop_receiver.changed() => break,
+            }
+            if *stop_receiver.borrow() {
+                break;
+            }
+            let health = self.check().await;
+            self.health_updater.update(health);
+        }
+        logs::info!("Stop signal received, bitcoin health check is shutting down");
+        Ok(())
+    }
+
+    async fn check(&self) -> Health {
+        let tip = async {
+            let info = self.client.get_blockchain_info().await?;
+            let header = self.client.get_block_header(&info.best_block_hash).await?;
+            Ok::<_, BitcoinRpcError>((info, header))
+        };
+        let (info, header) = match tip.await {
+            Ok(tip) => tip,
+            Err(err) => return Self::unreachable(err.to_string()),
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let details = BitcoinHealthDetails {
+            tip_height: info.blocks,
+            best_block_hash: info.best_block_hash,
+            initial_block_download: info.initial_block_download,
+            seconds_since_last_block: now.saturating_sub(header.time.into()),
+        };
+        Health::from(self.status(&details)).with_details(details)
+    }
+
+    fn status(&self, details: &BitcoinHealthDetails) -> HealthStatus {
+        if details.initial_block_download {
+            return HealthStatus::NotReady;
+        }
+        match self.max_block_age {
+            Some(max_age) if details.seconds_since_last_block > max_age.as_secs() => {
+                logs::warn!(
+                    "bitcoin node tip {} is {}s old",
+                    details.best_block_hash,
+                    details.seconds_since_last_block
+                );
+                HealthStatus::NotReady
+            }
+            _ => HealthStatus::Ready,
+        }
+    }
+
+    fn unreachable(error: String) -> Health {
+        logs::warn!("bitcoin health check failed: {error}");
+        Health::from(HealthStatus::NotReady).with_details(BitcoinErrorDetails { error })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::hashes::Hash;
+
+    use super::*;
+
+    fn task(max_block_age: Option<Duration>) -> BitcoinHealthTask {
+        // Creating the client doesn't connect to the node.
+        let client = BitcoinRpcClient::new("http://127.0.0.1:18443", "test", "1234").unwrap();
+        BitcoinHealthTask::new(client, Duration::from_secs(30), max_block_age).0
+    }
+
+    fn details(
+        initial_block_download: bool,
+        seconds_since_last_block: u64,
+    ) -> BitcoinHealthDetails {
+        BitcoinHealthDetails {
+            tip_height: 100,
+            best_block_hash: BlockHash::all_zeros(),
+            initial_block_download,
+            seconds_since_last_block,
+        }
+    }
+
+    #[test]
+    fn node_health_status() {
+        let task_without_max_age = task(None);
+        assert_eq!(
+            task_without_max_age.status(&details(false, 86_400)),
+            HealthStatus::Ready
+        );
+        assert_eq!(
+            task_without_max_age.status(&details(true, 0)),
+            HealthStatus::NotReady
+        );
+
+        let task_with_max_age = task(Some(Duration::from_secs(3_600)));
+        assert_eq!(
+            task_with_max_age.status(&details(false, 600)),
+            HealthStatus::Ready
+        );
+        assert_eq!(
+            task_with_max_age.status(&details(false, 7_200)),
+            HealthStatus::NotReady
+        );
+    }
+}
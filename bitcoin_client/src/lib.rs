@@ -1,48 +1,18 @@
-use bitcoin::Address;
-pub use bitcoincore_rpc::json::{GetRawTransactionResult, ListUnspentResultEntry};
-use bitcoincore_rpc::{Auth, Client, Result, RpcApi};
-
-#[derive(Debug)]
-pub struct BitcoinRpcClient {
-    client: Client,
-}
-
-impl BitcoinRpcClient {
-    pub fn new(url: &str, user: &str, password: &str) -> Result<Self> {
-        let client = Client::new(url, Auth::UserPass(user.to_string(), password.to_string()))?;
-        Ok(Self { client })
-    }
-
-    pub fn post_tx(&self, tx: String) -> Result<bitcoin::Txid> {
-        self.client.send_raw_transaction(tx)
-    }
-
-    pub fn get_tx(&self, tx_id: bitcoin::Txid) -> Result<bitcoin::Transaction> {
-        self.client.get_raw_transaction(&tx_id, None)
-    }
-
-    pub fn get_tx_info(&self, tx_id: bitcoin::Txid) -> Result<GetRawTransactionResult> {
-        self.client.get_raw_transaction_info(&tx_id, None)
-    }
-
-    pub fn get_block_count(&self) -> Result<u64> {
-        self.client.get_block_count()
-    }
-
-    // List unspent transaction outputs, for this api, url must has wallet field, e.g. "http://127.0.0.1:18443/wallet/benefactor".
-    pub fn get_unspent(
-        &self,
-        address: &Address,
-        min_confirmation: Option<usize>,
-    ) -> Result<Vec<ListUnspentResultEntry>> {
-        self.client
-            .list_unspent(min_confirmation, None, Some(&[&address]), Some(true), None)
-    }
-
-    pub fn get_block(&self, block_hash: &bitcoin::BlockHash) -> Result<bitcoin::Block> {
-        self.client.get_block(block_hash)
-    }
-}
+pub use bitcoincore_rpc::json::{
+    GetBlockchainInfoResult, GetRawTransactionResult, ListUnspentResultEntry,
+};
+
+pub use self::{
+    backend::{BitcoinBackend, TxInfo, Utxo},
+    client::{BitcoinRpcClient, RetryPolicy, RpcRequest, WalletClient},
+    error::{BitcoinRpcError, Result},
+};
+
+mod backend;
+mod client;
+pub mod error;
+pub mod health;
+pub mod mock;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,616 @@
+//! In-memory regtest chain for testing code built on [`BitcoinBackend`] without a node.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use bitcoin::{
+    absolute::LockTime,
+    block::{self, Header},
+    blockdata::constants::genesis_block,
+    consensus::deserialize,
+    hash_types::TxMerkleNode,
+    hashes::Hash,
+    opcodes::all::OP_RETURN,
+    script::Builder,
+    transaction::Version,
+    Address, Amount, Block, BlockHash, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn,
+    TxOut, Txid, Witness,
+};
+use reqwest::StatusCode;
+
+use crate::{
+    backend::{BitcoinBackend, TxInfo, Utxo},
+    error::{
+        BitcoinRpcError, Result, RPC_DESERIALIZATION_ERROR, RPC_INVALID_ADDRESS_OR_KEY,
+        RPC_VERIFY_ALREADY_IN_CHAIN, RPC_VERIFY_ERROR, RPC_VERIFY_REJECTED,
+    },
+};
+
+/// Error codes returned by Bitcoin Core in the same situations.
+const RPC_INVALID_PARAMETER: i32 = -8;
+
+const BLOCK_SUBSIDY: Amount = Amount::from_sat(50 * 100_000_000);
+const BLOCK_INTERVAL_SECS: u32 = 600;
+
+fn rpc_error(code: i32, message: &str) -> BitcoinRpcError {
+    BitcoinRpcError::Rpc {
+        code,
+        message: message.to_string(),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct UtxoEntry {
+    output: TxOut,
+    /// `None` for outputs of mempool transactions.
+    height: Option<u64>,
+}
+
+#[derive(Debug)]
+struct MockChain {
+    blocks: Vec<Block>,
+    block_heights: HashMap<BlockHash, u64>,
+    /// Mempool transactions in arrival order, together with their fees.
+    mempool: Vec<(Transaction, Amount)>,
+    /// Heights of confirmed transactions; `None` for mempool transactions.
+    txs: HashMap<Txid, (Transaction, Option<u64>)>,
+    utxos: HashMap<OutPoint, UtxoEntry>,
+    /// Fees of non-coinbase transactions, kept to re-add them to the mempool after a reorg.
+    fees: HashMap<Txid, Amount>,
+    /// Makes transactions created by [`MockBitcoinBackend::fund()`] unique. It starts at random,
+    /// so that mock chains don't share transactions.
+    faucet_nonce: u64,
+    /// Used as the header nonce, so that blocks replacing invalidated ones get new hashes.
+    blocks_mined: u32,
+    /// Set by [`MockBitcoinBackend::time_out_next_post_tx()`].
+    time_out_post_tx: bool,
+}
+
+impl MockChain {
+    fn new(network: Network) -> Self {
+        // Like in Bitcoin Core, the genesis coinbase is not spendable and not indexed.
+        let genesis = genesis_block(network);
+        Self {
+            block_heights: HashMap::from([(genesis.block_hash(), 0)]),
+            blocks: vec![genesis],
+            mempool: vec![],
+            txs: HashMap::new(),
+            utxos: HashMap::new(),
+            fees: HashMap::new(),
+            faucet_nonce: rand::random(),
+            blocks_mined: 0,
+            time_out_post_tx: false,
+        }
+    }
+
+    fn tip_height(&self) -> u64 {
+        self.blocks.len() as u64 - 1
+    }
+
+    fn confirmations(&self, height: Option<u64>) -> u32 {
+        height.map_or(0, |height| (self.tip_height() - height + 1) as u32)
+    }
+
+    fn accept_to_mempool(&mut self, tx: Transaction) -> Result<Txid> {
+        let txid = tx.txid();
+        if let Some((_, height)) = self.txs.get(&txid) {
+            return Err(match height {
+                Some(_) => rpc_error(
+                    RPC_VERIFY_ALREADY_IN_CHAIN,
+                    "Transaction already in block chain",
+                ),
+                None => rpc_error(RPC_VERIFY_REJECTED, "txn-already-in-mempool"),
+            });
+        }
+        if tx.is_coinbase() {
+            return Err(rpc_error(RPC_VERIFY_REJECTED, "coinbase"));
+        }
+
+        let mut input_value = Amount::ZERO;
+        for input in &tx.input {
+            let utxo = self
+                .utxos
+                .get(&input.previous_output)
+                .ok_or_else(|| rpc_error(RPC_VERIFY_ERROR, "bad-txns-inputs-missingorspent"))?;
+            input_value += utxo.output.value;
+        }
+        let output_value = tx.output.iter().map(|output| output.value).sum();
+        if input_value < output_value {
+            return Err(rpc_error(RPC_VERIFY_REJECTED, "bad-txns-in-belowout"));
+        }
+
+        self.add_to_mempool(tx, input_value - output_value);
+        Ok(txid)
+    }
+
+    fn add_to_mempool(&mut self, tx: Transaction, fee: Amount) {
+        self.apply_tx(&tx, None);
+        self.txs.insert(tx.txid(), (tx.clone(), None));
+        self.fees.insert(tx.txid(), fee);
+        self.mempool.push((tx, fee));
+    }
+
+    /// Disconnects the last `count` blocks. Their transactions return to the mempool ahead of
+    /// the ones already there; coinbase transactions are dropped.
+    fn invalidate_blocks(&mut self, count: usize) -> Vec<BlockHash> {
+        let fork_height = self.blocks.len().saturating_sub(count).max(1);
+        let disconnected = self.blocks.split_off(fork_height);
+        let mempool = std::mem::take(&mut self.mempool);
+
+        // Rebuilding the UTXO set from scratch is good enough for test chains.
+        self.txs.clear();
+        self.utxos.clear();
+        let confirmed = self.blocks[1..].to_vec();
+        for (height, block) in (1..).zip(&confirmed) {
+            for tx in &block.txdata {
+                self.apply_tx(tx, Some(height));
+                self.txs.insert(tx.txid(), (tx.clone(), Some(height)));
+            }
+        }
+
+        let reverted = disconnected
+            .iter()
+            .flat_map(|block| block.txdata.iter().skip(1).cloned())
+            .chain(mempool.into_iter().map(|(tx, _)| tx));
+        for tx in reverted.collect::<Vec<_>>() {
+            let fee = self.fees.get(&tx.txid()).copied().unwrap_or(Amount::ZERO);
+            self.add_to_mempool(tx, fee);
+        }
+
+        disconnected
+            .iter()
+            .map(|block| {
+                let block_hash = block.block_hash();
+                self.block_heights.remove(&block_hash);
+                block_hash
+            })
+            .collect()
+    }
+
+    fn remove_from_mempool(&mut self, txid: Txid) -> bool {
+        let Some(position) = self.mempool.iter().position(|(tx, _)| tx.txid() == txid) else {
+            return false;
+        };
+        let (tx, _) = self.mempool.remove(position);
+        self.txs.remove(&txid);
+        self.fees.remove(&txid);
+        // Spent outputs are not restored; this is only meant for transactions nobody depends on.
+        for vout in 0..tx.output.len() {
+            self.utxos.remove(&OutPoint::new(txid, vout as u32));
+        }
+        true
+    }
+
+    /// Moves the UTXO set past `tx`, which is either confirmed at `height` or enters the mempool.
+    fn apply_tx(&mut self, tx: &Transaction, height: Option<u64>) {
+        if !tx.is_coinbase() {
+            for input in &tx.input {
+                self.utxos.remove(&input.previous_output);
+            }
+        }
+        let txid = tx.txid();
+        for (vout, output) in tx.output.iter().enumerate() {
+            if output.script_pubkey.is_op_return() {
+                continue;
+            }
+            let entry = UtxoEntry {
+                output: output.clone(),
+                height,
+            };
+            self.utxos.insert(OutPoint::new(txid, vout as u32), entry);
+        }
+    }
+
+    fn mine_block(&mut self, reward_script: ScriptBuf) -> BlockHash {
+        let height = self.tip_height() + 1;
+        let tip = &self.blocks.last().unwrap().header;
+        let (prev_blockhash, time, bits) = (tip.block_hash(), tip.time, tip.bits);
+        self.blocks_mined += 1;
+
+        let fees: Amount = self.mempool.iter().map(|(_, fee)| *fee).sum();
+        let coinbase = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                // BIP-34 height; the extra push keeps the script at least 2 bytes long.
+                script_sig: Builder::new()
+                    .push_int(height as i64)
+                    .push_int(0)
+                    .into_script(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: BLOCK_SUBSIDY + fees,
+                script_pubkey: reward_script,
+            }],
+        };
+
+        let mut txdata = vec![coinbase];
+        txdata.extend(self.mempool.drain(..).map(|(tx, _)| tx));
+        let mut block = Block {
+            header: Header {
+                version: block::Version::TWO,
+                prev_blockhash,
+                merkle_root: TxMerkleNode::all_zeros(),
+                time: time + BLOCK_INTERVAL_SECS,
+                bits,
+                nonce: self.blocks_mined,
+            },
+            txdata,
+        };
+        block.header.merkle_root = block.compute_merkle_root().unwrap();
+
+        for tx in &block.txdata {
+            if tx.is_coinbase() {
+                self.apply_tx(tx, Some(height));
+            } else {
+                // Inputs are already spent; only the height of the outputs changes.
+                for vout in 0..tx.output.len() {
+                    if let Some(entry) = self.utxos.get_mut(&OutPoint::new(tx.txid(), vout as u32))
+                    {
+                        entry.height = Some(height);
+                    }
+                }
+            }
+            self.txs.insert(tx.txid(), (tx.clone(), Some(height)));
+        }
+        let block_hash = block.block_hash();
+        self.block_heights.insert(block_hash, height);
+        self.blocks.push(block);
+        block_hash
+    }
+}
+
+/// In-memory regtest chain implementing [`BitcoinBackend`].
+///
+/// Transactions are checked for missing or double-spent inputs and for outputs exceeding inputs,
+/// but scripts and signatures are not verified. Blocks are mined on demand with
+/// [`Self::mine_blocks()`] and include the whole mempool. Cloning is cheap; clones share the chain.
+#[derive(Debug, Clone)]
+pub struct MockBitcoinBackend {
+    chain: Arc<Mutex<MockChain>>,
+}
+
+impl Default for MockBitcoinBackend {
+    fn default() -> Self {
+        Self::new(Network::Regtest)
+    }
+}
+
+impl MockBitcoinBackend {
+    pub fn new(network: Network) -> Self {
+        Self {
+            chain: Arc::new(Mutex::new(MockChain::new(network))),
+        }
+    }
+
+    /// Mines `count` blocks paying the block reward to `reward_address`.
+    pub fn mine_blocks(&self, count: usize, reward_address: &Address) -> Vec<BlockHash> {
+        let mut chain = self.chain.lock().unwrap();
+        (0..count)
+            .map(|_| chain.mine_block(reward_address.script_pubkey()))
+            .collect()
+    }
+
+    /// Adds a transaction paying `amount` to `address` to the mempool, as if it was sent by
+    /// an external wallet.
+    pub fn fund(&self, address: &Address, amount: Amount) -> Txid {
+        let mut chain = self.chain.lock().unwrap();
+        chain.faucet_nonce = chain.faucet_nonce.wrapping_add(1);
+        let nonce = chain.faucet_nonce.to_le_bytes();
+        // The faucet input spends an output no one knows about, so the transaction bypasses
+        // `accept_to_mempool()`.
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(Txid::hash(&nonce), 0),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![
+                TxOut {
+                    value: amount,
+                    script_pubkey: address.script_pubkey(),
+                },
+                TxOut {
+                    value: Amount::ZERO,
+                    script_pubkey: Builder::new()
+                        .push_opcode(OP_RETURN)
+                        .push_slice(nonce)
+                        .into_script(),
+                },
+            ],
+        };
+        let txid = tx.txid();
+        chain.add_to_mempool(tx, Amount::ZERO);
+        txid
+    }
+
+    /// Disconnects the last `count` blocks, like `invalidateblock` does in Bitcoin Core, and
+    /// returns their hashes. Mining afterwards builds a competing chain from the new tip.
+    /// The genesis block is never disconnected.
+    pub fn invalidate_blocks(&self, count: usize) -> Vec<BlockHash> {
+        self.chain.lock().unwrap().invalidate_blocks(count)
+    }
+
+    /// Drops a transaction from the mempool, e.g. to simulate it being replaced. Returns `false`
+    /// if it is not in the mempool.
+    pub fn remove_from_mempool(&self, txid: Txid) -> bool {
+        self.chain.lock().unwrap().remove_from_mempool(txid)
+    }
+
+    /// Makes the next [`BitcoinBackend::post_tx()`] call fail with a gateway timeout after the
+    /// transaction is accepted, like a proxy giving up on a slow node.
+    pub fn time_out_next_post_tx(&self) {
+        self.chain.lock().unwrap().time_out_post_tx = true;
+    }
+
+    pub fn mempool(&self) -> Vec<Txid> {
+        let chain = self.chain.lock().unwrap();
+        chain.mempool.iter().map(|(tx, _)| tx.txid()).collect()
+    }
+}
+
+#[async_trait]
+impl BitcoinBackend for MockBitcoinBackend {
+    async fn post_tx(&self, tx: String) -> Result<Txid> {
+        let tx: Transaction = hex::decode(&tx)
+            .ok()
+            .and_then(|bytes| deserialize(&bytes).ok())
+            .ok_or_else(|| rpc_error(RPC_DESERIALIZATION_ERROR, "TX decode failed"))?;
+        let txid = tx.txid();
+        let mut chain = self.chain.lock().unwrap();
+        // Like `sendrawtransaction`, which relays transactions already in the mempool again.
+        if matches!(chain.txs.get(&txid), Some((_, None))) {
+            return Ok(txid);
+        }
+        // Like `BitcoinRpcClient::post_tx`.
+        let result = match chain.accept_to_mempool(tx) {
+            Err(BitcoinRpcError::Rpc {
+                code: RPC_VERIFY_ALREADY_IN_CHAIN,
+                ..
+            }) => Ok(txid),
+            result => result,
+        };
+        if result.is_ok() && std::mem::take(&mut chain.time_out_post_tx) {
+            return Err(BitcoinRpcError::Http {
+                status: StatusCode::GATEWAY_TIMEOUT,
+                body: "Gateway Timeout".to_string(),
+            });
+        }
+        result
+    }
+
+    async fn get_tx(&self, tx_id: Txid) -> Result<Transaction> {
+        Ok(self.get_tx_info(tx_id).await?.tx)
+    }
+
+    async fn get_tx_info(&self, tx_id: Txid) -> Result<TxInfo> {
+        let chain = self.chain.lock().unwrap();
+        let (tx, height) = chain.txs.get(&tx_id).ok_or_else(|| {
+            rpc_error(
+                RPC_INVALID_ADDRESS_OR_KEY,
+                "No such mempool or blockchain transaction",
+            )
+        })?;
+        Ok(TxInfo {
+            tx: tx.clone(),
+            block_hash: height.map(|height| chain.blocks[height as usize].block_hash()),
+            confirmations: chain.confirmations(*height),
+        })
+    }
+
+    async fn get_block_count(&self) -> Result<u64> {
+        Ok(self.chain.lock().unwrap().tip_height())
+    }
+
+    async fn get_block_hash(&self, height: u64) -> Result<BlockHash> {
+        let chain = self.chain.lock().unwrap();
+        chain
+            .blocks
+            .get(height as usize)
+            .map(Block::block_hash)
+            .ok_or_else(|| rpc_error(RPC_INVALID_PARAMETER, "Block height out of range"))
+    }
+
+    async fn get_block(&self, block_hash: &BlockHash) -> Result<Block> {
+        let chain = self.chain.lock().unwrap();
+        let height = chain
+            .block_heights
+            .get(block_hash)
+            .ok_or_else(|| rpc_error(RPC_INVALID_ADDRESS_OR_KEY, "Block not found"))?;
+        Ok(chain.blocks[*height as usize].clone())
+    }
+
+    async fn get_unspent(
+        &self,
+        address: &Address,
+        min_confirmation: Option<usize>,
+    ) -> Result<Vec<Utxo>> {
+        let chain = self.chain.lock().unwrap();
+        let script_pubkey = address.script_pubkey();
+        let min_confirmation = min_confirmation.unwrap_or(1) as u32;
+        let mut utxos: Vec<_> = chain
+            .utxos
+            .iter()
+            .filter(|(_, entry)| entry.output.script_pubkey == script_pubkey)
+            .map(|(outpoint, entry)| Utxo {
+                outpoint: *outpoint,
+                amount: entry.output.value,
+                script_pubkey: entry.output.script_pubkey.clone(),
+                confirmations: chain.confirmations(entry.height),
+            })
+            .filter(|utxo| utxo.confirmations >= min_confirmation)
+            .collect();
+        utxos.sort_by_key(|utxo| (std::cmp::Reverse(utxo.confirmations), utxo.outpoint));
+        Ok(utxos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::consensus::encode::serialize_hex;
+
+    use super::*;
+
+    fn address() -> Address {
+        Address::from_str("bcrt1phcnl4zcl2fu047pv4wx6y058v8u0n02at6lthvm7pcf2wrvjm5tqatn90k")
+            .unwrap()
+            .require_network(Network::Regtest)
+            .unwrap()
+    }
+
+    fn other_address() -> Address {
+        Address::p2wsh(&ScriptBuf::from(vec![0x51]), Network::Regtest)
+    }
+
+    fn spend(outpoint: OutPoint, to: &Address, amount: Amount) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: amount,
+                script_pubkey: to.script_pubkey(),
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn mining_confirms_transactions() {
+        let backend = MockBitcoinBackend::default();
+        let txid = backend.fund(&address(), Amount::from_sat(100_000));
+        assert_eq!(backend.mempool(), [txid]);
+        assert!(backend
+            .get_unspent(&address(), None)
+            .await
+            .unwrap()
+            .is_empty());
+        let unconfirmed = backend.get_unspent(&address(), Some(0)).await.unwrap();
+        assert_eq!(unconfirmed.len(), 1);
+        assert_eq!(unconfirmed[0].confirmations, 0);
+
+        let block_hashes = backend.mine_blocks(3, &other_address());
+        assert_eq!(backend.get_block_count().await.unwrap(), 3);
+        assert_eq!(backend.get_block_hash(1).await.unwrap(), block_hashes[0]);
+        assert!(backend.mempool().is_empty());
+
+        let info = backend.get_tx_info(txid).await.unwrap();
+        assert_eq!(info.block_hash, Some(block_hashes[0]));
+        assert_eq!(info.confirmations, 3);
+        let utxos = backend.get_unspent(&address(), None).await.unwrap();
+        assert_eq!(utxos[0].amount, Amount::from_sat(100_000));
+        assert_eq!(utxos[0].confirmations, 3);
+
+        let block = backend.get_block(&block_hashes[2]).await.unwrap();
+        assert!(block.check_merkle_root());
+        assert_eq!(block.header.prev_blockhash, block_hashes[1]);
+        // Like Bitcoin Core, heights up to 16 are pushed as `OP_N`, which `bip34_block_height()`
+        // doesn't parse.
+        let block_hash = backend.mine_blocks(14, &other_address())[13];
+        let block = backend.get_block(&block_hash).await.unwrap();
+        assert_eq!(block.bip34_block_height().unwrap(), 17);
+    }
+
+    #[tokio::test]
+    async fn spending_outputs() {
+        let backend = MockBitcoinBackend::default();
+        let funding_txid = backend.fund(&address(), Amount::from_sat(100_000));
+        let outpoint = OutPoint::new(funding_txid, 0);
+
+        let overspend = spend(outpoint, &other_address(), Amount::from_sat(100_001));
+        let err = backend
+            .post_tx(serialize_hex(&overspend))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BitcoinRpcError::Rpc {
+                code: RPC_VERIFY_REJECTED,
+                ..
+            }
+        ));
+
+        // Spending unconfirmed outputs is fine.
+        let tx = spend(outpoint, &other_address(), Amount::from_sat(90_000));
+        backend.time_out_next_post_tx();
+        let err = backend.post_tx(serialize_hex(&tx)).await.unwrap_err();
+        assert!(err.is_transient());
+        // The transaction was accepted anyway; reposting it returns its txid.
+        let txid = backend.post_tx(serialize_hex(&tx)).await.unwrap();
+        assert_eq!(backend.mempool(), [funding_txid, txid]);
+        assert_eq!(backend.get_tx(txid).await.unwrap(), tx);
+
+        let double_spend = spend(outpoint, &address(), Amount::from_sat(90_000));
+        let err = backend
+            .post_tx(serialize_hex(&double_spend))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BitcoinRpcError::Rpc {
+                code: RPC_VERIFY_ERROR,
+                ..
+            }
+        ));
+
+        let block_hash = backend.mine_blocks(1, &address())[0];
+        let block = backend.get_block(&block_hash).await.unwrap();
+        assert_eq!(block.txdata.len(), 3);
+        // The coinbase collects the fee of the spending transaction.
+        assert_eq!(
+            block.txdata[0].output[0].value,
+            BLOCK_SUBSIDY + Amount::from_sat(10_000)
+        );
+        // Reposting a mined transaction is a no-op.
+        assert_eq!(backend.post_tx(serialize_hex(&tx)).await.unwrap(), txid);
+
+        let utxos = backend.get_unspent(&other_address(), None).await.unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].outpoint, OutPoint::new(txid, 0));
+        let utxos = backend.get_unspent(&address(), None).await.unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].outpoint, OutPoint::new(block.txdata[0].txid(), 0));
+    }
+
+    #[tokio::test]
+    async fn invalidating_blocks() {
+        let backend = MockBitcoinBackend::default();
+        let kept_txid = backend.fund(&address(), Amount::from_sat(100_000));
+        backend.mine_blocks(1, &other_address());
+        let reverted_txid = backend.fund(&address(), Amount::from_sat(200_000));
+        let invalidated = backend.mine_blocks(1, &other_address());
+
+        assert_eq!(backend.invalidate_blocks(1), invalidated);
+        assert_eq!(backend.get_block_count().await.unwrap(), 1);
+        assert_eq!(backend.mempool(), [reverted_txid]);
+        let info = backend.get_tx_info(reverted_txid).await.unwrap();
+        assert_eq!((info.block_hash, info.confirmations), (None, 0));
+        assert!(backend.get_block(&invalidated[0]).await.is_err());
+
+        assert!(backend.remove_from_mempool(reverted_txid));
+        let replacement = backend.mine_blocks(1, &other_address());
+        assert_ne!(replacement, invalidated);
+        let block = backend.get_block(&replacement[0]).await.unwrap();
+        assert_eq!(block.txdata.len(), 1);
+        assert!(backend.get_tx_info(reverted_txid).await.is_err());
+        let utxos = backend.get_unspent(&address(), None).await.unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].outpoint, OutPoint::new(kept_txid, 0));
+        assert_eq!(utxos[0].confirmations, 2);
+    }
+}
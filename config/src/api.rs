@@ -1,5 +1,11 @@
-use std::{net::SocketAddr, time::Duration};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    num::NonZeroU32,
+    time::Duration,
+};
 
+use bitcoin::Network;
 use serde::Deserialize;
 
 use crate::{load_config, BITVM_BRIDGE_PREFIX, BYTES_IN_MB};
@@ -28,8 +34,30 @@ pub struct Web3JsonRpcConfig {
     pub ws_port: u16,
     pub max_batch_request_size: Option<usize>,
     pub max_response_body_size_mb: Option<usize>,
+    /// Seconds between polls of the pubsub notifiers. They also wake up on database changes, so
+    /// polling only catches up on missed notifications.
     pub pubsub_polling_interval: Option<u64>,
     pub threads_per_server: u32,
+    /// Connections each server accepts at once, over all clients.
+    pub max_connections: Option<u32>,
+    /// Connections each server accepts at once from the same IP address.
+    pub max_connections_per_ip: Option<usize>,
+    /// Subscriptions a WebSocket connection can hold at once.
+    pub subscriptions_limit: Option<usize>,
+    /// Subscriptions a WebSocket connection can hold at once to the same topic, e.g.
+    /// `operatorTasks`.
+    pub filters_limit: Option<usize>,
+    /// Calls each client IP address can make per minute, each weighing as in `method_weights`.
+    /// Zero disables the limit.
+    pub requests_per_minute_per_ip: Option<u32>,
+    /// Calls each client IP address can make per minute to each method. Unlimited if unset or
+    /// zero.
+    pub requests_per_minute_per_method: Option<u32>,
+    /// Weights of expensive methods towards `requests_per_minute_per_ip`, keyed by the full
+    /// method name, e.g. `bridge_queryPeginEventsByRange`. Other methods weigh 1.
+    pub method_weights: Option<HashMap<String, u32>>,
+    /// Proxies whose `X-Forwarded-For` header is trusted to tell the client IP address.
+    pub trusted_proxies: Option<Vec<IpAddr>>,
 }
 
 impl Web3JsonRpcConfig {
@@ -55,6 +83,43 @@ impl Web3JsonRpcConfig {
     pub fn ws_server_threads(&self) -> usize {
         self.threads_per_server as usize
     }
+
+    pub fn max_connections(&self) -> u32 {
+        self.max_connections.unwrap_or(5_000)
+    }
+
+    pub fn max_connections_per_ip(&self) -> usize {
+        self.max_connections_per_ip.unwrap_or(100)
+    }
+
+    pub fn subscriptions_limit(&self) -> usize {
+        self.subscriptions_limit.unwrap_or(32)
+    }
+
+    pub fn filters_limit(&self) -> usize {
+        self.filters_limit.unwrap_or(4)
+    }
+
+    pub fn requests_per_minute_per_ip(&self) -> Option<NonZeroU32> {
+        NonZeroU32::new(self.requests_per_minute_per_ip.unwrap_or(1_200))
+    }
+
+    pub fn requests_per_minute_per_method(&self) -> Option<NonZeroU32> {
+        self.requests_per_minute_per_method
+            .and_then(NonZeroU32::new)
+    }
+
+    pub fn method_weights(&self) -> HashMap<String, NonZeroU32> {
+        self.method_weights
+            .iter()
+            .flatten()
+            .filter_map(|(method, &weight)| Some((method.clone(), NonZeroU32::new(weight)?)))
+            .collect()
+    }
+
+    pub fn trusted_proxies(&self) -> Vec<IpAddr> {
+        self.trusted_proxies.clone().unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Deserialize, Clone, PartialEq)]
@@ -81,7 +146,23 @@ pub struct BitcoinRpcConfig {
     pub http_url: String,
     pub rpc_user: String,
     pub rpc_password: String,
+    /// Network the node runs on, e.g. `regtest` or `signet`.
+    pub network: Option<Network>,
     pub confirms_threshold: u32,
+    /// Timeout of a single attempt of an RPC call.
+    pub request_timeout_sec: Option<u64>,
+    /// Retries of RPC calls failing with a transport error.
+    pub max_retries: Option<u32>,
+    pub health_check_interval_sec: Option<u64>,
+    /// The node is reported unhealthy once its tip is older than this. Unset on regtest,
+    /// where blocks are only mined on demand.
+    pub max_block_age_sec: Option<u64>,
+    /// Wallet of the node used to look up unspent outputs.
+    pub wallet: Option<String>,
+    /// How often the block scanner polls the node for new blocks.
+    pub scan_interval_sec: Option<u64>,
+    /// Height the block scanner starts from when it has no cursor yet. Defaults to the tip.
+    pub start_block_height: Option<u64>,
 }
 
 impl BitcoinRpcConfig {
@@ -91,10 +172,40 @@ impl BitcoinRpcConfig {
             format!("{BITVM_BRIDGE_PREFIX}_BITCOIN").as_str(),
         )
     }
+
+    pub fn network(&self) -> Network {
+        self.network.unwrap_or(Network::Regtest)
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout_sec.unwrap_or(30))
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries.unwrap_or(3)
+    }
+
+    pub fn health_check_interval(&self) -> Duration {
+        Duration::from_secs(self.health_check_interval_sec.unwrap_or(30))
+    }
+
+    pub fn max_block_age(&self) -> Option<Duration> {
+        self.max_block_age_sec.map(Duration::from_secs)
+    }
+
+    pub fn wallet(&self) -> &str {
+        self.wallet.as_deref().unwrap_or("benefactor")
+    }
+
+    pub fn scan_interval(&self) -> Duration {
+        Duration::from_secs(self.scan_interval_sec.unwrap_or(10))
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use bitcoin::Network;
+
     use super::{ApiConfig, BitcoinRpcConfig, HealthCheckConfig, Web3JsonRpcConfig};
     use crate::utils::tests::EnvMutex;
 
@@ -110,13 +221,29 @@ mod tests {
                 max_response_body_size_mb: Some(10),
                 pubsub_polling_interval: Some(10),
                 threads_per_server: 128,
+                max_connections: None,
+                max_connections_per_ip: None,
+                subscriptions_limit: None,
+                filters_limit: None,
+                requests_per_minute_per_ip: None,
+                requests_per_minute_per_method: None,
+                method_weights: None,
+                trusted_proxies: None,
             },
             healthcheck: HealthCheckConfig { port: 33001 },
             bitcoin_rpc: BitcoinRpcConfig {
                 http_url: "http://127.0.0.1:18443".to_string(),
                 rpc_user: "test".to_string(),
                 rpc_password: "1234".to_string(),
+                network: Some(Network::Regtest),
                 confirms_threshold: 1,
+                request_timeout_sec: Some(30),
+                max_retries: Some(3),
+                health_check_interval_sec: Some(30),
+                max_block_age_sec: None,
+                wallet: None,
+                scan_interval_sec: Some(10),
+                start_block_height: None,
             },
         }
     }
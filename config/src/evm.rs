@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::{load_config, BITVM_BRIDGE_PREFIX};
+
+/// Endpoints of the target EVM chains. A chain is only indexed if it also has an active row in
+/// `bridges`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct EvmWatcherConfig {
+    #[serde(default)]
+    pub chains: Vec<EvmChainConfig>,
+}
+
+impl EvmWatcherConfig {
+    pub fn load_config() -> Result<EvmWatcherConfig, config::ConfigError> {
+        load_config(
+            "configuration/evm_watcher",
+            format!("{BITVM_BRIDGE_PREFIX}_EVM_WATCHER").as_str(),
+        )
+    }
+
+    pub fn chain(&self, chain_id: i32) -> Option<&EvmChainConfig> {
+        self.chains.iter().find(|chain| chain.chain_id == chain_id)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct EvmChainConfig {
+    /// Chain of the `bridges` row whose contracts are indexed.
+    pub chain_id: i32,
+    pub rpc_url: String,
+    /// Logs are indexed once their block is this deep below the chain head.
+    pub confirmations: Option<u64>,
+    pub poll_interval_sec: Option<u64>,
+    /// Upper bound on the block range of a single `eth_getLogs` request.
+    pub max_block_range: Option<u64>,
+    /// Block the indexer starts from when it has no cursor yet. Defaults to the confirmed head.
+    pub start_block: Option<u64>,
+}
+
+impl EvmChainConfig {
+    pub fn confirmations(&self) -> u64 {
+        self.confirmations.unwrap_or(12)
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval_sec.unwrap_or(5))
+    }
+
+    pub fn max_block_range(&self) -> u64 {
+        self.max_block_range.unwrap_or(1_000).max(1)
+    }
+}
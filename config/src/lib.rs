@@ -5,7 +5,10 @@ use serde::de::DeserializeOwned;
 
 pub mod api;
 pub mod constants;
+pub mod database;
 pub mod environment;
+pub mod evm;
+pub mod pegout;
 pub mod utils;
 
 const BYTES_IN_MB: usize = 1_024 * 1_024;
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::{load_config, BITVM_BRIDGE_PREFIX};
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct PegoutConfig {
+    /// How often the driver checks peg-outs for progress.
+    pub poll_interval_sec: Option<u64>,
+    /// Bitcoin blocks an operator has to broadcast the payout in, counted from its assignment.
+    pub payout_timeout_blocks: Option<u64>,
+    /// Bitcoin blocks the reimbursement can be challenged for after the kickoff confirms.
+    pub challenge_period_blocks: Option<u64>,
+}
+
+impl PegoutConfig {
+    pub fn load_config() -> Result<PegoutConfig, config::ConfigError> {
+        load_config(
+            "configuration/pegout",
+            format!("{BITVM_BRIDGE_PREFIX}_PEGOUT").as_str(),
+        )
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval_sec.unwrap_or(10))
+    }
+
+    pub fn payout_timeout_blocks(&self) -> u64 {
+        self.payout_timeout_blocks.unwrap_or(6)
+    }
+
+    pub fn challenge_period_blocks(&self) -> u64 {
+        self.challenge_period_blocks.unwrap_or(144)
+    }
+}
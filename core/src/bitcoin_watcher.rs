@@ -0,0 +1,651 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use anyhow::Context;
+use bitcoin::{
+    consensus::encode::serialize_hex, Address, Block, BlockHash, Network, ScriptBuf, Transaction,
+};
+use bitcoin_client::BitcoinBackend;
+use dal::{connection::ConnectionPool, StorageProcessor};
+use tokio::sync::{broadcast, watch};
+use types::{
+    bitcoin_tx::{
+        NewBitcoinTransaction, PeginDeposit, BITCOIN_TX_CONFIRMED, BITCOIN_TX_PENDING,
+        PEGIN_DEPOSIT_TX_TYPE,
+    },
+    pubsub::{BitcoinReorg, PubSubResult},
+};
+
+/// Name of the `sync_cursors` row holding the last scanned block.
+const CURSOR_NAME: &str = "bitcoin";
+
+/// Follows the Bitcoin chain tip and indexes transactions paying the `assertion_taproot_address`
+/// of an active bridge into `bitcoin_transactions`.
+///
+/// Each block is stored in a single database transaction together with its hash and the cursor,
+/// so the watcher resumes from the first unprocessed block after a restart. Deposits are
+/// `pending` until they have `confirms_threshold` confirmations; peg-ins referencing a confirmed
+/// deposit are then moved to `deposited`.
+///
+/// When the node's chain no longer contains the processed blocks, everything indexed above the
+/// last common block is rolled back and a [`BitcoinReorg`] is sent to pubsub subscribers.
+/// Peg-ins the committee already accepted keep their status but are flagged until their deposit
+/// confirms again.
+#[derive(Debug)]
+pub struct BitcoinWatcher {
+    backend: Arc<dyn BitcoinBackend>,
+    pool: ConnectionPool,
+    network: Network,
+    confirms_threshold: u32,
+    poll_interval: Duration,
+    /// Height to start from without a cursor; the current tip if unset.
+    start_height: Option<u64>,
+    reorg_sender: Option<broadcast::Sender<Vec<PubSubResult>>>,
+}
+
+impl BitcoinWatcher {
+    pub fn new(
+        backend: Arc<dyn BitcoinBackend>,
+        pool: ConnectionPool,
+        network: Network,
+        confirms_threshold: u32,
+        poll_interval: Duration,
+        start_height: Option<u64>,
+    ) -> Self {
+        Self {
+            backend,
+            pool,
+            network,
+            confirms_threshold,
+            poll_interval,
+            start_height,
+            reorg_sender: None,
+        }
+    }
+
+    pub fn with_reorg_notifications(
+        mut self,
+        sender: broadcast::Sender<Vec<PubSubResult>>,
+    ) -> Self {
+        self.reorg_sender = Some(sender);
+        self
+    }
+
+    pub async fn run(self, mut stop_receiver: watch::Receiver<bool>) -> anyhow::Result<()> {
+        let mut timer = tokio::time::interval(self.poll_interval);
+        loop {
+            tokio::select! {
+                _ = timer.tick() => {}
+                _ = stop_receiver.changed() => break,
+            }
+            if *stop_receiver.borrow() {
+                break;
+            }
+            if let Err(err) = self.sync().await {
+                logs::warn!("bitcoin watcher failed to sync: {err:#}");
+            }
+        }
+        logs::info!("Stop signal received, bitcoin watcher is shutting down");
+        Ok(())
+    }
+
+    /// Processes all blocks up to the current tip and refreshes confirmations of pending deposits.
+    pub async fn sync(&self) -> anyhow::Result<()> {
+        let tip = self.backend.get_block_count().await?;
+        let mut storage = self.pool.access_storage_tagged("bitcoin_watcher").await?;
+        let cursor = storage.sync_cursors_dal().get_cursor(CURSOR_NAME).await?;
+        let mut height = match cursor {
+            Some(cursor) => {
+                let cursor_height = cursor.block_number as u64;
+                // The node may have switched to a chain that is not longer than the processed one,
+                // so no new block would reveal the reorg.
+                let reorged = cursor_height > tip
+                    || self
+                        .backend
+                        .get_block_hash(cursor_height)
+                        .await?
+                        .to_string()
+                        != cursor.block_hash;
+                if reorged {
+                    self.handle_reorg(&mut storage, cursor_height.min(tip), cursor_height)
+                        .await?
+                        + 1
+                } else {
+                    cursor_height + 1
+                }
+            }
+            None => self.start_height.unwrap_or(tip),
+        };
+
+        let watched = self.watched_scripts(&mut storage).await?;
+        while height <= tip {
+            let block_hash = self.backend.get_block_hash(height).await?;
+            let block = self.backend.get_block(&block_hash).await?;
+            let parent_hash = match height.checked_sub(1) {
+                Some(parent) => {
+                    storage
+                        .bitcoin_blocks_dal()
+                        .get_block_hash(parent as i64)
+                        .await?
+                }
+                None => None,
+            };
+            if parent_hash.is_some_and(|hash| hash != block.header.prev_blockhash.to_string()) {
+                height = self
+                    .handle_reorg(&mut storage, height - 1, height - 1)
+                    .await?
+                    + 1;
+                continue;
+            }
+            self.process_block(&mut storage, &watched, height, block_hash, &block, tip)
+                .await
+                .with_context(|| format!("failed to process block {height}"))?;
+            height += 1;
+        }
+        self.update_confirmations(&mut storage, tip).await
+    }
+
+    /// Returns the script of each active bridge with its chain id.
+    async fn watched_scripts(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+    ) -> anyhow::Result<Vec<(ScriptBuf, i32)>> {
+        let bridges = storage.bridges_dal().get_active_bridges().await?;
+        let mut watched = Vec::with_capacity(bridges.len());
+        for bridge in bridges {
+            let address = Address::from_str(&bridge.assertion_taproot_address)
+                .map_err(anyhow::Error::from)
+                .and_then(|address| Ok(address.require_network(self.network)?));
+            match address {
+                Ok(address) => watched.push((address.script_pubkey(), bridge.chain_id)),
+                Err(err) => logs::warn!(
+                    "skipping bridge of chain {} with invalid address {}: {err}",
+                    bridge.chain_id,
+                    bridge.assertion_taproot_address
+                ),
+            }
+        }
+        Ok(watched)
+    }
+
+    async fn process_block(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        watched: &[(ScriptBuf, i32)],
+        height: u64,
+        block_hash: BlockHash,
+        block: &Block,
+        tip: u64,
+    ) -> anyhow::Result<()> {
+        let confirmations = (tip - height + 1) as i32;
+
+        let mut transaction = storage.start_transaction().await?;
+        for tx in &block.txdata {
+            let deposits = Self::find_deposits(tx, watched);
+            if deposits.is_empty() {
+                continue;
+            }
+            logs::info!("found peg-in deposit {} in block {height}", tx.txid());
+            let new_tx = NewBitcoinTransaction {
+                tx_hash: tx.txid().to_string(),
+                tx_type: PEGIN_DEPOSIT_TX_TYPE.to_string(),
+                status: self.status(confirmations).to_string(),
+                data: serialize_hex(tx),
+                extra_data: Some(serde_json::to_string(&deposits)?),
+                block_height: Some(height as i64),
+                block_hash: Some(block_hash.to_string()),
+                confirmations,
+            };
+            transaction
+                .bitcoin_transactions_dal()
+                .upsert_bitcoin_transaction(&new_tx)
+                .await?;
+        }
+        transaction
+            .bitcoin_blocks_dal()
+            .insert_block(
+                height as i64,
+                &block_hash.to_string(),
+                &block.header.prev_blockhash.to_string(),
+            )
+            .await?;
+        transaction
+            .sync_cursors_dal()
+            .set_cursor(CURSOR_NAME, height as i64, &block_hash.to_string())
+            .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Rolls back everything indexed above the last block at or below `from_height` that is still
+    /// part of the node's chain. Returns the height of that block.
+    async fn handle_reorg(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        from_height: u64,
+        processed_height: u64,
+    ) -> anyhow::Result<u64> {
+        let fork_height = self.find_fork_point(storage, from_height).await?;
+        let fork_hash = self.backend.get_block_hash(fork_height).await?.to_string();
+
+        let mut transaction = storage.start_transaction().await?;
+        let reverted_tx_hashes = transaction
+            .bitcoin_transactions_dal()
+            .revert_blocks_above(fork_height as i64)
+            .await?;
+        let reverted_pegin_ids = transaction
+            .pegins_dal()
+            .revert_deposits(&reverted_tx_hashes)
+            .await?;
+        let flagged_pegin_ids = transaction
+            .pegins_dal()
+            .flag_reorged_deposits(&reverted_tx_hashes)
+            .await?;
+        transaction
+            .bitcoin_blocks_dal()
+            .delete_blocks_above(fork_height as i64)
+            .await?;
+        transaction
+            .sync_cursors_dal()
+            .set_cursor(CURSOR_NAME, fork_height as i64, &fork_hash)
+            .await?;
+        transaction.commit().await?;
+
+        let reorg = BitcoinReorg {
+            fork_height,
+            fork_hash,
+            depth: processed_height - fork_height,
+            reverted_tx_hashes,
+            reverted_pegin_ids,
+            flagged_pegin_ids,
+        };
+        logs::warn!("bitcoin chain reorganized: {reorg:?}");
+        if let Some(sender) = &self.reorg_sender {
+            // Fails only if there are no subscribers.
+            sender.send(vec![PubSubResult::BitcoinReorg(reorg)]).ok();
+        }
+        Ok(fork_height)
+    }
+
+    async fn find_fork_point(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        from_height: u64,
+    ) -> anyhow::Result<u64> {
+        let mut height = from_height;
+        loop {
+            let stored_hash = storage
+                .bitcoin_blocks_dal()
+                .get_block_hash(height as i64)
+                .await?;
+            // Blocks below the first processed one are never rolled back.
+            let Some(stored_hash) = stored_hash else {
+                return Ok(height);
+            };
+            if self.backend.get_block_hash(height).await?.to_string() == stored_hash {
+                return Ok(height);
+            }
+            height = height
+                .checked_sub(1)
+                .context("processed blocks don't share a genesis block with the node")?;
+        }
+    }
+
+    fn find_deposits(tx: &Transaction, watched: &[(ScriptBuf, i32)]) -> Vec<PeginDeposit> {
+        let mut deposits = vec![];
+        for (vout, output) in tx.output.iter().enumerate() {
+            for (script, chain_id) in watched {
+                if output.script_pubkey == *script {
+                    deposits.push(PeginDeposit {
+                        chain_id: *chain_id,
+                        vout: vout as u32,
+                        amount: output.value.to_sat() as i64,
+                    });
+                }
+            }
+        }
+        deposits
+    }
+
+    async fn update_confirmations(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        tip: u64,
+    ) -> anyhow::Result<()> {
+        let pending = storage
+            .bitcoin_transactions_dal()
+            .get_bitcoin_transactions_by_status(PEGIN_DEPOSIT_TX_TYPE, BITCOIN_TX_PENDING)
+            .await?;
+        for tx in pending {
+            let Some(height) = tx.block_height else {
+                continue;
+            };
+            let confirmations = (tip as i64 - height + 1).max(0) as i32;
+            if confirmations == tx.confirmations {
+                continue;
+            }
+            storage
+                .bitcoin_transactions_dal()
+                .update_confirmations(&tx.tx_hash, confirmations, self.status(confirmations))
+                .await?;
+        }
+
+        let deposited = storage.pegins_dal().mark_confirmed_deposits().await?;
+        if !deposited.is_empty() {
+            logs::info!("peg-ins {deposited:?} are deposited");
+        }
+        let reconfirmed = storage.pegins_dal().clear_reconfirmed_deposits().await?;
+        if !reconfirmed.is_empty() {
+            logs::info!("reorged deposits of peg-ins {reconfirmed:?} are confirmed again");
+        }
+        Ok(())
+    }
+
+    fn status(&self, confirmations: i32) -> &'static str {
+        if confirmations >= self.confirms_threshold as i32 {
+            BITCOIN_TX_CONFIRMED
+        } else {
+            BITCOIN_TX_PENDING
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        secp256k1::rand::{self, Rng},
+        Amount,
+    };
+    use bitcoin_client::mock::MockBitcoinBackend;
+    use types::{
+        bridge::NewBridge,
+        pegin::{NewPegin, PeginStatus},
+    };
+
+    use super::*;
+
+    const BRIDGE_ADDRESS: &str = "bcrt1phcnl4zcl2fu047pv4wx6y058v8u0n02at6lthvm7pcf2wrvjm5tqatn90k";
+    const MINER_ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    fn address(address: &str) -> Address {
+        Address::from_str(address)
+            .unwrap()
+            .require_network(Network::Regtest)
+            .unwrap()
+    }
+
+    /// Inserts a bridge on a random chain and a peg-in to it. Transactions of the mock chain are
+    /// unique as well, but the cursor and the block hashes of the watcher are not, so these tests
+    /// wait for each other once they index blocks.
+    async fn setup(pool: &ConnectionPool) -> i32 {
+        let chain_id = rand::thread_rng().gen_range(1..i32::MAX);
+        let mut storage = pool.access_storage().await.unwrap();
+        storage
+            .bridges_dal()
+            .insert_bridge(&NewBridge {
+                chain_id,
+                chain_name: "test".to_string(),
+                operator_manager_address: "0x0000000000000000000000000000000000000000".to_string(),
+                assertion_taproot_address: BRIDGE_ADDRESS.to_string(),
+                status: "active".to_string(),
+                bridge_contract_address: None,
+            })
+            .await
+            .unwrap();
+        storage
+            .pegins_dal()
+            .insert_pegin(&NewPegin {
+                target_chain_id: chain_id,
+                public_key: "02".repeat(33),
+                sender_address: MINER_ADDRESS.to_string(),
+                status: PeginStatus::Created,
+                receive_address: "0x0000000000000000000000000000000000000001".to_string(),
+                amount: 100_000,
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn deposits_are_indexed_until_confirmed() {
+        let pool = ConnectionPool::test_pool().await;
+        let pegin_id = setup(&pool).await;
+        let backend = MockBitcoinBackend::default();
+        let miner = address(MINER_ADDRESS);
+        backend.mine_blocks(1, &miner);
+
+        let watcher = BitcoinWatcher::new(
+            Arc::new(backend.clone()),
+            pool.clone(),
+            Network::Regtest,
+            2,
+            Duration::from_secs(1),
+            Some(1),
+        );
+        let txid = backend.fund(&address(BRIDGE_ADDRESS), Amount::from_sat(100_000));
+        backend.mine_blocks(1, &miner);
+        watcher.sync().await.unwrap();
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let tx = storage
+            .bitcoin_transactions_dal()
+            .get_bitcoin_transaction(&txid.to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((tx.block_height, tx.confirmations), (Some(2), 1));
+        assert_eq!(tx.status, BITCOIN_TX_PENDING);
+        storage
+            .pegins_dal()
+            .set_pegin_tx(pegin_id, &txid.to_string(), &tx.data)
+            .await
+            .unwrap();
+        drop(storage);
+
+        backend.mine_blocks(1, &miner);
+        watcher.sync().await.unwrap();
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let tx = storage
+            .bitcoin_transactions_dal()
+            .get_bitcoin_transaction(&txid.to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (tx.confirmations, tx.status.as_str()),
+            (2, BITCOIN_TX_CONFIRMED)
+        );
+        let pegin = storage
+            .pegins_dal()
+            .get_pegin_by_id(pegin_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pegin.status, PeginStatus::Deposited);
+        let cursor = storage
+            .sync_cursors_dal()
+            .get_cursor(CURSOR_NAME)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cursor.block_number, 3);
+    }
+
+    #[tokio::test]
+    async fn reorgs_roll_back_deposits() {
+        let pool = ConnectionPool::test_pool().await;
+        let pegin_id = setup(&pool).await;
+        let backend = MockBitcoinBackend::default();
+        let miner = address(MINER_ADDRESS);
+        backend.mine_blocks(1, &miner);
+        let txid = backend.fund(&address(BRIDGE_ADDRESS), Amount::from_sat(100_000));
+        let mut storage = pool.access_storage().await.unwrap();
+        storage
+            .pegins_dal()
+            .set_pegin_tx(pegin_id, &txid.to_string(), "0200")
+            .await
+            .unwrap();
+        drop(storage);
+
+        let (reorg_sender, mut reorgs) = broadcast::channel(16);
+        let watcher = BitcoinWatcher::new(
+            Arc::new(backend.clone()),
+            pool.clone(),
+            Network::Regtest,
+            1,
+            Duration::from_secs(1),
+            Some(1),
+        )
+        .with_reorg_notifications(reorg_sender);
+        backend.mine_blocks(1, &miner);
+        watcher.sync().await.unwrap();
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let pegin = storage
+            .pegins_dal()
+            .get_pegin_by_id(pegin_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pegin.status, PeginStatus::Deposited);
+        drop(storage);
+
+        // The deposit is replaced on a longer competing chain.
+        let fork_hash = backend.get_block_hash(1).await.unwrap();
+        backend.invalidate_blocks(1);
+        assert!(backend.remove_from_mempool(txid));
+        let new_chain = backend.mine_blocks(2, &miner);
+        watcher.sync().await.unwrap();
+
+        let reorg = match reorgs.try_recv().unwrap().pop().unwrap() {
+            PubSubResult::BitcoinReorg(reorg) => reorg,
+            other => panic!("unexpected notification: {other:?}"),
+        };
+        assert_eq!(
+            reorg,
+            BitcoinReorg {
+                fork_height: 1,
+                fork_hash: fork_hash.to_string(),
+                depth: 1,
+                reverted_tx_hashes: vec![txid.to_string()],
+                reverted_pegin_ids: vec![pegin_id],
+                flagged_pegin_ids: vec![],
+            }
+        );
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let tx = storage
+            .bitcoin_transactions_dal()
+            .get_bitcoin_transaction(&txid.to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.status, BITCOIN_TX_PENDING);
+        assert_eq!((tx.block_height, tx.confirmations), (None, 0));
+        let pegin = storage
+            .pegins_dal()
+            .get_pegin_by_id(pegin_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pegin.status, PeginStatus::Created);
+        for (height, block_hash) in (2..).zip(&new_chain) {
+            let stored_hash = storage
+                .bitcoin_blocks_dal()
+                .get_block_hash(height)
+                .await
+                .unwrap();
+            assert_eq!(stored_hash, Some(block_hash.to_string()));
+        }
+        let cursor = storage
+            .sync_cursors_dal()
+            .get_cursor(CURSOR_NAME)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cursor.block_hash, new_chain[1].to_string());
+    }
+
+    #[tokio::test]
+    async fn reorgs_flag_accepted_pegins() {
+        let pool = ConnectionPool::test_pool().await;
+        let pegin_id = setup(&pool).await;
+        let backend = MockBitcoinBackend::default();
+        let miner = address(MINER_ADDRESS);
+        backend.mine_blocks(1, &miner);
+        let txid = backend.fund(&address(BRIDGE_ADDRESS), Amount::from_sat(100_000));
+        let mut storage = pool.access_storage().await.unwrap();
+        storage
+            .pegins_dal()
+            .set_pegin_tx(pegin_id, &txid.to_string(), "0200")
+            .await
+            .unwrap();
+        drop(storage);
+
+        let (reorg_sender, mut reorgs) = broadcast::channel(16);
+        let watcher = BitcoinWatcher::new(
+            Arc::new(backend.clone()),
+            pool.clone(),
+            Network::Regtest,
+            1,
+            Duration::from_secs(1),
+            Some(1),
+        )
+        .with_reorg_notifications(reorg_sender);
+        backend.mine_blocks(1, &miner);
+        watcher.sync().await.unwrap();
+
+        let mut storage = pool.access_storage().await.unwrap();
+        storage
+            .pegins_dal()
+            .update_pegin_status(pegin_id, PeginStatus::Deposited, PeginStatus::Confirmed)
+            .await
+            .unwrap();
+        drop(storage);
+
+        // The committee accepted the peg-in before its deposit went back to the mempool.
+        let fork_hash = backend.get_block_hash(1).await.unwrap();
+        backend.invalidate_blocks(1);
+        watcher.sync().await.unwrap();
+
+        let reorg = match reorgs.try_recv().unwrap().pop().unwrap() {
+            PubSubResult::BitcoinReorg(reorg) => reorg,
+            other => panic!("unexpected notification: {other:?}"),
+        };
+        assert_eq!(
+            reorg,
+            BitcoinReorg {
+                fork_height: 1,
+                fork_hash: fork_hash.to_string(),
+                depth: 1,
+                reverted_tx_hashes: vec![txid.to_string()],
+                reverted_pegin_ids: vec![],
+                flagged_pegin_ids: vec![pegin_id],
+            }
+        );
+        let mut storage = pool.access_storage().await.unwrap();
+        let pegin = storage
+            .pegins_dal()
+            .get_pegin_by_id(pegin_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pegin.status, PeginStatus::Confirmed);
+        assert!(pegin.deposit_reorged_at.is_some());
+        drop(storage);
+
+        // The deposit confirms again on the new chain.
+        backend.mine_blocks(1, &miner);
+        watcher.sync().await.unwrap();
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let pegin = storage
+            .pegins_dal()
+            .get_pegin_by_id(pegin_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pegin.status, PeginStatus::Confirmed);
+        assert_eq!(pegin.deposit_reorged_at, None);
+    }
+}
@@ -0,0 +1,2229 @@
+use std::{collections::HashMap, net::IpAddr, sync::Arc};
+
+use bitcoin::{
+    consensus::deserialize,
+    opcodes::all::{OP_CHECKSIG, OP_CHECKSIGVERIFY},
+    script::Builder,
+    Address, Network, ScriptBuf, Transaction, TxOut, Txid,
+};
+use bitcoin_client::{BitcoinBackend, BitcoinRpcError};
+use bridge_rpc::error::Web3Error;
+use config::pegout::PegoutConfig;
+use dal::{connection::ConnectionPool, error::DalError, StorageProcessor};
+use serde::Serialize;
+use types::{
+    bridge::CommitteeMember,
+    challenger::{
+        ChallengeStatus, QueryChallengeDataReponse, QueryChallengeDataRequest,
+        StartChallengeResponse,
+    },
+    error::{InvalidTransition, ValidationError},
+    evm_event::{BridgeEvent, EvmEvent},
+    operator::{Operator, OperatorFilter, OPERATOR_ACTIVE},
+    pagination::Pagination,
+    pegin::{NewPegin, PeginDetails, PeginEventDetails, PeginOperation, PeginStatus},
+    pegout::{
+        PegoutDetail, PegoutEventDetail, PegoutInfo, PegoutOperationRangeResponse,
+        PegoutOperationRangerRequest, PegoutStatus, PegoutTransition,
+    },
+    presigned_tx::{
+        CommitteeSignature, PresignedTransaction, PresignedTxType, PRESIGNED_TX_SIGNED,
+    },
+    pubsub::{
+        ChallengeCommitteeTaskResponse, OperatorPegoutTxRequest, PeginCommitteeTaskResponse,
+        PeginOperatorTaskResponse, PeginReceivedRequest, PegoutOperatorTaskResponse,
+        PegoutReceivedRequest, SignedRequest,
+    },
+    rpc::{
+        LPPeginRequest, LPPegoutRequest, PeginCreateRequest, PeginCreateResponse,
+        PeginEventRangeRequest, PeginRequest, PeginTakeTxMsgReponse, PeginTakeTxMsgRequest,
+        PegoutRequest, PegouttEventRangeRequest,
+    },
+    validation::{parse_bitcoin_address, parse_txid, parse_x_only_key},
+};
+
+use crate::{
+    chains::{Chain, ChainRegistry},
+    nonces::Nonces,
+};
+
+/// Widest EVM block range events can be listed for in one query.
+pub const MAX_EVENT_BLOCK_RANGE: i64 = 1_000;
+
+/// Requester of the history and event queries, which only read and tolerate stale data, so they
+/// can be routed to the read replica.
+pub const HISTORY_REQUESTER: &str = "bridge_history";
+/// `pegin_operations.status` of take transactions submitted by operators.
+const PEGIN_OPERATION_PENDING: &str = "pending";
+
+pub type BridgeResult<T> = Result<T, BridgeError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BridgeError {
+    #[error("chain {0} is not supported")]
+    UnsupportedChain(i32),
+    #[error("{resource} {id} not found")]
+    NotFound { resource: &'static str, id: String },
+    #[error("{0}")]
+    InvalidRequest(String),
+    /// The request doesn't fit the current state of a peg-in, peg-out or operator.
+    #[error("{0}")]
+    Conflict(String),
+    /// A signed request isn't signed by the key it names.
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error(transparent)]
+    InvalidTransition(#[from] InvalidTransition),
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+    #[error(transparent)]
+    Dal(#[from] DalError),
+    #[error(transparent)]
+    Bitcoin(#[from] BitcoinRpcError),
+}
+
+impl From<BridgeError> for Web3Error {
+    fn from(err: BridgeError) -> Self {
+        match err {
+            BridgeError::UnsupportedChain(chain_id) => Web3Error::UnsupportedChain(chain_id),
+            BridgeError::NotFound { resource, id } => Web3Error::NotFound { resource, id },
+            BridgeError::InvalidRequest(message) => Web3Error::InvalidParams(message),
+            BridgeError::Conflict(message) => Web3Error::Conflict(message),
+            BridgeError::Unauthorized(reason) => Web3Error::Unauthorized(reason),
+            BridgeError::InvalidTransition(err)
+            | BridgeError::Dal(DalError::InvalidTransition(err)) => {
+                Web3Error::InvalidTransition(err)
+            }
+            BridgeError::Validation(err) => Web3Error::InvalidParams(err.to_string()),
+            BridgeError::Dal(
+                DalError::Connect { .. } | DalError::Acquire { .. } | DalError::CircuitOpen { .. },
+            ) => {
+                logs::warn!("bridge request failed: {err}");
+                Web3Error::Unavailable {
+                    upstream: "database",
+                }
+            }
+            BridgeError::Bitcoin(err) if err.is_tx_rejection() => {
+                Web3Error::TransactionRejected(err.to_string())
+            }
+            BridgeError::Bitcoin(err) if err.is_transient() => {
+                logs::warn!("bridge request failed: {err}");
+                Web3Error::Unavailable {
+                    upstream: "bitcoin node",
+                }
+            }
+            BridgeError::Dal(_) | BridgeError::Bitcoin(_) => {
+                logs::warn!("bridge request failed: {err}");
+                Web3Error::InternalError
+            }
+        }
+    }
+}
+
+fn invalid(message: impl Into<String>) -> BridgeError {
+    BridgeError::InvalidRequest(message.into())
+}
+
+fn conflict(message: impl Into<String>) -> BridgeError {
+    BridgeError::Conflict(message.into())
+}
+
+fn not_found(resource: &'static str, id: impl ToString) -> BridgeError {
+    BridgeError::NotFound {
+        resource,
+        id: id.to_string(),
+    }
+}
+
+fn decode_tx(raw_hex: &str) -> BridgeResult<Transaction> {
+    hex::decode(raw_hex)
+        .ok()
+        .and_then(|bytes| deserialize(&bytes).ok())
+        .ok_or_else(|| invalid("invalid raw transaction"))
+}
+
+/// N-of-N script of `committee`, with keys in committee order.
+fn multi_sig_script(committee: &[CommitteeMember]) -> BridgeResult<ScriptBuf> {
+    let mut builder = Builder::new();
+    for (i, member) in committee.iter().enumerate() {
+        let key = parse_x_only_key(&member.public_key)?;
+        let opcode = if i + 1 == committee.len() {
+            OP_CHECKSIG
+        } else {
+            OP_CHECKSIGVERIFY
+        };
+        builder = builder.push_x_only_key(&key).push_opcode(opcode);
+    }
+    Ok(builder.into_script())
+}
+
+fn paid_to(tx: &Transaction, address: &Address) -> u64 {
+    let script_pubkey = address.script_pubkey();
+    tx.output
+        .iter()
+        .filter(|output| output.script_pubkey == script_pubkey)
+        .map(|output| output.value.to_sat())
+        .sum()
+}
+
+/// Serves the `bridge` RPC namespace: creates peg-ins, hands peg-outs to operators, and collects
+/// the transactions and signatures of operators, committee members and challengers.
+#[derive(Debug, Clone)]
+pub struct BridgeService {
+    pool: ConnectionPool,
+    chains: Arc<ChainRegistry>,
+    backend: Arc<dyn BitcoinBackend>,
+    network: Network,
+    config: PegoutConfig,
+    /// Nonces operators sign requests with, see [`SignedRequest`].
+    nonces: Arc<Nonces>,
+}
+
+impl BridgeService {
+    pub fn new(
+        pool: ConnectionPool,
+        chains: Arc<ChainRegistry>,
+        backend: Arc<dyn BitcoinBackend>,
+        network: Network,
+        config: PegoutConfig,
+    ) -> Self {
+        Self {
+            pool,
+            chains,
+            backend,
+            network,
+            config,
+            nonces: Arc::default(),
+        }
+    }
+
+    /// Issues a nonce to sign a request with, or `None` if `client` has too many unused ones.
+    pub fn issue_nonce(&self, client: IpAddr) -> Option<String> {
+        self.nonces.issue(client)
+    }
+
+    /// Checks that the holder of `public_key` signed the call of `method`, consuming the nonce.
+    fn authenticate<T: Serialize>(
+        &self,
+        method: &str,
+        request: &SignedRequest<T>,
+        public_key: &str,
+    ) -> BridgeResult<()> {
+        if !self.nonces.take(&request.nonce) {
+            return Err(BridgeError::Unauthorized(format!(
+                "nonce {} is unknown or expired",
+                request.nonce
+            )));
+        }
+        request
+            .verify(method, public_key)
+            .map_err(|err| BridgeError::Unauthorized(err.to_string()))
+    }
+
+    fn chain(&self, chain_id: i32) -> BridgeResult<&Chain> {
+        self.chains
+            .get(chain_id)
+            .ok_or(BridgeError::UnsupportedChain(chain_id))
+    }
+
+    async fn storage(&self) -> BridgeResult<StorageProcessor<'_>> {
+        Ok(self.pool.access_storage_tagged("bridge").await?)
+    }
+
+    async fn history_storage(&self) -> BridgeResult<StorageProcessor<'_>> {
+        Ok(self.pool.access_storage_tagged(HISTORY_REQUESTER).await?)
+    }
+
+    /// N-of-N script of the committee `pubkey` is a member of, with keys in committee order.
+    pub async fn get_pegin_multi_sig_script(&self, pubkey: &str) -> BridgeResult<ScriptBuf> {
+        let mut storage = self.storage().await?;
+        let member = committee_member(&mut storage, pubkey).await?;
+        let committee = storage
+            .bridges_dal()
+            .get_committee(member.bridge_id)
+            .await?;
+        multi_sig_script(&committee)
+    }
+
+    pub async fn query_operators(&self, filter: OperatorFilter) -> BridgeResult<Vec<Operator>> {
+        if let Some(chain_id) = filter.chain_id {
+            self.chain(chain_id)?;
+        }
+        let mut storage = self.storage().await?;
+        Ok(storage.operators_dal().get_operators(&filter).await?)
+    }
+
+    pub async fn query_pegin_take_tx_sign_msg(
+        &self,
+        request: PeginTakeTxMsgRequest,
+    ) -> BridgeResult<PeginTakeTxMsgReponse> {
+        let mut storage = self.storage().await?;
+        let operator = operator(&mut storage, &request.operator_public_key).await?;
+        let operation = storage
+            .pegins_dal()
+            .get_pegin_operation(request.pegin_id, operator.id)
+            .await?
+            .ok_or_else(|| {
+                not_found(
+                    "take_transaction",
+                    format!("{}/{}", request.pegin_id, operator.id),
+                )
+            })?;
+        let txid = decode_tx(&operation.raw_take_tx)?.txid();
+        Ok(PeginTakeTxMsgReponse {
+            pegin_id: operation.pegin_id,
+            operator_id: operation.operator_id,
+            txid: txid.to_string(),
+            raw_take_tx: operation.raw_take_tx,
+        })
+    }
+
+    /// Validates a peg-in request, returning the peg-in to insert and its deposit address.
+    fn validate_pegin(&self, request: &PeginCreateRequest) -> BridgeResult<(NewPegin, Address)> {
+        let chain = self.chain(request.target_chain_id)?;
+        let pegin = NewPegin::new(request, self.network)?;
+        let deposit_address =
+            parse_bitcoin_address(&chain.bridge.assertion_taproot_address, self.network)?;
+        Ok((pegin, deposit_address))
+    }
+
+    pub async fn create_pegin(
+        &self,
+        request: PeginCreateRequest,
+    ) -> BridgeResult<PeginCreateResponse> {
+        let (pegin, deposit_address) = self.validate_pegin(&request)?;
+        let mut storage = self.storage().await?;
+        let pegin_id = storage.pegins_dal().insert_pegin(&pegin).await?;
+        Ok(PeginCreateResponse {
+            pegin_id,
+            deposit_address: deposit_address.to_string(),
+            amount: request.amount,
+        })
+    }
+
+    /// Checks that the deposit pays at least `amount` to `deposit_address`.
+    fn validate_deposit(
+        raw_pegin_hex: &str,
+        deposit_address: &Address,
+        amount: i64,
+    ) -> BridgeResult<()> {
+        let tx = decode_tx(raw_pegin_hex)?;
+        let paid = paid_to(&tx, deposit_address);
+        if paid < amount as u64 {
+            return Err(invalid(format!(
+                "deposit pays {paid} sat to {deposit_address}, expected {amount}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Records the deposit before broadcasting it, so that it is never on chain without the
+    /// bridge knowing it. A refused deposit stays recorded until the next one replaces it; it
+    /// can't confirm.
+    async fn broadcast_deposit(&self, pegin_id: i32, raw_pegin_hex: &str) -> BridgeResult<()> {
+        let txid = decode_tx(raw_pegin_hex)?.txid();
+        let mut storage = self.storage().await?;
+        storage
+            .pegins_dal()
+            .set_pegin_tx(pegin_id, &txid.to_string(), raw_pegin_hex)
+            .await?;
+        drop(storage);
+        self.backend.post_tx(raw_pegin_hex.to_string()).await?;
+        Ok(())
+    }
+
+    /// Broadcasts the deposit of a created peg-in.
+    pub async fn submit_pegin(&self, request: PeginRequest) -> BridgeResult<u32> {
+        let pegin = self.pegin(request.pegin_id).await?;
+        if pegin.status != PeginStatus::Created {
+            return Err(conflict(format!(
+                "peg-in {} is already {}",
+                pegin.id, pegin.status
+            )));
+        }
+        let deposit_address = &self
+            .chain(pegin.target_chain_id)?
+            .bridge
+            .assertion_taproot_address;
+        let deposit_address = parse_bitcoin_address(deposit_address, self.network)?;
+        Self::validate_deposit(&request.raw_pegin_hex, &deposit_address, pegin.amount)?;
+        self.broadcast_deposit(pegin.id, &request.raw_pegin_hex)
+            .await?;
+        Ok(pegin.id as u32)
+    }
+
+    /// Creates a peg-in and broadcasts its deposit in one go.
+    pub async fn submit_lp_pegin(&self, request: LPPeginRequest) -> BridgeResult<u32> {
+        let (pegin, deposit_address) = self.validate_pegin(&request.pegin)?;
+        Self::validate_deposit(
+            &request.raw_pegin_hex,
+            &deposit_address,
+            request.pegin.amount,
+        )?;
+        let pegin_id = {
+            let mut storage = self.storage().await?;
+            storage.pegins_dal().insert_pegin(&pegin).await?
+        };
+        self.broadcast_deposit(pegin_id, &request.raw_pegin_hex)
+            .await?;
+        Ok(pegin_id as u32)
+    }
+
+    pub async fn pegin(&self, pegin_id: i32) -> BridgeResult<PeginDetails> {
+        let mut storage = self.storage().await?;
+        pegin(&mut storage, pegin_id).await
+    }
+
+    pub async fn pegout(&self, pegout_id: i32) -> BridgeResult<PegoutDetail> {
+        let mut storage = self.storage().await?;
+        pegout(&mut storage, pegout_id).await
+    }
+
+    /// Without an `event_idx` this is the first peg-out the transaction burned for.
+    pub async fn pegout_by_burn_tx_hash(
+        &self,
+        burn_tx_hash: &str,
+        event_idx: Option<i32>,
+    ) -> BridgeResult<PegoutDetail> {
+        let mut storage = self.storage().await?;
+        storage
+            .pegouts_dal()
+            .get_pegouts_by_burn_tx_hash(burn_tx_hash)
+            .await?
+            .into_iter()
+            .find(|pegout| event_idx.map_or(true, |idx| pegout.event_idx == idx))
+            .ok_or_else(|| not_found("pegout", burn_tx_hash))
+    }
+
+    pub async fn get_pegout_history(
+        &self,
+        request: PegoutRequest,
+    ) -> BridgeResult<Vec<PegoutInfo>> {
+        let mut storage = self.history_storage().await?;
+        let pegouts = storage
+            .pegouts_dal()
+            .get_pegouts_by_sender_address(&request.sender_address, request.pagination)
+            .await?;
+        Ok(pegouts.into_iter().map(PegoutInfo::from).collect())
+    }
+
+    pub async fn get_pegin_history(&self, address: &str) -> BridgeResult<Vec<PeginDetails>> {
+        let mut storage = self.history_storage().await?;
+        Ok(storage
+            .pegins_dal()
+            .get_pegins_by_sender_address(address, Pagination::default())
+            .await?)
+    }
+
+    /// Peg-outs are paid from a whole peg-in UTXO, so only the amounts of unused peg-ins can be
+    /// pegged out.
+    pub async fn get_available_pegout_amounts(
+        &self,
+        amount: u64,
+        n: u32,
+    ) -> BridgeResult<Vec<u64>> {
+        let amount = i64::try_from(amount).map_err(|_| invalid("amount is out of range"))?;
+        let mut storage = self.storage().await?;
+        let amounts = storage
+            .pegins_dal()
+            .get_available_pegin_amounts(amount, n.min(Pagination::MAX_LIMIT))
+            .await?;
+        Ok(amounts.into_iter().map(|amount| amount as u64).collect())
+    }
+
+    /// Peg-outs waiting for an operator to pay them out, oldest first.
+    pub async fn query_lp_pending_pegouts(&self) -> BridgeResult<Vec<PegoutInfo>> {
+        let mut storage = self.storage().await?;
+        let pegouts = storage
+            .pegouts_dal()
+            .get_pegouts_by_status(PegoutStatus::BurnObserved, Pagination::MAX_LIMIT)
+            .await?;
+        Ok(pegouts.into_iter().map(PegoutInfo::from).collect())
+    }
+
+    /// Assigns a peg-out to the operator, reimbursing it from a peg-in UTXO of the same amount
+    /// it has a take transaction for. The operator has to broadcast the payout within
+    /// `payout_timeout_blocks`, or the assignment is released.
+    pub async fn try_lock_pegin_utxo(
+        &self,
+        request: SignedRequest<LPPegoutRequest>,
+    ) -> BridgeResult<Option<PeginOperation>> {
+        self.authenticate(
+            "bridge_tryLockPeginUtxo",
+            &request,
+            &request.request.operator_public_key,
+        )?;
+        let request = request.request;
+        let height = self.backend.get_block_count().await? as i64;
+        let mut storage = self.storage().await?;
+        let operator = operator(&mut storage, &request.operator_public_key).await?;
+        if operator.status != OPERATOR_ACTIVE {
+            return Err(conflict(format!(
+                "operator {} is {}",
+                operator.id, operator.status
+            )));
+        }
+        let mut transaction = storage.start_transaction().await?;
+        let pegout = pegout(&mut transaction, request.pegout_id).await?;
+        if pegout.chain_id != operator.chain_id {
+            return Err(invalid(format!(
+                "operator {} doesn't serve chain {}",
+                operator.id, pegout.chain_id
+            )));
+        }
+        if pegout.status != PegoutStatus::BurnObserved {
+            return Ok(None);
+        }
+        let Some(operation) = transaction
+            .pegins_dal()
+            .lock_available_pegin(pegout.amount, operator.id)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let transition = PegoutTransition {
+            from: PegoutStatus::BurnObserved,
+            to: PegoutStatus::OperatorAssigned,
+            block_height: Some(height),
+            deadline_height: Some(height + self.config.payout_timeout_blocks() as i64),
+        };
+        if !transaction
+            .pegouts_dal()
+            .update_pegout_status(pegout.id, transition)
+            .await?
+        {
+            return Ok(None);
+        }
+        match transaction
+            .pegouts_dal()
+            .assign_operator(pegout.id, operation.pegin_id, operator.id)
+            .await
+        {
+            // A concurrent transaction locked the peg-in after this one checked it.
+            Err(err) if err.is_unique_violation() => {
+                return Err(conflict(format!(
+                    "peg-in {} was locked concurrently",
+                    operation.pegin_id
+                )));
+            }
+            result => result?,
+        };
+        transaction.commit().await?;
+        Ok(Some(operation))
+    }
+
+    fn validate_block_range(
+        &self,
+        chain_id: i32,
+        from_block: i64,
+        to_block: i64,
+    ) -> BridgeResult<()> {
+        self.chain(chain_id)?;
+        if from_block > to_block || to_block - from_block >= MAX_EVENT_BLOCK_RANGE {
+            return Err(invalid(format!(
+                "block range must be ordered and span at most {MAX_EVENT_BLOCK_RANGE} blocks"
+            )));
+        }
+        Ok(())
+    }
+
+    async fn events(
+        &self,
+        chain_id: i32,
+        event_type: &str,
+        from_block: i64,
+        to_block: i64,
+    ) -> BridgeResult<Vec<(EvmEvent, BridgeEvent)>> {
+        self.validate_block_range(chain_id, from_block, to_block)?;
+        let mut storage = self.history_storage().await?;
+        let events = storage
+            .events_dal()
+            .get_events_by_block_range(chain_id, event_type, from_block, to_block)
+            .await?;
+        Ok(events
+            .into_iter()
+            .filter_map(|event| match serde_json::from_str(&event.data) {
+                Ok(decoded) => Some((event, decoded)),
+                Err(err) => {
+                    logs::warn!(
+                        "skipping undecodable event {}:{}: {err}",
+                        event.tx_hash,
+                        event.event_idx
+                    );
+                    None
+                }
+            })
+            .collect())
+    }
+
+    pub async fn query_pegin_events_by_range(
+        &self,
+        request: PeginEventRangeRequest,
+    ) -> BridgeResult<Vec<PeginEventDetails>> {
+        let events = self
+            .events(
+                request.chain_id,
+                "mint",
+                request.from_block,
+                request.to_block,
+            )
+            .await?;
+        Ok(events
+            .into_iter()
+            .filter_map(|(event, decoded)| match decoded {
+                BridgeEvent::Mint {
+                    to,
+                    amount,
+                    pegin_tx_hash,
+                } => Some(PeginEventDetails {
+                    chain_id: event.chain_id,
+                    tx_hash: event.tx_hash,
+                    event_idx: event.event_idx,
+                    block_number: event.block_number,
+                    to,
+                    amount,
+                    pegin_tx_hash,
+                    status: event.status,
+                }),
+                _ => None,
+            })
+            .collect())
+    }
+
+    pub async fn query_pegout_events_by_range(
+        &self,
+        request: PegouttEventRangeRequest,
+    ) -> BridgeResult<Vec<PegoutEventDetail>> {
+        let events = self
+            .events(
+                request.chain_id,
+                "burn",
+                request.from_block,
+                request.to_block,
+            )
+            .await?;
+        Ok(events
+            .into_iter()
+            .filter_map(|(event, decoded)| match decoded {
+                BridgeEvent::Burn {
+                    from,
+                    amount,
+                    btc_address,
+                } => Some(PegoutEventDetail {
+                    chain_id: event.chain_id,
+                    tx_hash: event.tx_hash,
+                    event_idx: event.event_idx,
+                    block_number: event.block_number,
+                    from,
+                    amount,
+                    btc_address,
+                    status: event.status,
+                }),
+                _ => None,
+            })
+            .collect())
+    }
+
+    pub async fn query_pegout_operations_for_challenger(
+        &self,
+        request: PegoutOperationRangerRequest,
+    ) -> BridgeResult<Vec<PegoutOperationRangeResponse>> {
+        let mut storage = self.storage().await?;
+        let pegouts = storage
+            .pegouts_dal()
+            .get_operated_pegouts(
+                request.from_pegout_id,
+                request.len.min(PegoutOperationRangerRequest::MAX_LEN),
+            )
+            .await?;
+        Ok(pegouts
+            .into_iter()
+            .filter_map(|pegout| {
+                Some(PegoutOperationRangeResponse {
+                    pegout_id: pegout.id,
+                    pegin_id: pegout.pegin_id?,
+                    operator_id: pegout.operator_id?,
+                    status: pegout.status,
+                    payout_tx_hash: pegout.payout_tx_hash,
+                    kickoff_tx_hash: pegout.kickoff_tx_hash,
+                    deadline_height: pegout.deadline_height,
+                })
+            })
+            .collect())
+    }
+
+    /// Returns the peg-out if its kickoff can be challenged.
+    async fn challengeable_pegout(
+        storage: &mut StorageProcessor<'_>,
+        pegout_id: i32,
+    ) -> BridgeResult<PegoutDetail> {
+        let pegout = pegout(storage, pegout_id).await?;
+        if pegout.status != PegoutStatus::ChallengeWindowOpen {
+            return Err(conflict(format!(
+                "peg-out {pegout_id} can't be challenged while {}",
+                pegout.status
+            )));
+        }
+        Ok(pegout)
+    }
+
+    pub async fn query_challenge_data(
+        &self,
+        request: QueryChallengeDataRequest,
+    ) -> BridgeResult<QueryChallengeDataReponse> {
+        let mut storage = self.storage().await?;
+        let pegout = Self::challengeable_pegout(&mut storage, request.pegout_id).await?;
+        let (Some(pegin_id), Some(operator_id), Some(kickoff_tx_hash), Some(deadline_height)) = (
+            pegout.pegin_id,
+            pegout.operator_id,
+            pegout.kickoff_tx_hash,
+            pegout.deadline_height,
+        ) else {
+            return Err(conflict(format!("peg-out {} has no kickoff", pegout.id)));
+        };
+        let challenge_tx = storage
+            .presigned_transactions_dal()
+            .get_presigned_transactions(
+                pegin_id,
+                Some(operator_id),
+                Some(PresignedTxType::Challenge),
+            )
+            .await?
+            .into_iter()
+            .find(|tx| tx.status == PRESIGNED_TX_SIGNED)
+            .ok_or_else(|| not_found("challenge_transaction", pegout.id))?;
+        Ok(QueryChallengeDataReponse {
+            pegout_id: pegout.id,
+            operator_id,
+            kickoff_tx_hash,
+            raw_challenge_tx: challenge_tx.raw_hex,
+            deadline_height,
+        })
+    }
+
+    /// Records a challenge broadcast by a challenger and returns its id.
+    pub async fn submit_challenge(&self, request: StartChallengeResponse) -> BridgeResult<u32> {
+        request.validate(self.network)?;
+        let mut storage = self.storage().await?;
+        let pegout = Self::challengeable_pegout(&mut storage, request.pegout_id).await?;
+        let challenge_id = storage
+            .challenges_dal()
+            .insert_challenge(
+                pegout.id,
+                &request.challenger_address,
+                &request.challenge_tx_hash,
+            )
+            .await?
+            .ok_or_else(|| {
+                conflict(format!(
+                    "challenge {} is already submitted",
+                    request.challenge_tx_hash
+                ))
+            })?;
+        Ok(challenge_id as u32)
+    }
+
+    pub async fn query_challenge_status(&self, challenge_id: u32) -> BridgeResult<ChallengeStatus> {
+        let mut storage = self.storage().await?;
+        let challenge = storage
+            .challenges_dal()
+            .get_challenge(challenge_id as i32)
+            .await?
+            .ok_or_else(|| not_found("challenge", challenge_id))?;
+        Ok(challenge.status)
+    }
+
+    /// Stores the take transaction and the transaction graph an operator prepared for a
+    /// confirmed peg-in, for the committee to sign.
+    pub async fn submit_pegin_operator_presigned_transactions(
+        &self,
+        response: SignedRequest<PeginOperatorTaskResponse>,
+    ) -> BridgeResult<()> {
+        self.authenticate(
+            "bridge_submitPeginOperatorPresignedTransactions",
+            &response,
+            &response.request.operator_public_key,
+        )?;
+        let response = response.request;
+        let take_txid = decode_tx(&response.raw_take_tx)?.txid();
+        let presigned_txs = response
+            .presigned_txs
+            .iter()
+            .map(|tx| Ok((decode_tx(&tx.raw_hex)?.txid(), tx)))
+            .collect::<BridgeResult<Vec<_>>>()?;
+
+        let mut storage = self.storage().await?;
+        let operator = operator(&mut storage, &response.operator_public_key).await?;
+        let pegin = pegin(&mut storage, response.pegin_id).await?;
+        if pegin.target_chain_id != operator.chain_id {
+            return Err(invalid(format!(
+                "operator {} doesn't serve chain {}",
+                operator.id, pegin.target_chain_id
+            )));
+        }
+        if pegin.status != PeginStatus::Confirmed {
+            return Err(conflict(format!(
+                "peg-in {} doesn't take transactions while {}",
+                pegin.id, pegin.status
+            )));
+        }
+        if pegin.deposit_reorged_at.is_some() {
+            return Err(conflict(format!(
+                "peg-in {} doesn't take transactions until its deposit confirms again",
+                pegin.id
+            )));
+        }
+        if storage
+            .pegins_dal()
+            .get_pegin_operation(pegin.id, operator.id)
+            .await?
+            .is_some()
+        {
+            return Err(conflict(format!(
+                "operator {} already submitted transactions for peg-in {}",
+                operator.id, pegin.id
+            )));
+        }
+
+        let mut transaction = storage.start_transaction().await?;
+        transaction
+            .pegins_dal()
+            .insert_pegin_operation(
+                pegin.id,
+                operator.id,
+                &response.raw_take_tx,
+                PEGIN_OPERATION_PENDING,
+            )
+            .await?;
+        let dal = &mut transaction.presigned_transactions_dal();
+        dal.insert_presigned_transaction(
+            &take_txid.to_string(),
+            PresignedTxType::Take,
+            pegin.id,
+            Some(operator.id),
+            &response.raw_take_tx,
+        )
+        .await?;
+        for (txid, tx) in presigned_txs {
+            dal.insert_presigned_transaction(
+                &txid.to_string(),
+                tx.tx_type,
+                pegin.id,
+                Some(operator.id),
+                &tx.raw_hex,
+            )
+            .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Returns the peg-out if `operator_public_key` is its operator and it is in one of
+    /// `statuses`.
+    async fn operated_pegout(
+        storage: &mut StorageProcessor<'_>,
+        pegout_id: i32,
+        operator_public_key: &str,
+        statuses: &[PegoutStatus],
+    ) -> BridgeResult<(PegoutDetail, Operator)> {
+        let operator = operator(storage, operator_public_key).await?;
+        let pegout = pegout(storage, pegout_id).await?;
+        if pegout.operator_id != Some(operator.id) {
+            return Err(invalid(format!(
+                "peg-out {pegout_id} isn't assigned to operator {}",
+                operator.id
+            )));
+        }
+        if !statuses.contains(&pegout.status) {
+            let expected: Vec<_> = statuses.iter().map(ToString::to_string).collect();
+            return Err(conflict(format!(
+                "peg-out {pegout_id} is {}, expected {}",
+                pegout.status,
+                expected.join(" or ")
+            )));
+        }
+        Ok((pegout, operator))
+    }
+
+    /// Broadcasts a transaction whose peg-out status change and txid are already committed, so
+    /// that the transaction is never on chain without the bridge knowing it. If the node refuses
+    /// the transaction, the status change is undone with `revert`. Other failures, e.g. timeouts,
+    /// keep it: the node may have accepted the transaction, and the operator can retry posting it.
+    async fn broadcast_or_revert(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        pegout_id: i32,
+        raw_tx: String,
+        revert: PegoutTransition,
+    ) -> BridgeResult<()> {
+        let Err(err) = self.backend.post_tx(raw_tx).await else {
+            return Ok(());
+        };
+        if !err.is_tx_rejection() {
+            logs::warn!("broadcasting a transaction of peg-out {pegout_id} failed: {err}");
+            return Err(err.into());
+        }
+        if !storage
+            .pegouts_dal()
+            .update_pegout_status(pegout_id, revert)
+            .await?
+        {
+            logs::warn!(
+                "peg-out {pegout_id} advanced before its failed broadcast could be reverted"
+            );
+        }
+        Err(err.into())
+    }
+
+    /// Posts a transaction an earlier request committed as `committed_txid` again, e.g. after its
+    /// broadcast timed out.
+    async fn rebroadcast(
+        &self,
+        pegout: &PegoutDetail,
+        committed_txid: Option<&str>,
+        txid: Txid,
+        raw_tx: String,
+    ) -> BridgeResult<()> {
+        if committed_txid != Some(txid.to_string().as_str()) {
+            return Err(conflict(format!(
+                "peg-out {} is {} with another transaction",
+                pegout.id, pegout.status
+            )));
+        }
+        self.backend.post_tx(raw_tx).await?;
+        Ok(())
+    }
+
+    /// Broadcasts the kickoff of an operator whose payout is confirmed.
+    pub async fn submit_pegout_operator_response(
+        &self,
+        response: SignedRequest<PegoutOperatorTaskResponse>,
+    ) -> BridgeResult<()> {
+        self.authenticate(
+            "bridge_submitPegoutOperatorResponse",
+            &response,
+            &response.request.operator_public_key,
+        )?;
+        let response = response.request;
+        let kickoff_txid = decode_tx(&response.raw_kickoff_tx)?.txid();
+        let mut storage = self.storage().await?;
+        let (pegout, _) = Self::operated_pegout(
+            &mut storage,
+            response.pegout_id,
+            &response.operator_public_key,
+            &[PegoutStatus::PayoutConfirmed, PegoutStatus::KickedOff],
+        )
+        .await?;
+        if pegout.status == PegoutStatus::KickedOff {
+            return self
+                .rebroadcast(
+                    &pegout,
+                    pegout.kickoff_tx_hash.as_deref(),
+                    kickoff_txid,
+                    response.raw_kickoff_tx,
+                )
+                .await;
+        }
+
+        let mut transaction = storage.start_transaction().await?;
+        let transition = PegoutTransition {
+            from: PegoutStatus::PayoutConfirmed,
+            to: PegoutStatus::KickedOff,
+            block_height: None,
+            deadline_height: None,
+        };
+        if !transaction
+            .pegouts_dal()
+            .update_pegout_status(pegout.id, transition)
+            .await?
+        {
+            return Err(conflict(format!(
+                "peg-out {} was advanced concurrently",
+                pegout.id
+            )));
+        }
+        transaction
+            .pegouts_dal()
+            .set_kickoff_tx_hash(pegout.id, &kickoff_txid.to_string())
+            .await?;
+        transaction.commit().await?;
+
+        let revert = PegoutTransition {
+            from: PegoutStatus::KickedOff,
+            to: PegoutStatus::PayoutConfirmed,
+            block_height: None,
+            deadline_height: pegout.deadline_height,
+        };
+        self.broadcast_or_revert(&mut storage, pegout.id, response.raw_kickoff_tx, revert)
+            .await
+    }
+
+    /// Broadcasts the payout of the operator assigned to a peg-out.
+    pub async fn submit_operator_pegout_tx(
+        &self,
+        request: SignedRequest<OperatorPegoutTxRequest>,
+    ) -> BridgeResult<()> {
+        self.authenticate(
+            "bridge_submitOperatorPegoutTx",
+            &request,
+            &request.request.operator_public_key,
+        )?;
+        let request = request.request;
+        let payout = decode_tx(&request.raw_payout_tx)?;
+        let mut storage = self.storage().await?;
+        let (pegout, operator) = Self::operated_pegout(
+            &mut storage,
+            request.pegout_id,
+            &request.operator_public_key,
+            &[
+                PegoutStatus::OperatorAssigned,
+                PegoutStatus::PayoutBroadcast,
+            ],
+        )
+        .await?;
+        if pegout.status == PegoutStatus::PayoutBroadcast {
+            return self
+                .rebroadcast(
+                    &pegout,
+                    pegout.payout_tx_hash.as_deref(),
+                    payout.txid(),
+                    request.raw_payout_tx,
+                )
+                .await;
+        }
+        let receive_address = parse_bitcoin_address(&pegout.receive_address, self.network)?;
+        // The operator keeps at most its registered fee.
+        let min_payout = (pegout.amount - operator.fee).max(1) as u64;
+        let paid = paid_to(&payout, &receive_address);
+        if paid < min_payout {
+            return Err(invalid(format!(
+                "payout pays {paid} sat to {}, expected at least {min_payout}",
+                pegout.receive_address
+            )));
+        }
+
+        let mut transaction = storage.start_transaction().await?;
+        let transition = PegoutTransition {
+            from: PegoutStatus::OperatorAssigned,
+            to: PegoutStatus::PayoutBroadcast,
+            block_height: None,
+            // Kept, so that a payout that never reaches the chain still releases the peg-out.
+            deadline_height: pegout.deadline_height,
+        };
+        if !transaction
+            .pegouts_dal()
+            .update_pegout_status(pegout.id, transition)
+            .await?
+        {
+            return Err(conflict(format!(
+                "peg-out {} is no longer assigned to the operator",
+                pegout.id
+            )));
+        }
+        transaction
+            .pegouts_dal()
+            .set_payout_tx_hash(pegout.id, &payout.txid().to_string())
+            .await?;
+        transaction.commit().await?;
+
+        let revert = PegoutTransition {
+            from: PegoutStatus::PayoutBroadcast,
+            to: PegoutStatus::OperatorAssigned,
+            block_height: None,
+            deadline_height: pegout.deadline_height,
+        };
+        self.broadcast_or_revert(&mut storage, pegout.id, request.raw_payout_tx, revert)
+            .await
+    }
+
+    /// Completes a peg-out once the take transaction reimbursing its operator is on chain.
+    pub async fn submit_pegout_received(
+        &self,
+        request: SignedRequest<PegoutReceivedRequest>,
+    ) -> BridgeResult<()> {
+        self.authenticate(
+            "bridge_submitPegoutReceived",
+            &request,
+            &request.request.operator_public_key,
+        )?;
+        let request = request.request;
+        let take_txid = parse_txid(&request.take_tx_hash)?;
+        let mut storage = self.storage().await?;
+        let (pegout, operator) = Self::operated_pegout(
+            &mut storage,
+            request.pegout_id,
+            &request.operator_public_key,
+            &[PegoutStatus::ChallengeWindowClosed],
+        )
+        .await?;
+        let Some(pegin_id) = pegout.pegin_id else {
+            return Err(conflict(format!("peg-out {} has no peg-in", pegout.id)));
+        };
+        let pegin = pegin(&mut storage, pegin_id).await?;
+        let operation = storage
+            .pegins_dal()
+            .get_pegin_operation(pegin_id, operator.id)
+            .await?
+            .ok_or_else(|| {
+                conflict(format!(
+                    "operator {} has no take transaction for peg-in {pegin_id}",
+                    operator.id
+                ))
+            })?;
+        let presigned_txid = decode_tx(&operation.raw_take_tx)?.txid();
+        if take_txid != presigned_txid {
+            return Err(invalid(format!(
+                "{take_txid} isn't the take transaction {presigned_txid} of peg-in {pegin_id}"
+            )));
+        }
+        let take_tx = self.backend.get_tx_info(take_txid).await?;
+        if take_tx.confirmations == 0 {
+            return Err(conflict(format!(
+                "take transaction {take_txid} is not confirmed"
+            )));
+        }
+        // The operator is reimbursed from the peg-in deposit, through its kickoff or directly.
+        let reimbursing = [pegout.kickoff_tx_hash, pegin.pegin_tx_hash];
+        let spends_reimbursing_tx = take_tx.tx.input.iter().any(|input| {
+            let txid = input.previous_output.txid.to_string();
+            reimbursing.iter().flatten().any(|hash| *hash == txid)
+        });
+        if !spends_reimbursing_tx {
+            return Err(invalid(format!(
+                "take transaction {take_txid} spends neither the kickoff nor the peg-in deposit"
+            )));
+        }
+
+        let mut transaction = storage.start_transaction().await?;
+        let transition = PegoutTransition {
+            from: PegoutStatus::ChallengeWindowClosed,
+            to: PegoutStatus::Reimbursed,
+            block_height: None,
+            deadline_height: None,
+        };
+        if transaction
+            .pegouts_dal()
+            .update_pegout_status(pegout.id, transition)
+            .await?
+        {
+            transaction
+                .operators_dal()
+                .increment_pegout_cnt(operator.id)
+                .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Returns the committee member if it guards the bridge of `chain_id`.
+    async fn committee_member_of(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        public_key: &str,
+        chain_id: i32,
+    ) -> BridgeResult<CommitteeMember> {
+        let member = committee_member(storage, public_key).await?;
+        if self.chain(chain_id)?.bridge.id != member.bridge_id {
+            return Err(invalid(format!(
+                "{public_key} isn't a committee member of chain {chain_id}"
+            )));
+        }
+        Ok(member)
+    }
+
+    /// Records that a committee member accepts a deposited peg-in. The peg-in moves to
+    /// `Confirmed` once the whole committee accepted it.
+    pub async fn submit_pegin_received(
+        &self,
+        request: SignedRequest<PeginReceivedRequest>,
+    ) -> BridgeResult<()> {
+        self.authenticate(
+            "bridge_submitPeginReceived",
+            &request,
+            &request.request.committee_public_key,
+        )?;
+        let request = request.request;
+        let mut storage = self.storage().await?;
+        let pegin = pegin(&mut storage, request.pegin_id).await?;
+        let member = self
+            .committee_member_of(
+                &mut storage,
+                &request.committee_public_key,
+                pegin.target_chain_id,
+            )
+            .await?;
+        match pegin.status {
+            PeginStatus::Deposited => {}
+            PeginStatus::Created | PeginStatus::Refunded => {
+                return Err(conflict(format!(
+                    "peg-in {} can't be accepted while {}",
+                    pegin.id, pegin.status
+                )));
+            }
+            // The rest of the committee accepted it first.
+            PeginStatus::Confirmed | PeginStatus::PresignCollected | PeginStatus::Minted => {
+                return Ok(());
+            }
+        }
+
+        let committee_size = storage
+            .bridges_dal()
+            .get_committee(member.bridge_id)
+            .await?
+            .len() as i64;
+        let mut transaction = storage.start_transaction().await?;
+        let accepted = transaction
+            .pegins_dal()
+            .accept_pegin(pegin.id, &member.public_key)
+            .await?;
+        if accepted >= committee_size {
+            transaction
+                .pegins_dal()
+                .update_pegin_status(pegin.id, PeginStatus::Deposited, PeginStatus::Confirmed)
+                .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Outputs spent by the inputs of `tx`, looked up among `known` transactions first and on the
+    /// Bitcoin node otherwise.
+    async fn prevouts(
+        &self,
+        tx: &Transaction,
+        known: &HashMap<Txid, Transaction>,
+    ) -> BridgeResult<Vec<TxOut>> {
+        let mut prevouts = Vec::with_capacity(tx.input.len());
+        for input in &tx.input {
+            let outpoint = input.previous_output;
+            let output = match known.get(&outpoint.txid) {
+                Some(prev_tx) => prev_tx.output.get(outpoint.vout as usize).cloned(),
+                None => match self.backend.get_tx_info(outpoint.txid).await {
+                    Ok(info) => info.tx.output.get(outpoint.vout as usize).cloned(),
+                    Err(err) if err.is_unknown_tx() => None,
+                    Err(err) => return Err(err.into()),
+                },
+            };
+            let output = output.ok_or_else(|| {
+                invalid(format!(
+                    "transaction {} spends unknown output {outpoint}",
+                    tx.txid()
+                ))
+            })?;
+            prevouts.push(output);
+        }
+        Ok(prevouts)
+    }
+
+    /// Checks that `member` signed each transaction of `txs` it submits a signature for, see
+    /// [`CommitteeSignature::message`]. Inputs may spend the peg-in deposit, other transactions
+    /// of the peg-in or outputs the Bitcoin node knows.
+    async fn verify_signatures(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        member: &CommitteeMember,
+        pegin: &PeginDetails,
+        txs: &[PresignedTransaction],
+        signatures: &[CommitteeSignature],
+    ) -> BridgeResult<()> {
+        let committee = storage
+            .bridges_dal()
+            .get_committee(member.bridge_id)
+            .await?;
+        let leaf_script = multi_sig_script(&committee)?;
+        let key = parse_x_only_key(&member.public_key)?;
+        let mut known = HashMap::new();
+        let pegin_txs = storage
+            .presigned_transactions_dal()
+            .get_presigned_transactions(pegin.id, None, None)
+            .await?;
+        let raw_txs = pegin_txs
+            .iter()
+            .map(|tx| &tx.raw_hex)
+            .chain(&pegin.raw_pegin_hex);
+        for raw_tx in raw_txs {
+            let tx = decode_tx(raw_tx)?;
+            known.insert(tx.txid(), tx);
+        }
+
+        for signature in signatures {
+            let Some(presigned) = txs.iter().find(|tx| tx.txid == signature.txid) else {
+                return Err(invalid(format!(
+                    "transaction {} doesn't need committee signatures",
+                    signature.txid
+                )));
+            };
+            let tx = decode_tx(&presigned.raw_hex)?;
+            let prevouts = self.prevouts(&tx, &known).await?;
+            let message = CommitteeSignature::message(&tx, &prevouts, &leaf_script)?;
+            signature.verify(&key, &message)?;
+        }
+        Ok(())
+    }
+
+    /// Records signatures checked by [`Self::verify_signatures()`].
+    async fn add_signatures(
+        storage: &mut StorageProcessor<'_>,
+        member: &CommitteeMember,
+        signatures: &[CommitteeSignature],
+    ) -> BridgeResult<()> {
+        let committee_size = storage
+            .bridges_dal()
+            .get_committee(member.bridge_id)
+            .await?
+            .len() as i32;
+        for signature in signatures {
+            storage
+                .presigned_transactions_dal()
+                .add_committee_signature(
+                    &signature.txid,
+                    &member.public_key,
+                    &signature.signature,
+                    committee_size,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Records the signatures of a committee member over the transactions of a peg-in. The
+    /// peg-in moves to `PresignCollected` once every transaction is signed by the whole
+    /// committee.
+    pub async fn submit_pegin_committee_presigned_transactions(
+        &self,
+        response: PeginCommitteeTaskResponse,
+    ) -> BridgeResult<()> {
+        let mut storage = self.storage().await?;
+        let pegin = pegin(&mut storage, response.pegin_id).await?;
+        let member = self
+            .committee_member_of(
+                &mut storage,
+                &response.committee_public_key,
+                pegin.target_chain_id,
+            )
+            .await?;
+        if pegin.status != PeginStatus::Confirmed {
+            return Err(conflict(format!(
+                "peg-in {} doesn't take signatures while {}",
+                pegin.id, pegin.status
+            )));
+        }
+        if pegin.deposit_reorged_at.is_some() {
+            return Err(conflict(format!(
+                "peg-in {} doesn't take signatures until its deposit confirms again",
+                pegin.id
+            )));
+        }
+
+        let txs = storage
+            .presigned_transactions_dal()
+            .get_presigned_transactions(pegin.id, None, None)
+            .await?;
+        self.verify_signatures(&mut storage, &member, &pegin, &txs, &response.signatures)
+            .await?;
+
+        let mut transaction = storage.start_transaction().await?;
+        Self::add_signatures(&mut transaction, &member, &response.signatures).await?;
+        let unsigned = transaction
+            .presigned_transactions_dal()
+            .count_unsigned(pegin.id)
+            .await?;
+        if unsigned == 0 {
+            transaction
+                .pegins_dal()
+                .update_pegin_status(
+                    pegin.id,
+                    PeginStatus::Confirmed,
+                    PeginStatus::PresignCollected,
+                )
+                .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Records the signatures of a committee member over the challenge transactions of the
+    /// operator paying a peg-out.
+    pub async fn submit_challenge_committee_presigned_transactions(
+        &self,
+        response: ChallengeCommitteeTaskResponse,
+    ) -> BridgeResult<()> {
+        let mut storage = self.storage().await?;
+        let pegout = pegout(&mut storage, response.pegout_id).await?;
+        let member = self
+            .committee_member_of(
+                &mut storage,
+                &response.committee_public_key,
+                pegout.chain_id,
+            )
+            .await?;
+        let (Some(pegin_id), Some(operator_id)) = (pegout.pegin_id, pegout.operator_id) else {
+            return Err(conflict(format!("peg-out {} has no operator", pegout.id)));
+        };
+
+        let pegin = pegin(&mut storage, pegin_id).await?;
+        let txs = storage
+            .presigned_transactions_dal()
+            .get_presigned_transactions(
+                pegin_id,
+                Some(operator_id),
+                Some(PresignedTxType::Challenge),
+            )
+            .await?;
+        self.verify_signatures(&mut storage, &member, &pegin, &txs, &response.signatures)
+            .await?;
+
+        let mut transaction = storage.start_transaction().await?;
+        Self::add_signatures(&mut transaction, &member, &response.signatures).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+}
+
+async fn pegin(storage: &mut StorageProcessor<'_>, pegin_id: i32) -> BridgeResult<PeginDetails> {
+    storage
+        .pegins_dal()
+        .get_pegin_by_id(pegin_id)
+        .await?
+        .ok_or_else(|| not_found("pegin", pegin_id))
+}
+
+async fn pegout(storage: &mut StorageProcessor<'_>, pegout_id: i32) -> BridgeResult<PegoutDetail> {
+    storage
+        .pegouts_dal()
+        .get_pegout_by_id(pegout_id)
+        .await?
+        .ok_or_else(|| not_found("pegout", pegout_id))
+}
+
+async fn operator(storage: &mut StorageProcessor<'_>, public_key: &str) -> BridgeResult<Operator> {
+    storage
+        .operators_dal()
+        .get_operator_by_public_key(public_key)
+        .await?
+        .ok_or_else(|| not_found("operator", public_key))
+}
+
+async fn committee_member(
+    storage: &mut StorageProcessor<'_>,
+    public_key: &str,
+) -> BridgeResult<CommitteeMember> {
+    storage
+        .bridges_dal()
+        .get_committee_member(public_key)
+        .await?
+        .ok_or_else(|| not_found("committee_member", public_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use bitcoin::{
+        absolute::LockTime,
+        consensus::encode::serialize_hex,
+        secp256k1::{
+            rand::{self, Rng},
+            Keypair, Secp256k1, SecretKey,
+        },
+        transaction::Version,
+        Amount, OutPoint, Script, Sequence, TxIn, Witness,
+    };
+    use bitcoin_client::mock::MockBitcoinBackend;
+    use config::evm::EvmWatcherConfig;
+    use types::{
+        bridge::NewBridge, operator::NewOperator, pegout::NewPegout,
+        presigned_tx::NewPresignedTransaction,
+    };
+
+    use super::*;
+
+    const SENDER_ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+    const DEPOSIT_ADDRESS: &str =
+        "bcrt1phcnl4zcl2fu047pv4wx6y058v8u0n02at6lthvm7pcf2wrvjm5tqatn90k";
+    const CLIENT: IpAddr = IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+
+    /// Chain, committee and operator registered by `setup()`. They are random, so that tests
+    /// running concurrently don't insert the same unique values.
+    struct Fixture {
+        chain_id: i32,
+        committee: [SecretKey; 2],
+        operator: SecretKey,
+    }
+
+    impl Fixture {
+        fn operator_key(&self) -> String {
+            public_key(&self.operator)
+        }
+
+        /// `request` to `method` signed by the registered operator.
+        fn signed<T: Serialize>(
+            &self,
+            bridge: &BridgeService,
+            method: &str,
+            request: T,
+        ) -> SignedRequest<T> {
+            signed_by(&self.operator, bridge, method, request)
+        }
+
+        fn pegin_request(&self, amount: i64) -> PeginCreateRequest {
+            PeginCreateRequest {
+                target_chain_id: self.chain_id,
+                public_key: public_key(&random_key()),
+                sender_address: SENDER_ADDRESS.to_string(),
+                receive_address: "0x0000000000000000000000000000000000000003".to_string(),
+                amount,
+            }
+        }
+    }
+
+    fn address(address: &str) -> Address {
+        parse_bitcoin_address(address, Network::Regtest).unwrap()
+    }
+
+    /// A transaction spending `outpoint`, hex-encoded.
+    fn spend(outpoint: OutPoint, outputs: &[(&str, u64)]) -> String {
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: outputs
+                .iter()
+                .map(|(to, amount)| TxOut {
+                    value: Amount::from_sat(*amount),
+                    script_pubkey: address(to).script_pubkey(),
+                })
+                .collect(),
+        };
+        serialize_hex(&tx)
+    }
+
+    fn random_hash() -> String {
+        hex::encode(rand::random::<[u8; 32]>())
+    }
+
+    fn random_key() -> SecretKey {
+        SecretKey::new(&mut rand::thread_rng())
+    }
+
+    fn public_key(secret_key: &SecretKey) -> String {
+        let (key, _) = secret_key.x_only_public_key(&Secp256k1::new());
+        key.to_string()
+    }
+
+    /// `request` to `method` signed by `secret_key`.
+    fn signed_by<T: Serialize>(
+        secret_key: &SecretKey,
+        bridge: &BridgeService,
+        method: &str,
+        request: T,
+    ) -> SignedRequest<T> {
+        let nonce = bridge.issue_nonce(CLIENT).unwrap();
+        let message = SignedRequest::message(method, &nonce, &request);
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_secret_key(&secp, secret_key);
+        SignedRequest {
+            request,
+            nonce,
+            signature: hex::encode(secp.sign_schnorr_no_aux_rand(&message, &keypair).as_ref()),
+        }
+    }
+
+    /// Signature of `secret_key` over `raw_tx`, spending `prevouts`.
+    fn committee_signature(
+        secret_key: &SecretKey,
+        raw_tx: &str,
+        prevouts: &[TxOut],
+        leaf_script: &Script,
+    ) -> CommitteeSignature {
+        let tx = decode_tx(raw_tx).unwrap();
+        let message = CommitteeSignature::message(&tx, prevouts, leaf_script).unwrap();
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_secret_key(&secp, secret_key);
+        CommitteeSignature {
+            txid: tx.txid().to_string(),
+            signature: hex::encode(secp.sign_schnorr_no_aux_rand(&message, &keypair).as_ref()),
+        }
+    }
+
+    async fn setup(
+        pool: &ConnectionPool,
+        backend: &MockBitcoinBackend,
+    ) -> (BridgeService, Fixture) {
+        let fixture = Fixture {
+            chain_id: rand::thread_rng().gen_range(1..i32::MAX),
+            committee: [random_key(), random_key()],
+            operator: random_key(),
+        };
+        let mut storage = pool.access_storage().await.unwrap();
+        storage
+            .bridges_dal()
+            .insert_bridge(&NewBridge {
+                chain_id: fixture.chain_id,
+                chain_name: "anvil".to_string(),
+                operator_manager_address: "0x0000000000000000000000000000000000000001".to_string(),
+                assertion_taproot_address: DEPOSIT_ADDRESS.to_string(),
+                status: "active".to_string(),
+                bridge_contract_address: None,
+            })
+            .await
+            .unwrap();
+        let bridge = storage
+            .bridges_dal()
+            .get_bridge_by_chain_id(fixture.chain_id)
+            .await
+            .unwrap()
+            .unwrap();
+        for (index, secret_key) in fixture.committee.iter().enumerate() {
+            // Committee addresses are unique, so they're derived from the keys.
+            let member_key = public_key(secret_key);
+            storage
+                .bridges_dal()
+                .insert_committee_member(
+                    bridge.id,
+                    &member_key,
+                    &format!("0x{}", &member_key[..40]),
+                    index as i32,
+                )
+                .await
+                .unwrap();
+        }
+        storage
+            .operators_dal()
+            .insert_operator(&NewOperator {
+                address: "0x0000000000000000000000000000000000000002".to_string(),
+                chain_id: fixture.chain_id,
+                public_key: fixture.operator_key(),
+                fee: 1_000,
+                status: "active".to_string(),
+                stake_amount: 10_000_000,
+                max_support_amount: 100_000_000,
+                min_support_amount: 10_000,
+                max_pegin_cnt: 10,
+                max_pegout_cnt: 10,
+                register_at: chrono::Utc::now().naive_utc(),
+            })
+            .await
+            .unwrap();
+
+        let chains = ChainRegistry::new(vec![bridge], &EvmWatcherConfig { chains: vec![] });
+        let config = PegoutConfig {
+            poll_interval_sec: None,
+            payout_timeout_blocks: Some(3),
+            challenge_period_blocks: None,
+        };
+        let bridge = BridgeService::new(
+            pool.clone(),
+            Arc::new(chains),
+            Arc::new(backend.clone()),
+            Network::Regtest,
+            config,
+        );
+        (bridge, fixture)
+    }
+
+    /// Inserts a peg-in and advances it to `status` along the lifecycle.
+    async fn insert_pegin(pool: &ConnectionPool, fixture: &Fixture, status: PeginStatus) -> i32 {
+        let mut storage = pool.access_storage().await.unwrap();
+        let id = storage
+            .pegins_dal()
+            .insert_pegin(
+                &NewPegin::new(&fixture.pegin_request(100_000), Network::Regtest).unwrap(),
+            )
+            .await
+            .unwrap();
+        let path = [
+            PeginStatus::Created,
+            PeginStatus::Deposited,
+            PeginStatus::Confirmed,
+            PeginStatus::PresignCollected,
+            PeginStatus::Minted,
+        ];
+        for step in path.windows(2).take_while(|step| step[0] != status) {
+            storage
+                .pegins_dal()
+                .update_pegin_status(id, step[0], step[1])
+                .await
+                .unwrap();
+        }
+        id
+    }
+
+    #[test]
+    fn mapping_errors_to_rpc_errors() {
+        let rejected = BridgeError::Bitcoin(BitcoinRpcError::Rpc {
+            code: -26,
+            message: "bad-txns-in-belowout".to_string(),
+        });
+        assert_matches!(Web3Error::from(rejected), Web3Error::TransactionRejected(_));
+        let warming_up = BridgeError::Bitcoin(BitcoinRpcError::Rpc {
+            code: -28,
+            message: "Loading block index".to_string(),
+        });
+        assert_matches!(
+            Web3Error::from(warming_up),
+            Web3Error::Unavailable {
+                upstream: "bitcoin node"
+            }
+        );
+        let circuit_open = BridgeError::Dal(DalError::CircuitOpen {
+            retry_in: std::time::Duration::from_secs(1),
+        });
+        assert_matches!(
+            Web3Error::from(circuit_open),
+            Web3Error::Unavailable {
+                upstream: "database"
+            }
+        );
+        let transition = PegoutStatus::BurnObserved
+            .transition(PegoutStatus::Reimbursed)
+            .unwrap_err();
+        assert_matches!(
+            Web3Error::from(BridgeError::Dal(transition.into())),
+            Web3Error::InvalidTransition(_)
+        );
+        assert_matches!(
+            Web3Error::from(not_found("pegin", 3)),
+            Web3Error::NotFound { resource: "pegin", id } if id == "3"
+        );
+    }
+
+    #[tokio::test]
+    async fn creating_and_depositing_pegins() {
+        let pool = ConnectionPool::test_pool().await;
+        let backend = MockBitcoinBackend::default();
+        let (bridge, fixture) = setup(&pool, &backend).await;
+
+        let err = bridge
+            .create_pegin(PeginCreateRequest {
+                target_chain_id: 1,
+                ..fixture.pegin_request(100_000)
+            })
+            .await
+            .unwrap_err();
+        assert_matches!(err, BridgeError::UnsupportedChain(1));
+
+        let created = bridge
+            .create_pegin(fixture.pegin_request(100_000))
+            .await
+            .unwrap();
+        assert_eq!(created.deposit_address, DEPOSIT_ADDRESS);
+
+        let funding = OutPoint::new(
+            backend.fund(&address(SENDER_ADDRESS), Amount::from_sat(200_000)),
+            0,
+        );
+        let short = PeginRequest {
+            pegin_id: created.pegin_id,
+            raw_pegin_hex: spend(funding, &[(DEPOSIT_ADDRESS, 50_000)]),
+        };
+        let err = bridge.submit_pegin(short).await.unwrap_err();
+        assert_matches!(err, BridgeError::InvalidRequest(_));
+
+        let raw_pegin_hex = spend(funding, &[(DEPOSIT_ADDRESS, 100_000)]);
+        let pegin_id = bridge
+            .submit_pegin(PeginRequest {
+                pegin_id: created.pegin_id,
+                raw_pegin_hex: raw_pegin_hex.clone(),
+            })
+            .await
+            .unwrap();
+        let pegin = bridge.pegin(pegin_id as i32).await.unwrap();
+        let txid = decode_tx(&raw_pegin_hex).unwrap().txid();
+        assert_eq!(pegin.pegin_tx_hash, Some(txid.to_string()));
+        assert_eq!(pegin.raw_pegin_hex, Some(raw_pegin_hex));
+        assert!(backend.mempool().contains(&txid));
+    }
+
+    #[tokio::test]
+    async fn collecting_presigned_transactions() {
+        let pool = ConnectionPool::test_pool().await;
+        let backend = MockBitcoinBackend::default();
+        let (bridge, fixture) = setup(&pool, &backend).await;
+        let pegin_id = insert_pegin(&pool, &fixture, PeginStatus::Deposited).await;
+        let members = fixture.committee.each_ref().map(public_key);
+        let funding = OutPoint::new(
+            backend.fund(&address(SENDER_ADDRESS), Amount::from_sat(200_000)),
+            0,
+        );
+        let raw_pegin_hex = spend(funding, &[(DEPOSIT_ADDRESS, 100_000)]);
+        let deposit = decode_tx(&raw_pegin_hex).unwrap();
+        let mut storage = pool.access_storage().await.unwrap();
+        storage
+            .pegins_dal()
+            .set_pegin_tx(pegin_id, &deposit.txid().to_string(), &raw_pegin_hex)
+            .await
+            .unwrap();
+        drop(storage);
+
+        let script = bridge
+            .get_pegin_multi_sig_script(&members[1])
+            .await
+            .unwrap();
+        let expected = Builder::new()
+            .push_x_only_key(&parse_x_only_key(&members[0]).unwrap())
+            .push_opcode(OP_CHECKSIGVERIFY)
+            .push_x_only_key(&parse_x_only_key(&members[1]).unwrap())
+            .push_opcode(OP_CHECKSIG)
+            .into_script();
+        assert_eq!(script, expected);
+
+        // The deposit is confirmed once every member accepted it with a request signed by its
+        // own key. Accepting it again is a no-op.
+        let [first, second] = &fixture.committee;
+        let forger = random_key();
+        let accept = |signer: &SecretKey, member: &SecretKey, pegin_id: i32| {
+            let request = PeginReceivedRequest {
+                pegin_id,
+                committee_public_key: public_key(member),
+            };
+            bridge.submit_pegin_received(signed_by(
+                signer,
+                &bridge,
+                "bridge_submitPeginReceived",
+                request,
+            ))
+        };
+        let err = accept(&forger, first, pegin_id).await.unwrap_err();
+        assert_matches!(err, BridgeError::Unauthorized(_));
+        accept(first, first, pegin_id).await.unwrap();
+        accept(first, first, pegin_id).await.unwrap();
+        assert_eq!(
+            bridge.pegin(pegin_id).await.unwrap().status,
+            PeginStatus::Deposited
+        );
+        accept(second, second, pegin_id).await.unwrap();
+        assert_eq!(
+            bridge.pegin(pegin_id).await.unwrap().status,
+            PeginStatus::Confirmed
+        );
+        accept(second, second, pegin_id).await.unwrap();
+        let undeposited = insert_pegin(&pool, &fixture, PeginStatus::Created).await;
+        let err = accept(first, first, undeposited).await.unwrap_err();
+        assert_matches!(err, BridgeError::Conflict(_));
+
+        // The take transaction spends the deposit, the challenge spends the take transaction.
+        let raw_take_tx = spend(OutPoint::new(deposit.txid(), 0), &[(SENDER_ADDRESS, 1)]);
+        let take = decode_tx(&raw_take_tx).unwrap();
+        let raw_challenge_tx = spend(OutPoint::new(take.txid(), 0), &[(SENDER_ADDRESS, 2)]);
+        let presigned_txs = PeginOperatorTaskResponse {
+            pegin_id,
+            operator_public_key: fixture.operator_key(),
+            raw_take_tx: raw_take_tx.clone(),
+            presigned_txs: vec![NewPresignedTransaction {
+                tx_type: PresignedTxType::Challenge,
+                raw_hex: raw_challenge_tx.clone(),
+            }],
+        };
+        bridge
+            .submit_pegin_operator_presigned_transactions(fixture.signed(
+                &bridge,
+                "bridge_submitPeginOperatorPresignedTransactions",
+                presigned_txs,
+            ))
+            .await
+            .unwrap();
+        let take_msg = bridge
+            .query_pegin_take_tx_sign_msg(PeginTakeTxMsgRequest {
+                pegin_id,
+                operator_public_key: fixture.operator_key(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(take_msg.raw_take_tx, raw_take_tx);
+        assert_eq!(take_msg.txid, take.txid().to_string());
+
+        let sign = |secret_key: &SecretKey| {
+            vec![
+                committee_signature(secret_key, &raw_take_tx, &deposit.output[..1], &script),
+                committee_signature(secret_key, &raw_challenge_tx, &take.output, &script),
+            ]
+        };
+        // A well-formed signature made with another key is refused.
+        let err = bridge
+            .submit_pegin_committee_presigned_transactions(PeginCommitteeTaskResponse {
+                pegin_id,
+                committee_public_key: members[0].clone(),
+                signatures: sign(&forger),
+            })
+            .await
+            .unwrap_err();
+        assert_matches!(
+            err,
+            BridgeError::Validation(ValidationError::InvalidSignature(_))
+        );
+
+        for (i, member) in members.iter().enumerate() {
+            assert_eq!(
+                bridge.pegin(pegin_id).await.unwrap().status,
+                PeginStatus::Confirmed
+            );
+            bridge
+                .submit_pegin_committee_presigned_transactions(PeginCommitteeTaskResponse {
+                    pegin_id,
+                    committee_public_key: member.clone(),
+                    signatures: sign(&fixture.committee[i]),
+                })
+                .await
+                .unwrap();
+            if i == 0 {
+                let err = bridge
+                    .submit_pegin_committee_presigned_transactions(PeginCommitteeTaskResponse {
+                        pegin_id,
+                        committee_public_key: member.clone(),
+                        signatures: vec![CommitteeSignature {
+                            txid: "00".repeat(32),
+                            signature: "01".repeat(64),
+                        }],
+                    })
+                    .await
+                    .unwrap_err();
+                assert_matches!(err, BridgeError::InvalidRequest(_));
+            }
+        }
+        assert_eq!(
+            bridge.pegin(pegin_id).await.unwrap().status,
+            PeginStatus::PresignCollected
+        );
+    }
+
+    #[tokio::test]
+    async fn operators_lock_pegins_and_pay_out() {
+        let pool = ConnectionPool::test_pool().await;
+        let backend = MockBitcoinBackend::default();
+        backend.mine_blocks(1, &address(SENDER_ADDRESS));
+        let (bridge, fixture) = setup(&pool, &backend).await;
+        let pegin_id = insert_pegin(&pool, &fixture, PeginStatus::Minted).await;
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let operator = operator(&mut storage, &fixture.operator_key())
+            .await
+            .unwrap();
+        storage
+            .pegins_dal()
+            .insert_pegin_operation(pegin_id, operator.id, "0200", PEGIN_OPERATION_PENDING)
+            .await
+            .unwrap();
+        let pegout_id = storage
+            .pegouts_dal()
+            .insert_pegout(&NewPegout {
+                chain_id: fixture.chain_id,
+                burn_tx_hash: format!("0x{}", random_hash()),
+                event_idx: 0,
+                sender_address: "0x0000000000000000000000000000000000000002".to_string(),
+                receive_address: SENDER_ADDRESS.to_string(),
+                amount: 100_000,
+                status: PegoutStatus::BurnObserved,
+            })
+            .await
+            .unwrap();
+        drop(storage);
+
+        assert_eq!(
+            bridge.get_available_pegout_amounts(1, 10).await.unwrap(),
+            [100_000]
+        );
+        let pending = bridge.query_lp_pending_pegouts().await.unwrap();
+        assert_eq!(pending.len(), 1);
+
+        let request = LPPegoutRequest {
+            pegout_id,
+            operator_public_key: fixture.operator_key(),
+        };
+        // Requests have to be signed by the operator they name, each with a fresh nonce.
+        let forged = signed_by(
+            &random_key(),
+            &bridge,
+            "bridge_tryLockPeginUtxo",
+            request.clone(),
+        );
+        let err = bridge.try_lock_pegin_utxo(forged).await.unwrap_err();
+        assert_matches!(err, BridgeError::Unauthorized(_));
+        let other_method = fixture.signed(&bridge, "bridge_submitPegoutReceived", request.clone());
+        let err = bridge.try_lock_pegin_utxo(other_method).await.unwrap_err();
+        assert_matches!(err, BridgeError::Unauthorized(_));
+        assert_eq!(bridge.pegout(pegout_id).await.unwrap().operator_id, None);
+
+        let lock = fixture.signed(&bridge, "bridge_tryLockPeginUtxo", request.clone());
+        let operation = bridge
+            .try_lock_pegin_utxo(lock.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(operation.pegin_id, pegin_id);
+        let err = bridge.try_lock_pegin_utxo(lock).await.unwrap_err();
+        assert_matches!(err, BridgeError::Unauthorized(_));
+        let lock = fixture.signed(&bridge, "bridge_tryLockPeginUtxo", request);
+        assert_eq!(bridge.try_lock_pegin_utxo(lock).await.unwrap(), None);
+        let pegout = bridge.pegout(pegout_id).await.unwrap();
+        assert_eq!(pegout.status, PegoutStatus::OperatorAssigned);
+        assert_eq!(pegout.deadline_height, Some(1 + 3));
+        assert!(bridge
+            .get_available_pegout_amounts(1, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let err = bridge
+            .submit_operator_pegout_tx(fixture.signed(
+                &bridge,
+                "bridge_submitOperatorPegoutTx",
+                OperatorPegoutTxRequest {
+                    pegout_id,
+                    operator_public_key: fixture.operator_key(),
+                    raw_payout_tx: spend(OutPoint::null(), &[(SENDER_ADDRESS, 98_999)]),
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_matches!(err, BridgeError::InvalidRequest(_));
+        assert_eq!(
+            bridge.pegout(pegout_id).await.unwrap().status,
+            PegoutStatus::OperatorAssigned
+        );
+
+        // A payout the node refuses leaves the peg-out with the operator.
+        let err = bridge
+            .submit_operator_pegout_tx(fixture.signed(
+                &bridge,
+                "bridge_submitOperatorPegoutTx",
+                OperatorPegoutTxRequest {
+                    pegout_id,
+                    operator_public_key: fixture.operator_key(),
+                    raw_payout_tx: spend(OutPoint::null(), &[(SENDER_ADDRESS, 99_000)]),
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_matches!(err, BridgeError::Bitcoin(err) if err.is_tx_rejection());
+        let pegout = bridge.pegout(pegout_id).await.unwrap();
+        assert_eq!(pegout.status, PegoutStatus::OperatorAssigned);
+        assert_eq!(pegout.deadline_height, Some(1 + 3));
+        let mut storage = pool.access_storage().await.unwrap();
+        let transitions = storage
+            .pegouts_dal()
+            .get_status_transitions(pegout_id)
+            .await
+            .unwrap();
+        assert_eq!(
+            transitions
+                .iter()
+                .map(|transition| transition.to_status)
+                .collect::<Vec<_>>(),
+            [
+                PegoutStatus::OperatorAssigned,
+                PegoutStatus::PayoutBroadcast,
+                PegoutStatus::OperatorAssigned
+            ]
+        );
+        drop(storage);
+
+        let funding = OutPoint::new(
+            backend.fund(&address(DEPOSIT_ADDRESS), Amount::from_sat(100_000)),
+            0,
+        );
+        let raw_payout_tx = spend(funding, &[(SENDER_ADDRESS, 99_000)]);
+        let submit_payout = |raw_payout_tx: &str| {
+            bridge.submit_operator_pegout_tx(fixture.signed(
+                &bridge,
+                "bridge_submitOperatorPegoutTx",
+                OperatorPegoutTxRequest {
+                    pegout_id,
+                    operator_public_key: fixture.operator_key(),
+                    raw_payout_tx: raw_payout_tx.to_string(),
+                },
+            ))
+        };
+        // A broadcast that times out may have reached the node, so the payout stays committed.
+        backend.time_out_next_post_tx();
+        let err = submit_payout(&raw_payout_tx).await.unwrap_err();
+        assert_matches!(err, BridgeError::Bitcoin(err) if err.is_transient());
+        let payout_txid = decode_tx(&raw_payout_tx).unwrap().txid();
+        let pegout = bridge.pegout(pegout_id).await.unwrap();
+        assert_eq!(pegout.status, PegoutStatus::PayoutBroadcast);
+        assert_eq!(pegout.payout_tx_hash, Some(payout_txid.to_string()));
+        assert_eq!(pegout.deadline_height, Some(1 + 3));
+        assert!(backend.mempool().contains(&payout_txid));
+
+        // Retrying posts the same payout again, but the operator can't swap it for another one.
+        submit_payout(&raw_payout_tx).await.unwrap();
+        let other_payout = spend(funding, &[(SENDER_ADDRESS, 99_500)]);
+        let err = submit_payout(&other_payout).await.unwrap_err();
+        assert_matches!(err, BridgeError::Conflict(_));
+        let pegout = bridge.pegout(pegout_id).await.unwrap();
+        assert_eq!(pegout.status, PegoutStatus::PayoutBroadcast);
+        assert_eq!(pegout.payout_tx_hash, Some(payout_txid.to_string()));
+
+        let operations = bridge
+            .query_pegout_operations_for_challenger(PegoutOperationRangerRequest {
+                from_pegout_id: 0,
+                len: 10,
+            })
+            .await
+            .unwrap();
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].operator_id, operator.id);
+    }
+
+    #[tokio::test]
+    async fn slashed_operators_cant_lock_pegins() {
+        let pool = ConnectionPool::test_pool().await;
+        let backend = MockBitcoinBackend::default();
+        backend.mine_blocks(1, &address(SENDER_ADDRESS));
+        let (bridge, fixture) = setup(&pool, &backend).await;
+        let pegin_id = insert_pegin(&pool, &fixture, PeginStatus::Minted).await;
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let operator_id = operator(&mut storage, &fixture.operator_key())
+            .await
+            .unwrap()
+            .id;
+        storage
+            .pegins_dal()
+            .insert_pegin_operation(pegin_id, operator_id, "0200", PEGIN_OPERATION_PENDING)
+            .await
+            .unwrap();
+        let pegout_id = storage
+            .pegouts_dal()
+            .insert_pegout(&NewPegout {
+                chain_id: fixture.chain_id,
+                burn_tx_hash: format!("0x{}", random_hash()),
+                event_idx: 0,
+                sender_address: "0x0000000000000000000000000000000000000002".to_string(),
+                receive_address: SENDER_ADDRESS.to_string(),
+                amount: 100_000,
+                status: PegoutStatus::BurnObserved,
+            })
+            .await
+            .unwrap();
+        storage
+            .operators_dal()
+            .slash(operator_id, 100_000)
+            .await
+            .unwrap();
+        drop(storage);
+
+        let request = LPPegoutRequest {
+            pegout_id,
+            operator_public_key: fixture.operator_key(),
+        };
+        let lock = fixture.signed(&bridge, "bridge_tryLockPeginUtxo", request);
+        let err = bridge.try_lock_pegin_utxo(lock).await.unwrap_err();
+        assert_matches!(err, BridgeError::Conflict(_));
+        let pegout = bridge.pegout(pegout_id).await.unwrap();
+        assert_eq!(pegout.status, PegoutStatus::BurnObserved);
+        assert_eq!(pegout.operator_id, None);
+    }
+
+    #[tokio::test]
+    async fn reimbursing_operators() {
+        let pool = ConnectionPool::test_pool().await;
+        let backend = MockBitcoinBackend::default();
+        let (bridge, fixture) = setup(&pool, &backend).await;
+        let pegin_id = insert_pegin(&pool, &fixture, PeginStatus::Minted).await;
+        let funding = OutPoint::new(
+            backend.fund(&address(SENDER_ADDRESS), Amount::from_sat(200_000)),
+            0,
+        );
+        let raw_pegin_hex = spend(funding, &[(DEPOSIT_ADDRESS, 100_000)]);
+        let deposit_txid = backend.post_tx(raw_pegin_hex.clone()).await.unwrap();
+        let raw_take_tx = spend(OutPoint::new(deposit_txid, 0), &[(SENDER_ADDRESS, 99_000)]);
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let operator_id = operator(&mut storage, &fixture.operator_key())
+            .await
+            .unwrap()
+            .id;
+        storage
+            .pegins_dal()
+            .set_pegin_tx(pegin_id, &deposit_txid.to_string(), &raw_pegin_hex)
+            .await
+            .unwrap();
+        storage
+            .pegins_dal()
+            .insert_pegin_operation(pegin_id, operator_id, &raw_take_tx, PEGIN_OPERATION_PENDING)
+            .await
+            .unwrap();
+        let pegout_id = storage
+            .pegouts_dal()
+            .insert_pegout(&NewPegout {
+                chain_id: fixture.chain_id,
+                burn_tx_hash: format!("0x{}", random_hash()),
+                event_idx: 0,
+                sender_address: "0x0000000000000000000000000000000000000002".to_string(),
+                receive_address: SENDER_ADDRESS.to_string(),
+                amount: 100_000,
+                status: PegoutStatus::BurnObserved,
+            })
+            .await
+            .unwrap();
+        storage
+            .pegouts_dal()
+            .assign_operator(pegout_id, pegin_id, operator_id)
+            .await
+            .unwrap();
+        let path = [
+            PegoutStatus::BurnObserved,
+            PegoutStatus::OperatorAssigned,
+            PegoutStatus::PayoutBroadcast,
+            PegoutStatus::PayoutConfirmed,
+            PegoutStatus::KickedOff,
+            PegoutStatus::ChallengeWindowOpen,
+            PegoutStatus::ChallengeWindowClosed,
+        ];
+        for step in path.windows(2) {
+            let transition = PegoutTransition {
+                from: step[0],
+                to: step[1],
+                block_height: None,
+                deadline_height: None,
+            };
+            storage
+                .pegouts_dal()
+                .update_pegout_status(pegout_id, transition)
+                .await
+                .unwrap();
+        }
+        drop(storage);
+
+        let received = |take_txid: Txid| {
+            let request = PegoutReceivedRequest {
+                pegout_id,
+                operator_public_key: fixture.operator_key(),
+                take_tx_hash: take_txid.to_string(),
+            };
+            bridge.submit_pegout_received(fixture.signed(
+                &bridge,
+                "bridge_submitPegoutReceived",
+                request,
+            ))
+        };
+        // Only the take transaction the committee presigned for the operator reimburses it.
+        backend.mine_blocks(1, &address(SENDER_ADDRESS));
+        let err = received(funding.txid).await.unwrap_err();
+        assert_matches!(err, BridgeError::InvalidRequest(_));
+        let take_txid = backend.post_tx(raw_take_tx).await.unwrap();
+        let err = received(take_txid).await.unwrap_err();
+        assert_matches!(err, BridgeError::Conflict(_));
+        assert_eq!(
+            bridge.pegout(pegout_id).await.unwrap().status,
+            PegoutStatus::ChallengeWindowClosed
+        );
+
+        backend.mine_blocks(1, &address(SENDER_ADDRESS));
+        received(take_txid).await.unwrap();
+        assert_eq!(
+            bridge.pegout(pegout_id).await.unwrap().status,
+            PegoutStatus::Reimbursed
+        );
+        let mut storage = pool.access_storage().await.unwrap();
+        let operator = operator(&mut storage, &fixture.operator_key())
+            .await
+            .unwrap();
+        assert_eq!(operator.pegout_cnt, 1);
+    }
+}
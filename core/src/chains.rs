@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use config::evm::{EvmChainConfig, EvmWatcherConfig};
+use dal::connection::ConnectionPool;
+use types::bridge::Bridge;
+
+/// A target EVM chain served by this node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chain {
+    pub bridge: Bridge,
+    /// Endpoint the chain is indexed through, `None` if it is missing from the config.
+    pub evm: Option<EvmChainConfig>,
+}
+
+/// The chains with an active row in `bridges`, keyed by `chain_id`. RPC queries for any other
+/// chain are rejected.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChainRegistry {
+    chains: BTreeMap<i32, Chain>,
+}
+
+impl ChainRegistry {
+    pub fn new(bridges: Vec<Bridge>, config: &EvmWatcherConfig) -> Self {
+        let chains = bridges
+            .into_iter()
+            .map(|bridge| {
+                let evm = config.chain(bridge.chain_id).cloned();
+                if evm.is_none() {
+                    logs::warn!(
+                        "no EVM endpoint configured for chain {}, its events won't be indexed",
+                        bridge.chain_id
+                    );
+                }
+                (bridge.chain_id, Chain { bridge, evm })
+            })
+            .collect::<BTreeMap<_, _>>();
+        for chain in &config.chains {
+            if !chains.contains_key(&chain.chain_id) {
+                logs::warn!(
+                    "EVM endpoint configured for chain {} without an active bridge",
+                    chain.chain_id
+                );
+            }
+        }
+        Self { chains }
+    }
+
+    /// Loads the active bridges from the database.
+    pub async fn load(pool: &ConnectionPool, config: &EvmWatcherConfig) -> anyhow::Result<Self> {
+        let mut storage = pool.access_storage_tagged("chain_registry").await?;
+        let bridges = storage
+            .bridges_dal()
+            .get_active_bridges()
+            .await
+            .context("failed to load active bridges")?;
+        Ok(Self::new(bridges, config))
+    }
+
+    pub fn get(&self, chain_id: i32) -> Option<&Chain> {
+        self.chains.get(&chain_id)
+    }
+
+    pub fn chains(&self) -> impl Iterator<Item = &Chain> {
+        self.chains.values()
+    }
+
+    /// Chains that have an endpoint to index their events from.
+    pub fn indexed_chains(&self) -> impl Iterator<Item = &EvmChainConfig> {
+        self.chains.values().filter_map(|chain| chain.evm.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+
+    use super::*;
+
+    fn bridge(chain_id: i32) -> Bridge {
+        Bridge {
+            id: chain_id,
+            chain_id,
+            chain_name: format!("chain-{chain_id}"),
+            operator_manager_address: "0x0000000000000000000000000000000000000001".to_string(),
+            assertion_taproot_address: String::new(),
+            status: "active".to_string(),
+            bridge_contract_address: None,
+            created_at: NaiveDateTime::default(),
+            updated_at: NaiveDateTime::default(),
+        }
+    }
+
+    fn endpoint(chain_id: i32) -> EvmChainConfig {
+        EvmChainConfig {
+            chain_id,
+            rpc_url: format!("http://127.0.0.1:{}", 8000 + chain_id),
+            confirmations: None,
+            poll_interval_sec: None,
+            max_block_range: None,
+            start_block: None,
+        }
+    }
+
+    #[test]
+    fn matching_bridges_to_endpoints() {
+        let config = EvmWatcherConfig {
+            chains: vec![endpoint(1), endpoint(3)],
+        };
+        let registry = ChainRegistry::new(vec![bridge(1), bridge(2)], &config);
+
+        let chain_ids: Vec<_> = registry
+            .chains()
+            .map(|chain| chain.bridge.chain_id)
+            .collect();
+        assert_eq!(chain_ids, [1, 2]);
+        assert_eq!(registry.get(1).unwrap().evm, Some(endpoint(1)));
+        assert_eq!(registry.get(2).unwrap().evm, None);
+        assert!(registry.get(3).is_none());
+
+        let indexed: Vec<_> = registry
+            .indexed_chains()
+            .map(|chain| chain.chain_id)
+            .collect();
+        assert_eq!(indexed, [1]);
+    }
+}
@@ -0,0 +1,78 @@
+//! Bindings for the events of the contracts deployed per bridge.
+
+use alloy::{
+    primitives::{Address, Log},
+    sol,
+    sol_types::SolEventInterface,
+};
+use types::evm_event::BridgeEvent;
+
+sol! {
+    /// Bridged BTC token, minted for peg-ins and burned for peg-outs.
+    interface IBridge {
+        event Mint(address indexed to, uint256 amount, bytes32 indexed peginTxHash);
+        event Burn(address indexed from, uint256 amount, string btcAddress);
+    }
+
+    interface IOperatorManager {
+        event OperatorRegistered(address indexed operator, bytes publicKey);
+        event OperatorRemoved(address indexed operator);
+    }
+}
+
+/// Contracts of a single bridge whose logs are indexed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BridgeContracts {
+    /// Not set until the bridge contract is deployed.
+    pub bridge: Option<Address>,
+    pub operator_manager: Address,
+}
+
+impl BridgeContracts {
+    pub fn addresses(&self) -> Vec<Address> {
+        self.bridge
+            .into_iter()
+            .chain([self.operator_manager])
+            .collect()
+    }
+
+    /// Decodes a log emitted by one of the contracts. Returns `None` for logs of other contracts
+    /// and for events the bridge doesn't care about.
+    pub fn decode(&self, log: &Log) -> Option<BridgeEvent> {
+        if Some(log.address) == self.bridge {
+            let event = match IBridge::IBridgeEvents::decode_log(log, true).ok()?.data {
+                IBridge::IBridgeEvents::Mint(mint) => BridgeEvent::Mint {
+                    to: mint.to.to_string(),
+                    amount: mint.amount.to_string(),
+                    pegin_tx_hash: mint.peginTxHash.to_string(),
+                },
+                IBridge::IBridgeEvents::Burn(burn) => BridgeEvent::Burn {
+                    from: burn.from.to_string(),
+                    amount: burn.amount.to_string(),
+                    btc_address: burn.btcAddress,
+                },
+            };
+            Some(event)
+        } else if log.address == self.operator_manager {
+            let event = match IOperatorManager::IOperatorManagerEvents::decode_log(log, true)
+                .ok()?
+                .data
+            {
+                IOperatorManager::IOperatorManagerEvents::OperatorRegistered(registered) => {
+                    BridgeEvent::OperatorRegistered {
+                        operator: registered.operator.to_string(),
+                        public_key: hex::encode(&registered.publicKey),
+                    }
+                }
+                IOperatorManager::IOperatorManagerEvents::OperatorRemoved(removed) => {
+                    BridgeEvent::OperatorRemoved {
+                        operator: removed.operator.to_string(),
+                    }
+                }
+            };
+            Some(event)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+
+use alloy::{
+    primitives::{Address, B256, U256},
+    providers::{Provider, RootProvider},
+    rpc::types::{BlockNumberOrTag, Filter, Log},
+    transports::http::{Client, Http},
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+/// Where the EVM watcher reads logs from. Implemented by [`RpcLogSource`] for a live node, and by
+/// [`RecordedLogSource`] for logs recorded from one.
+#[async_trait]
+pub trait EvmLogSource: fmt::Debug + Send + Sync + 'static {
+    async fn block_number(&self) -> anyhow::Result<u64>;
+
+    async fn block_hash(&self, number: u64) -> anyhow::Result<B256>;
+
+    /// Returns the logs of `addresses` in the inclusive block range, in chain order.
+    async fn get_logs(
+        &self,
+        addresses: &[Address],
+        from_block: u64,
+        to_block: u64,
+    ) -> anyhow::Result<Vec<Log>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct RpcLogSource {
+    provider: RootProvider<Http<Client>>,
+}
+
+impl RpcLogSource {
+    pub fn new(rpc_url: &str) -> anyhow::Result<Self> {
+        let url = rpc_url
+            .parse()
+            .with_context(|| format!("invalid EVM RPC URL {rpc_url}"))?;
+        Ok(Self {
+            provider: RootProvider::new_http(url),
+        })
+    }
+}
+
+/// Part of an `eth_getBlockByNumber` response; the rest of the block is not needed.
+#[derive(Debug, Deserialize)]
+struct BlockHeader {
+    hash: B256,
+}
+
+#[async_trait]
+impl EvmLogSource for RpcLogSource {
+    async fn block_number(&self) -> anyhow::Result<u64> {
+        Ok(self.provider.get_block_number().await?)
+    }
+
+    async fn block_hash(&self, number: u64) -> anyhow::Result<B256> {
+        let block: Option<BlockHeader> = self
+            .provider
+            .raw_request(
+                "eth_getBlockByNumber".into(),
+                (BlockNumberOrTag::Number(number), false),
+            )
+            .await?;
+        Ok(block
+            .with_context(|| format!("block {number} not found"))?
+            .hash)
+    }
+
+    async fn get_logs(
+        &self,
+        addresses: &[Address],
+        from_block: u64,
+        to_block: u64,
+    ) -> anyhow::Result<Vec<Log>> {
+        let filter = Filter::new()
+            .address(addresses.to_vec())
+            .from_block(from_block)
+            .to_block(to_block);
+        Ok(self.provider.get_logs(&filter).await?)
+    }
+}
+
+/// Serves a fixed set of logs, e.g. recorded from anvil with `cast logs --json`, up to a fixed
+/// chain head.
+#[derive(Debug, Clone, Default)]
+pub struct RecordedLogSource {
+    head: u64,
+    logs: Vec<Log>,
+}
+
+impl RecordedLogSource {
+    pub fn new(head: u64, logs: Vec<Log>) -> Self {
+        Self { head, logs }
+    }
+
+    /// Parses logs in the format of `eth_getLogs` responses.
+    pub fn from_json(head: u64, json: &str) -> anyhow::Result<Self> {
+        let logs = serde_json::from_str(json).context("invalid recorded logs")?;
+        Ok(Self::new(head, logs))
+    }
+}
+
+#[async_trait]
+impl EvmLogSource for RecordedLogSource {
+    async fn block_number(&self) -> anyhow::Result<u64> {
+        Ok(self.head)
+    }
+
+    async fn block_hash(&self, number: u64) -> anyhow::Result<B256> {
+        anyhow::ensure!(number <= self.head, "block {number} not found");
+        let recorded = self
+            .logs
+            .iter()
+            .find(|log| log.block_number == Some(number))
+            .and_then(|log| log.block_hash);
+        // Blocks without recorded logs get a made up, but stable hash.
+        Ok(recorded.unwrap_or_else(|| U256::from(number).into()))
+    }
+
+    async fn get_logs(
+        &self,
+        addresses: &[Address],
+        from_block: u64,
+        to_block: u64,
+    ) -> anyhow::Result<Vec<Log>> {
+        let mut logs: Vec<_> = self
+            .logs
+            .iter()
+            .filter(|log| addresses.contains(&log.inner.address))
+            .filter(|log| {
+                log.block_number
+                    .is_some_and(|number| (from_block..=to_block).contains(&number))
+            })
+            .cloned()
+            .collect();
+        logs.sort_by_key(|log| (log.block_number, log.log_index));
+        Ok(logs)
+    }
+}
@@ -0,0 +1,424 @@
+use std::sync::Arc;
+
+use alloy::{primitives::Address, rpc::types::Log};
+use anyhow::Context;
+use config::evm::EvmChainConfig;
+use dal::{connection::ConnectionPool, StorageProcessor};
+use serde_json::json;
+use tokio::sync::watch;
+use types::{
+    bridge::Bridge,
+    evm_event::{NewEvmEvent, NewEvmTransaction, EVM_EVENT_UNPROCESSED, EVM_TX_CONFIRMED},
+};
+
+pub use self::{
+    contracts::BridgeContracts,
+    log_source::{EvmLogSource, RecordedLogSource, RpcLogSource},
+};
+
+pub mod contracts;
+mod log_source;
+
+/// Indexes the events of the contracts of the bridge for `chain_id` into `events`, together with
+/// the emitting transactions in `evm_transactions`.
+///
+/// Logs are only read once their block is `confirmations` deep, so the watcher doesn't have to
+/// handle reorgs. Each range of blocks is stored in a single database transaction together with
+/// the `evm:<chain_id>` cursor, and re-indexing a log is a no-op.
+#[derive(Debug)]
+pub struct EvmWatcher {
+    source: Arc<dyn EvmLogSource>,
+    pool: ConnectionPool,
+    config: EvmChainConfig,
+}
+
+impl EvmWatcher {
+    pub fn new(
+        source: Arc<dyn EvmLogSource>,
+        pool: ConnectionPool,
+        config: EvmChainConfig,
+    ) -> Self {
+        Self {
+            source,
+            pool,
+            config,
+        }
+    }
+
+    fn cursor_name(&self) -> String {
+        format!("evm:{}", self.config.chain_id)
+    }
+
+    pub async fn run(self, mut stop_receiver: watch::Receiver<bool>) -> anyhow::Result<()> {
+        let mut timer = tokio::time::interval(self.config.poll_interval());
+        loop {
+            tokio::select! {
+                _ = timer.tick() => {}
+                _ = stop_receiver.changed() => break,
+            }
+            if *stop_receiver.borrow() {
+                break;
+            }
+            if let Err(err) = self.sync().await {
+                logs::warn!(
+                    "EVM watcher for chain {} failed to sync: {err:#}",
+                    self.config.chain_id
+                );
+            }
+        }
+        logs::info!(
+            "Stop signal received, EVM watcher for chain {} is shutting down",
+            self.config.chain_id
+        );
+        Ok(())
+    }
+
+    /// Indexes all confirmed blocks after the cursor.
+    pub async fn sync(&self) -> anyhow::Result<()> {
+        let head = self.source.block_number().await?;
+        let Some(confirmed_head) = head.checked_sub(self.config.confirmations()) else {
+            return Ok(());
+        };
+        let mut storage = self.pool.access_storage_tagged("evm_watcher").await?;
+        let bridge = storage
+            .bridges_dal()
+            .get_bridge_by_chain_id(self.config.chain_id)
+            .await?;
+        let Some(bridge) = bridge.filter(|bridge| bridge.status == "active") else {
+            logs::warn!("no active bridge for chain {}", self.config.chain_id);
+            return Ok(());
+        };
+        let contracts = Self::contracts(&bridge)?;
+
+        let cursor_name = self.cursor_name();
+        let cursor = storage.sync_cursors_dal().get_cursor(&cursor_name).await?;
+        let mut from_block = match cursor {
+            Some(cursor) => cursor.block_number as u64 + 1,
+            None => self.config.start_block.unwrap_or(confirmed_head),
+        };
+        while from_block <= confirmed_head {
+            let to_block = confirmed_head.min(from_block + self.config.max_block_range() - 1);
+            self.index_blocks(&mut storage, &contracts, from_block, to_block)
+                .await
+                .with_context(|| format!("failed to index blocks {from_block}..={to_block}"))?;
+            from_block = to_block + 1;
+        }
+        Ok(())
+    }
+
+    fn contracts(bridge: &Bridge) -> anyhow::Result<BridgeContracts> {
+        let operator_manager: Address = bridge
+            .operator_manager_address
+            .parse()
+            .context("invalid operator manager address")?;
+        let bridge_contract = bridge
+            .bridge_contract_address
+            .as_deref()
+            .map(str::parse::<Address>)
+            .transpose()
+            .context("invalid bridge contract address")?;
+        Ok(BridgeContracts {
+            bridge: bridge_contract,
+            operator_manager,
+        })
+    }
+
+    async fn index_blocks(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        contracts: &BridgeContracts,
+        from_block: u64,
+        to_block: u64,
+    ) -> anyhow::Result<()> {
+        let logs = self
+            .source
+            .get_logs(&contracts.addresses(), from_block, to_block)
+            .await?;
+        let to_block_hash = self.source.block_hash(to_block).await?;
+
+        let mut transaction = storage.start_transaction().await?;
+        let mut new_events = 0;
+        for log in &logs {
+            if self.store_log(&mut transaction, contracts, log).await? {
+                new_events += 1;
+            }
+        }
+        transaction
+            .sync_cursors_dal()
+            .set_cursor(
+                &self.cursor_name(),
+                to_block as i64,
+                &to_block_hash.to_string(),
+            )
+            .await?;
+        transaction.commit().await?;
+
+        if new_events > 0 {
+            logs::info!(
+                "indexed {new_events} events of chain {} in blocks {from_block}..={to_block}",
+                self.config.chain_id
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns `false` for logs that are not bridge events or are already stored.
+    async fn store_log(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        contracts: &BridgeContracts,
+        log: &Log,
+    ) -> anyhow::Result<bool> {
+        let Some(event) = contracts.decode(&log.inner) else {
+            logs::debug!("skipping unknown log {log:?}");
+            return Ok(false);
+        };
+        let (Some(tx_hash), Some(log_index), Some(block_number)) =
+            (log.transaction_hash, log.log_index, log.block_number)
+        else {
+            anyhow::bail!("log of a pending block: {log:?}");
+        };
+        let tx_hash = tx_hash.to_string();
+
+        let inserted = storage
+            .events_dal()
+            .insert_event(&NewEvmEvent {
+                chain_id: self.config.chain_id,
+                tx_hash: tx_hash.clone(),
+                event_idx: log_index as i32,
+                event_type: event.event_type().to_string(),
+                data: serde_json::to_string(&event)?,
+                status: EVM_EVENT_UNPROCESSED.to_string(),
+                block_number: block_number as i64,
+            })
+            .await?;
+        // The transaction is typed by its first bridge event.
+        let location = json!({
+            "block_number": block_number,
+            "block_hash": log.block_hash,
+            "transaction_index": log.transaction_index,
+        });
+        storage
+            .evm_transactions_dal()
+            .insert_evm_transaction(&NewEvmTransaction {
+                chain_id: self.config.chain_id,
+                tx_hash,
+                tx_type: event.event_type().to_string(),
+                status: EVM_TX_CONFIRMED.to_string(),
+                data: location.to_string(),
+                extra_data: None,
+            })
+            .await?;
+        Ok(inserted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::{Bytes, B256, U256},
+        sol_types::SolEvent,
+    };
+    use bitcoin::secp256k1::rand::{self, Rng};
+    use types::{bridge::NewBridge, evm_event::BridgeEvent};
+
+    use super::{
+        contracts::{IBridge, IOperatorManager},
+        *,
+    };
+
+    const BRIDGE: Address = Address::repeat_byte(0xb1);
+    const OPERATOR_MANAGER: Address = Address::repeat_byte(0x0a);
+    const USER: Address = Address::repeat_byte(0x01);
+
+    fn config(chain_id: i32) -> EvmChainConfig {
+        EvmChainConfig {
+            rpc_url: "http://127.0.0.1:8545".to_string(),
+            chain_id,
+            confirmations: Some(2),
+            poll_interval_sec: Some(1),
+            max_block_range: Some(4),
+            start_block: Some(1),
+        }
+    }
+
+    fn log(
+        address: Address,
+        event: &impl SolEvent,
+        block_number: u64,
+        log_index: u64,
+        tx_hash: B256,
+    ) -> Log {
+        Log {
+            inner: alloy::primitives::Log {
+                address,
+                data: event.encode_log_data(),
+            },
+            block_hash: Some(B256::with_last_byte(block_number as u8)),
+            block_number: Some(block_number),
+            block_timestamp: None,
+            transaction_hash: Some(tx_hash),
+            transaction_index: Some(0),
+            log_index: Some(log_index),
+            removed: false,
+        }
+    }
+
+    fn recorded_logs() -> Vec<Log> {
+        let mint = IBridge::Mint {
+            to: USER,
+            amount: U256::from(100_000),
+            peginTxHash: B256::repeat_byte(0xaa),
+        };
+        let burn = IBridge::Burn {
+            from: USER,
+            amount: U256::from(40_000),
+            btcAddress: "bcrt1qtest".to_string(),
+        };
+        let registered = IOperatorManager::OperatorRegistered {
+            operator: USER,
+            publicKey: Bytes::from(vec![2; 33]),
+        };
+        vec![
+            log(BRIDGE, &mint, 5, 0, B256::repeat_byte(1)),
+            log(BRIDGE, &burn, 5, 1, B256::repeat_byte(1)),
+            // Same event signature, but not emitted by the bridge.
+            log(
+                Address::repeat_byte(0xee),
+                &burn,
+                6,
+                0,
+                B256::repeat_byte(2),
+            ),
+            log(OPERATOR_MANAGER, &registered, 8, 3, B256::repeat_byte(3)),
+            log(BRIDGE, &burn, 12, 0, B256::repeat_byte(4)),
+        ]
+    }
+
+    /// Inserts a bridge on a random chain, so that tests running concurrently don't share events.
+    async fn insert_bridge(pool: &ConnectionPool) -> i32 {
+        let chain_id = rand::thread_rng().gen_range(1..i32::MAX);
+        let mut storage = pool.access_storage().await.unwrap();
+        storage
+            .bridges_dal()
+            .insert_bridge(&NewBridge {
+                chain_id,
+                chain_name: "anvil".to_string(),
+                operator_manager_address: OPERATOR_MANAGER.to_string(),
+                assertion_taproot_address: "bcrt1q".to_string(),
+                status: "active".to_string(),
+                bridge_contract_address: Some(BRIDGE.to_string()),
+            })
+            .await
+            .unwrap();
+        chain_id
+    }
+
+    #[tokio::test]
+    async fn indexing_confirmed_events() {
+        let pool = ConnectionPool::test_pool().await;
+        let chain_id = insert_bridge(&pool).await;
+        let source = RecordedLogSource::new(12, recorded_logs());
+        let watcher = EvmWatcher::new(Arc::new(source), pool.clone(), config(chain_id));
+        watcher.sync().await.unwrap();
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let events = storage
+            .events_dal()
+            .get_events_by_tx_hash(chain_id, &B256::repeat_byte(1).to_string())
+            .await
+            .unwrap();
+        let decoded: Vec<BridgeEvent> = events
+            .iter()
+            .map(|event| serde_json::from_str(&event.data).unwrap())
+            .collect();
+        assert_eq!(
+            decoded,
+            [
+                BridgeEvent::Mint {
+                    to: USER.to_string(),
+                    amount: "100000".to_string(),
+                    pegin_tx_hash: B256::repeat_byte(0xaa).to_string(),
+                },
+                BridgeEvent::Burn {
+                    from: USER.to_string(),
+                    amount: "40000".to_string(),
+                    btc_address: "bcrt1qtest".to_string(),
+                },
+            ]
+        );
+        let tx = storage
+            .evm_transactions_dal()
+            .get_evm_transaction(chain_id, &B256::repeat_byte(1).to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.tx_type, "mint");
+
+        let registered = storage
+            .events_dal()
+            .get_events_by_status(chain_id, "operator_registered", EVM_EVENT_UNPROCESSED, 10)
+            .await
+            .unwrap();
+        assert_eq!(registered.len(), 1);
+        assert_eq!(registered[0].block_number, Some(8));
+        // The foreign log and the burn above the confirmed head are not indexed.
+        for tx_hash in [B256::repeat_byte(2), B256::repeat_byte(4)] {
+            let events = storage
+                .events_dal()
+                .get_events_by_tx_hash(chain_id, &tx_hash.to_string())
+                .await
+                .unwrap();
+            assert!(events.is_empty());
+        }
+        let cursor = storage
+            .sync_cursors_dal()
+            .get_cursor(&format!("evm:{chain_id}"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cursor.block_number, 10);
+        drop(storage);
+
+        // A restarted watcher resumes from the cursor once the chain moves on.
+        let source = RecordedLogSource::new(14, recorded_logs());
+        let watcher = EvmWatcher::new(Arc::new(source), pool.clone(), config(chain_id));
+        watcher.sync().await.unwrap();
+        let mut storage = pool.access_storage().await.unwrap();
+        let events = storage
+            .events_dal()
+            .get_events_by_status(chain_id, "burn", EVM_EVENT_UNPROCESSED, 10)
+            .await
+            .unwrap();
+        let blocks: Vec<_> = events.iter().map(|event| event.block_number).collect();
+        assert_eq!(blocks, [Some(5), Some(12)]);
+    }
+
+    #[tokio::test]
+    async fn reindexing_is_idempotent() {
+        let pool = ConnectionPool::test_pool().await;
+        let chain_id = insert_bridge(&pool).await;
+        let source = Arc::new(RecordedLogSource::new(12, recorded_logs()));
+        let contracts = BridgeContracts {
+            bridge: Some(BRIDGE),
+            operator_manager: OPERATOR_MANAGER,
+        };
+        let watcher = EvmWatcher::new(source, pool.clone(), config(chain_id));
+
+        let mut storage = pool.access_storage().await.unwrap();
+        watcher
+            .index_blocks(&mut storage, &contracts, 1, 10)
+            .await
+            .unwrap();
+        watcher
+            .index_blocks(&mut storage, &contracts, 5, 8)
+            .await
+            .unwrap();
+        let events = storage
+            .events_dal()
+            .get_events_by_tx_hash(chain_id, &B256::repeat_byte(1).to_string())
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 2);
+    }
+}
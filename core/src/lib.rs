@@ -1,18 +1,54 @@
-use std::time::Instant;
+use std::{collections::HashSet, sync::Arc, time::Instant};
 
 use anyhow::Context;
-use bitcoin::Network;
-use config::api::{ApiConfig, HealthCheckConfig};
-use dal::connection::{ConnectionPool, DbVariant};
+use bitcoin_client::{health::BitcoinHealthTask, BitcoinRpcClient, RetryPolicy};
+use bitcoin_watcher::BitcoinWatcher;
+use bridge::BridgeService;
+use chains::ChainRegistry;
+use config::{
+    api::{ApiConfig, HealthCheckConfig},
+    database::DatabaseConfig,
+    evm::EvmWatcherConfig,
+    pegout::PegoutConfig,
+};
+use dal::{
+    connection::{ConnectionPool, DbVariant, ReplicaRouting},
+    health::DatabaseHealthTask,
+};
+use evm_watcher::{EvmWatcher, RpcLogSource};
 use health_check::{healthcheck::HealthCheckHandle, CheckHealth};
+use pegout_driver::PegoutDriver;
 use server::{ApiBuilder, Namespace};
 use test::Test;
-use tokio::{sync::watch, task::JoinHandle};
+use tokio::{
+    sync::{broadcast, watch},
+    task::JoinHandle,
+};
 
+pub mod bitcoin_watcher;
+pub mod bridge;
+pub mod chains;
+pub mod evm_watcher;
+mod nonces;
+pub mod pegout_driver;
 pub mod server;
 pub mod test;
 
 pub async fn genesis_init() -> anyhow::Result<()> {
+    let started_at = Instant::now();
+    logs::info!("running database migrations");
+
+    let db_config = DatabaseConfig::load_config().context("failed to load database config")?;
+    let connection_pool = ConnectionPool::singleton(&db_config, DbVariant::Master)
+        .build()
+        .await
+        .context("failed to connect to the database")?;
+    connection_pool
+        .run_migrations()
+        .await
+        .context("genesis_init")?;
+
+    logs::info!("database migrations finished in {:?}", started_at.elapsed());
     Ok(())
 }
 
@@ -24,10 +60,115 @@ pub async fn initialize_tasks() -> anyhow::Result<(
     let (stop_sender, stop_receiver) = watch::channel(false);
     let mut task_futures: Vec<JoinHandle<anyhow::Result<()>>> = vec![];
     let mut healthchecks: Vec<Box<dyn CheckHealth>> = Vec::new();
-    let connection_pool = ConnectionPool::builder(DbVariant::Master).build().await;
+    let db_config = DatabaseConfig::load_config().expect("failed to load database config");
+    let mut pool_builder = ConnectionPool::builder(&db_config, DbVariant::Master);
+    if db_config.replica_url.is_some() {
+        pool_builder.set_replica_routing(Some(ReplicaRouting {
+            read_only_requesters: HashSet::from([bridge::HISTORY_REQUESTER]),
+            max_replication_lag: db_config.max_replication_lag(),
+        }));
+    }
+    let connection_pool = pool_builder
+        .build()
+        .await
+        .context("failed to connect to the database")?;
+    if let Some(circuit_breaker) = connection_pool.circuit_breaker() {
+        healthchecks.push(Box::new(circuit_breaker.clone()));
+    }
     let api_config = ApiConfig::load_config().expect("failed to load api config");
+    let network = api_config.bitcoin_rpc.network();
     let test = Test::new();
 
+    // Database health check
+    {
+        let (db_health_task, db_health_check) =
+            DatabaseHealthTask::new(connection_pool.clone(), db_config.health_check_interval());
+        task_futures.push(tokio::spawn(db_health_task.run(stop_receiver.clone())));
+        healthchecks.push(Box::new(db_health_check));
+    }
+    let bitcoin_rpc = &api_config.bitcoin_rpc;
+    let bitcoin_client = BitcoinRpcClient::new(
+        &bitcoin_rpc.http_url,
+        &bitcoin_rpc.rpc_user,
+        &bitcoin_rpc.rpc_password,
+    )
+    .context("failed to create Bitcoin RPC client")?
+    .with_timeout(bitcoin_rpc.request_timeout())
+    .with_retry_policy(RetryPolicy {
+        max_retries: bitcoin_rpc.max_retries(),
+        ..RetryPolicy::default()
+    });
+    // Bitcoin node health check
+    {
+        let (bitcoin_health_task, bitcoin_health_check) = BitcoinHealthTask::new(
+            bitcoin_client.clone(),
+            bitcoin_rpc.health_check_interval(),
+            bitcoin_rpc.max_block_age(),
+        );
+        task_futures.push(tokio::spawn(bitcoin_health_task.run(stop_receiver.clone())));
+        healthchecks.push(Box::new(bitcoin_health_check));
+    }
+    // Reorgs found by the Bitcoin block scanner, forwarded to pubsub subscribers.
+    let (bitcoin_reorgs, _) = broadcast::channel(128);
+    // Bitcoin block scanner
+    {
+        let bitcoin_watcher = BitcoinWatcher::new(
+            Arc::new(bitcoin_client.wallet(bitcoin_rpc.wallet())),
+            connection_pool.clone(),
+            network,
+            bitcoin_rpc.confirms_threshold,
+            bitcoin_rpc.scan_interval(),
+            bitcoin_rpc.start_block_height,
+        )
+        .with_reorg_notifications(bitcoin_reorgs.clone());
+        task_futures.push(tokio::spawn(bitcoin_watcher.run(stop_receiver.clone())));
+    }
+    let pegout_config = PegoutConfig::load_config().context("failed to load pegout config")?;
+    // Peg-out driver
+    {
+        let pegout_driver = PegoutDriver::new(
+            Arc::new(bitcoin_client.wallet(bitcoin_rpc.wallet())),
+            connection_pool.clone(),
+            network,
+            pegout_config.clone(),
+            bitcoin_rpc.confirms_threshold,
+        );
+        task_futures.push(tokio::spawn(pegout_driver.run(stop_receiver.clone())));
+    }
+    // Target EVM chains, one per active bridge.
+    let evm_config =
+        EvmWatcherConfig::load_config().context("failed to load EVM watcher config")?;
+    let chains = Arc::new(
+        ChainRegistry::load(&connection_pool, &evm_config)
+            .await
+            .context("failed to load target chains")?,
+    );
+    // EVM event indexers
+    for chain_config in chains.indexed_chains() {
+        let log_source = RpcLogSource::new(&chain_config.rpc_url)
+            .with_context(|| format!("invalid RPC URL for chain {}", chain_config.chain_id))?;
+        let evm_watcher = EvmWatcher::new(
+            Arc::new(log_source),
+            connection_pool.clone(),
+            chain_config.clone(),
+        );
+        task_futures.push(tokio::spawn(evm_watcher.run(stop_receiver.clone())));
+    }
+    logs::info!(
+        "serving chains {:?}",
+        chains
+            .chains()
+            .map(|chain| chain.bridge.chain_id)
+            .collect::<Vec<_>>()
+    );
+    let bridge = BridgeService::new(
+        connection_pool.clone(),
+        chains.clone(),
+        Arc::new(bitcoin_client.wallet(bitcoin_rpc.wallet())),
+        network,
+        pegout_config,
+    );
+
     // Http server
     {
         let started_at = Instant::now();
@@ -35,12 +176,21 @@ pub async fn initialize_tasks() -> anyhow::Result<(
 
         let http_server_handles = ApiBuilder::jsonrpsee_backend(connection_pool.clone())
             .http(api_config.web3_json_rpc.http_port)
+            .with_max_connections(api_config.web3_json_rpc.max_connections())
+            .with_max_connections_per_ip(api_config.web3_json_rpc.max_connections_per_ip())
+            .with_requests_per_minute_per_ip(api_config.web3_json_rpc.requests_per_minute_per_ip())
+            .with_requests_per_minute_per_method(
+                api_config.web3_json_rpc.requests_per_minute_per_method(),
+            )
+            .with_method_weights(api_config.web3_json_rpc.method_weights())
+            .with_trusted_proxies(api_config.web3_json_rpc.trusted_proxies())
             .with_batch_request_size_limit(api_config.web3_json_rpc.max_batch_request_size())
             .with_response_body_size_limit(api_config.web3_json_rpc.max_response_body_size())
-            .enable_api_namespaces(vec![Namespace::Bridge])
+            .with_chains(chains.clone())
+            .enable_api_namespaces(vec![Namespace::Bridge, Namespace::Chain])
             .build()
             .context("failed to build HTTP JSON-RPC server")?
-            .run(test.clone(), Network::Regtest, stop_receiver.clone())
+            .run(test.clone(), bridge.clone(), network, stop_receiver.clone())
             .await
             .context("Failed initializing HTTP JSON-RPC server")?;
 
@@ -55,16 +205,25 @@ pub async fn initialize_tasks() -> anyhow::Result<(
 
         let server_handles = ApiBuilder::pubsub_backend(connection_pool.clone())
             .ws(api_config.web3_json_rpc.ws_port)
-            // .with_filters_limit(api_config.web3_json_rpc.filters_limit())
-            // .with_subscriptions_limit(api_config.web3_json_rpc.subscriptions_limit())
+            .with_max_connections(api_config.web3_json_rpc.max_connections())
+            .with_max_connections_per_ip(api_config.web3_json_rpc.max_connections_per_ip())
+            .with_requests_per_minute_per_ip(api_config.web3_json_rpc.requests_per_minute_per_ip())
+            .with_requests_per_minute_per_method(
+                api_config.web3_json_rpc.requests_per_minute_per_method(),
+            )
+            .with_method_weights(api_config.web3_json_rpc.method_weights())
+            .with_trusted_proxies(api_config.web3_json_rpc.trusted_proxies())
+            .with_filters_limit(api_config.web3_json_rpc.filters_limit())
+            .with_subscriptions_limit(api_config.web3_json_rpc.subscriptions_limit())
             .with_batch_request_size_limit(api_config.web3_json_rpc.max_batch_request_size())
             .with_response_body_size_limit(api_config.web3_json_rpc.max_response_body_size())
             .with_polling_interval(api_config.web3_json_rpc.pubsub_interval())
             .with_threads(api_config.web3_json_rpc.ws_server_threads())
+            .with_bitcoin_reorgs(bitcoin_reorgs)
             .enable_api_namespaces(vec![Namespace::Pubsub])
             .build()
             .context("failed to build Websocket server")?
-            .run(test.clone(), Network::Regtest, stop_receiver.clone())
+            .run(test.clone(), bridge.clone(), network, stop_receiver.clone())
             .await
             .context("run_pubsub_api")?;
 
@@ -0,0 +1,99 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bitcoin::secp256k1::rand;
+
+use crate::server::limits::{Counter, Permit};
+
+/// Time a client has to use a nonce it was issued.
+pub(crate) const NONCE_TTL: Duration = Duration::from_secs(60);
+/// Maximum number of unused nonces of a client IP address, so that a client requesting them
+/// can't exhaust memory or keep others from getting any.
+const MAX_NONCES_PER_IP: usize = 16;
+
+/// Single-use nonces that clients sign to prove who they are, e.g. in
+/// [`types::pubsub::SubscriptionAuth`]. Each unused nonce holds a permit of the client it was
+/// issued to until it is used or expires.
+#[derive(Debug)]
+pub(crate) struct Nonces {
+    per_ip: Arc<Counter<IpAddr>>,
+    issued: Mutex<HashMap<String, (Instant, Permit<IpAddr>)>>,
+}
+
+impl Default for Nonces {
+    fn default() -> Self {
+        Self {
+            per_ip: Counter::new(Some(MAX_NONCES_PER_IP)),
+            issued: Mutex::default(),
+        }
+    }
+}
+
+impl Nonces {
+    /// Returns a new nonce for `client`, or `None` if too many of its nonces are waiting to be
+    /// used.
+    pub(crate) fn issue(&self, client: IpAddr) -> Option<String> {
+        let mut issued = self.issued.lock().unwrap();
+        let now = Instant::now();
+        issued.retain(|_, (expires_at, _)| *expires_at > now);
+        let permit = self.per_ip.try_acquire(client).ok()?;
+        let nonce = hex::encode(rand::random::<[u8; 32]>());
+        issued.insert(nonce.clone(), (now + NONCE_TTL, permit));
+        Some(nonce)
+    }
+
+    /// Consumes `nonce`, returning whether it was issued and hasn't expired.
+    pub(crate) fn take(&self, nonce: &str) -> bool {
+        let issued = self.issued.lock().unwrap().remove(nonce);
+        issued.is_some_and(|(expires_at, _)| expires_at > Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+    const BOB: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
+
+    #[test]
+    fn nonces_are_single_use() {
+        let nonces = Nonces::default();
+        let nonce = nonces.issue(ALICE).unwrap();
+        assert_eq!(nonce.len(), 64);
+        assert_ne!(nonces.issue(ALICE).unwrap(), nonce);
+
+        assert!(nonces.take(&nonce));
+        assert!(!nonces.take(&nonce));
+        assert!(!nonces.take("00"));
+
+        let expired = "ff".repeat(32);
+        let permit = nonces.per_ip.try_acquire(ALICE).unwrap();
+        nonces
+            .issued
+            .lock()
+            .unwrap()
+            .insert(expired.clone(), (Instant::now(), permit));
+        assert!(!nonces.take(&expired));
+    }
+
+    #[test]
+    fn capping_nonces_per_ip() {
+        let nonces = Nonces::default();
+        let issued: Vec<_> = (0..MAX_NONCES_PER_IP)
+            .map(|_| nonces.issue(ALICE).unwrap())
+            .collect();
+        assert_eq!(nonces.issue(ALICE), None);
+        // Other clients aren't affected.
+        nonces.issue(BOB).unwrap();
+
+        // Using a nonce lets the client have another one.
+        assert!(nonces.take(&issued[0]));
+        nonces.issue(ALICE).unwrap();
+        assert_eq!(nonces.issue(ALICE), None);
+    }
+}
@@ -0,0 +1,1159 @@
+use std::{str::FromStr, sync::Arc};
+
+use anyhow::Context;
+use bitcoin::{Network, Txid};
+use bitcoin_client::BitcoinBackend;
+use config::pegout::PegoutConfig;
+use dal::{connection::ConnectionPool, StorageProcessor};
+use tokio::sync::watch;
+use types::{
+    challenger::{Challenge, ChallengeStatus},
+    evm_event::{
+        BridgeEvent, EvmEvent, EVM_EVENT_INVALID, EVM_EVENT_PROCESSED, EVM_EVENT_UNPROCESSED,
+    },
+    pegout::{NewPegout, PegoutDetail, PegoutStatus, PegoutTransition},
+};
+
+/// Peg-outs handled per status and tick.
+const BATCH_SIZE: u32 = 100;
+
+/// Advances peg-outs through the steps that follow from the EVM and Bitcoin chains:
+///
+/// - an unprocessed burn event creates a `BurnObserved` peg-out;
+/// - an `OperatorAssigned` peg-out goes back to `BurnObserved` once its payout deadline passes,
+///   releasing the locked peg-in UTXO;
+/// - a `PayoutBroadcast` peg-out goes back to `OperatorAssigned` once its payout deadline passes
+///   if the node doesn't know the payout, e.g. because it was double-spent or evicted from the
+///   mempool, and is released with it;
+/// - the payout and kickoff transactions are confirmed after `confirms_threshold` blocks, the
+///   latter opening a challenge window of `challenge_period_blocks`;
+/// - a challenge spending the kickoff that is included before the deadline and confirmed slashes
+///   the operator;
+/// - an unchallenged window is closed once the chain passes its deadline.
+///
+/// The remaining steps are driven by operators and challengers. Every transition is recorded in
+/// `pegout_status_transitions`.
+#[derive(Debug)]
+pub struct PegoutDriver {
+    backend: Arc<dyn BitcoinBackend>,
+    pool: ConnectionPool,
+    network: Network,
+    config: PegoutConfig,
+    confirms_threshold: u32,
+}
+
+impl PegoutDriver {
+    pub fn new(
+        backend: Arc<dyn BitcoinBackend>,
+        pool: ConnectionPool,
+        network: Network,
+        config: PegoutConfig,
+        confirms_threshold: u32,
+    ) -> Self {
+        Self {
+            backend,
+            pool,
+            network,
+            config,
+            confirms_threshold,
+        }
+    }
+
+    pub async fn run(self, mut stop_receiver: watch::Receiver<bool>) -> anyhow::Result<()> {
+        let mut timer = tokio::time::interval(self.config.poll_interval());
+        loop {
+            tokio::select! {
+                _ = timer.tick() => {}
+                _ = stop_receiver.changed() => break,
+            }
+            if *stop_receiver.borrow() {
+                break;
+            }
+            if let Err(err) = self.advance().await {
+                logs::warn!("pegout driver failed to advance pegouts: {err:#}");
+            }
+        }
+        logs::info!("Stop signal received, pegout driver is shutting down");
+        Ok(())
+    }
+
+    pub async fn advance(&self) -> anyhow::Result<()> {
+        let height = self.backend.get_block_count().await? as i64;
+        let mut storage = self.pool.access_storage_tagged("pegout_driver").await?;
+        self.observe_burns(&mut storage).await?;
+        self.expire_payouts(&mut storage, height).await?;
+        self.release_expired_assignments(&mut storage, height)
+            .await?;
+        self.confirm_payouts(&mut storage, height).await?;
+        self.open_challenge_windows(&mut storage, height).await?;
+        self.settle_challenges(&mut storage, height).await?;
+        self.close_challenge_windows(&mut storage, height).await?;
+        Ok(())
+    }
+
+    /// Creates a peg-out for every unprocessed burn event of an active bridge. Burns that can't
+    /// be paid out, e.g. to an invalid Bitcoin address, are marked `invalid`.
+    async fn observe_burns(&self, storage: &mut StorageProcessor<'_>) -> anyhow::Result<()> {
+        for bridge in storage.bridges_dal().get_active_bridges().await? {
+            let burns = storage
+                .events_dal()
+                .get_events_by_status(bridge.chain_id, "burn", EVM_EVENT_UNPROCESSED, BATCH_SIZE)
+                .await?;
+            for event in burns {
+                let mut transaction = storage.start_transaction().await?;
+                let existing = transaction
+                    .pegouts_dal()
+                    .get_pegout_by_burn_event(event.chain_id, &event.tx_hash, event.event_idx)
+                    .await?;
+                let status = match self.new_pegout(&event) {
+                    // Peg-outs are keyed by their burn event, so a burn never pays out twice.
+                    Ok(_) if existing.is_some() => EVM_EVENT_PROCESSED,
+                    Ok(pegout) => {
+                        let id = transaction.pegouts_dal().insert_pegout(&pegout).await?;
+                        logs::info!(
+                            "burn {}:{} created pegout {id}",
+                            event.tx_hash,
+                            event.event_idx
+                        );
+                        EVM_EVENT_PROCESSED
+                    }
+                    Err(err) => {
+                        logs::warn!(
+                            "burn {}:{} can't be paid out: {err:#}",
+                            event.tx_hash,
+                            event.event_idx
+                        );
+                        EVM_EVENT_INVALID
+                    }
+                };
+                transaction
+                    .events_dal()
+                    .update_event_status(event.chain_id, &event.tx_hash, event.event_idx, status)
+                    .await?;
+                transaction.commit().await?;
+            }
+        }
+        Ok(())
+    }
+
+    fn new_pegout(&self, event: &EvmEvent) -> anyhow::Result<NewPegout> {
+        let BridgeEvent::Burn {
+            from,
+            amount,
+            btc_address,
+        } = serde_json::from_str(&event.data)?
+        else {
+            anyhow::bail!("not a burn event");
+        };
+        let amount = amount
+            .parse()
+            .with_context(|| format!("invalid amount {amount}"))?;
+        Ok(NewPegout::new(
+            event.chain_id,
+            &event.tx_hash,
+            event.event_idx,
+            &from,
+            &btc_address,
+            amount,
+            self.network,
+        )?)
+    }
+
+    async fn expire_payouts(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        height: i64,
+    ) -> anyhow::Result<()> {
+        let expired = storage
+            .pegouts_dal()
+            .get_expired_pegouts(PegoutStatus::PayoutBroadcast, height, BATCH_SIZE)
+            .await?;
+        for pegout in expired {
+            if let Err(err) = self.expire_payout(storage, &pegout, height).await {
+                logs::warn!(
+                    "failed expiring the payout of pegout {}: {err:#}",
+                    pegout.id
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Takes the payout of a peg-out back if it can't confirm anymore. Payouts the node knows
+    /// may still confirm, so they are waited for: releasing the peg-out could pay it twice.
+    async fn expire_payout(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        pegout: &PegoutDetail,
+        height: i64,
+    ) -> anyhow::Result<()> {
+        if let Some(payout_tx_hash) = &pegout.payout_tx_hash {
+            let txid = Txid::from_str(payout_tx_hash)
+                .with_context(|| format!("invalid txid {payout_tx_hash}"))?;
+            match self.backend.get_tx_info(txid).await {
+                Err(err) if err.is_unknown_tx() => {}
+                Ok(_) => return Ok(()),
+                Err(err) => return Err(err.into()),
+            }
+        }
+        let transition = PegoutTransition {
+            from: PegoutStatus::PayoutBroadcast,
+            to: PegoutStatus::OperatorAssigned,
+            block_height: Some(height),
+            deadline_height: pegout.deadline_height,
+        };
+        if storage
+            .pegouts_dal()
+            .update_pegout_status(pegout.id, transition)
+            .await?
+        {
+            logs::info!(
+                "payout {:?} of pegout {} didn't reach the chain by the deadline",
+                pegout.payout_tx_hash,
+                pegout.id
+            );
+        }
+        Ok(())
+    }
+
+    async fn release_expired_assignments(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        height: i64,
+    ) -> anyhow::Result<()> {
+        let expired = storage
+            .pegouts_dal()
+            .get_expired_pegouts(PegoutStatus::OperatorAssigned, height, BATCH_SIZE)
+            .await?;
+        for pegout in expired {
+            if let Err(err) = self.release_assignment(storage, &pegout, height).await {
+                logs::warn!("failed releasing pegout {}: {err:#}", pegout.id);
+            }
+        }
+        Ok(())
+    }
+
+    async fn release_assignment(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        pegout: &PegoutDetail,
+        height: i64,
+    ) -> anyhow::Result<()> {
+        let mut transaction = storage.start_transaction().await?;
+        let transition = PegoutTransition {
+            from: PegoutStatus::OperatorAssigned,
+            to: PegoutStatus::BurnObserved,
+            block_height: Some(height),
+            deadline_height: None,
+        };
+        if transaction
+            .pegouts_dal()
+            .update_pegout_status(pegout.id, transition)
+            .await?
+        {
+            transaction
+                .pegouts_dal()
+                .unassign_operator(pegout.id)
+                .await?;
+            logs::info!(
+                "operator {:?} missed the payout deadline of pegout {}",
+                pegout.operator_id,
+                pegout.id
+            );
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn confirm_payouts(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        height: i64,
+    ) -> anyhow::Result<()> {
+        let broadcast = storage
+            .pegouts_dal()
+            .get_pegouts_by_status(PegoutStatus::PayoutBroadcast, BATCH_SIZE)
+            .await?;
+        for pegout in broadcast {
+            if let Err(err) = self.confirm_payout(storage, &pegout, height).await {
+                logs::warn!(
+                    "failed confirming the payout of pegout {}: {err:#}",
+                    pegout.id
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn confirm_payout(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        pegout: &PegoutDetail,
+        height: i64,
+    ) -> anyhow::Result<()> {
+        let Some(payout_tx_hash) = &pegout.payout_tx_hash else {
+            return Ok(());
+        };
+        if self
+            .inclusion_height(payout_tx_hash, height)
+            .await?
+            .is_none()
+        {
+            return Ok(());
+        }
+        let transition = PegoutTransition {
+            from: PegoutStatus::PayoutBroadcast,
+            to: PegoutStatus::PayoutConfirmed,
+            block_height: Some(height),
+            deadline_height: None,
+        };
+        storage
+            .pegouts_dal()
+            .update_pegout_status(pegout.id, transition)
+            .await?;
+        Ok(())
+    }
+
+    async fn open_challenge_windows(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        height: i64,
+    ) -> anyhow::Result<()> {
+        let kicked_off = storage
+            .pegouts_dal()
+            .get_pegouts_by_status(PegoutStatus::KickedOff, BATCH_SIZE)
+            .await?;
+        for pegout in kicked_off {
+            if let Err(err) = self.open_challenge_window(storage, &pegout, height).await {
+                logs::warn!(
+                    "failed opening the challenge window of pegout {}: {err:#}",
+                    pegout.id
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn open_challenge_window(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        pegout: &PegoutDetail,
+        height: i64,
+    ) -> anyhow::Result<()> {
+        let Some(kickoff_tx_hash) = &pegout.kickoff_tx_hash else {
+            return Ok(());
+        };
+        let Some(kickoff_height) = self.inclusion_height(kickoff_tx_hash, height).await? else {
+            return Ok(());
+        };
+        let transition = PegoutTransition {
+            from: PegoutStatus::KickedOff,
+            to: PegoutStatus::ChallengeWindowOpen,
+            block_height: Some(height),
+            deadline_height: Some(kickoff_height + self.config.challenge_period_blocks() as i64),
+        };
+        storage
+            .pegouts_dal()
+            .update_pegout_status(pegout.id, transition)
+            .await?;
+        Ok(())
+    }
+
+    async fn settle_challenges(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        height: i64,
+    ) -> anyhow::Result<()> {
+        let open = storage
+            .pegouts_dal()
+            .get_pegouts_by_status(PegoutStatus::ChallengeWindowOpen, BATCH_SIZE)
+            .await?;
+        for pegout in open {
+            if let Err(err) = self.settle_challenge(storage, &pegout, height).await {
+                logs::warn!(
+                    "failed settling challenges of pegout {}: {err:#}",
+                    pegout.id
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Slashes the operator of a peg-out once a challenge included in its window is confirmed,
+    /// by the amount it claimed to be reimbursed.
+    async fn settle_challenge(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        pegout: &PegoutDetail,
+        height: i64,
+    ) -> anyhow::Result<()> {
+        let challenges = storage
+            .challenges_dal()
+            .get_challenges_by_pegout(pegout.id)
+            .await?;
+        for challenge in challenges {
+            let Some((challenge_height, confirmations)) =
+                self.challenge_inclusion(pegout, &challenge, height).await?
+            else {
+                continue;
+            };
+            if confirmations < self.confirms_threshold {
+                continue;
+            }
+            let mut transaction = storage.start_transaction().await?;
+            let transition = PegoutTransition {
+                from: PegoutStatus::ChallengeWindowOpen,
+                to: PegoutStatus::Slashed,
+                block_height: Some(challenge_height),
+                deadline_height: None,
+            };
+            if transaction
+                .pegouts_dal()
+                .update_pegout_status(pegout.id, transition)
+                .await?
+            {
+                transaction
+                    .challenges_dal()
+                    .update_challenge_status(challenge.id, ChallengeStatus::Succeeded)
+                    .await?;
+                if let Some(operator_id) = pegout.operator_id {
+                    transaction
+                        .operators_dal()
+                        .slash(operator_id, pegout.amount)
+                        .await?;
+                }
+                logs::warn!(
+                    "challenge {} slashed operator {:?} of pegout {}",
+                    challenge.id,
+                    pegout.operator_id,
+                    pegout.id
+                );
+            }
+            transaction.commit().await?;
+            break;
+        }
+        Ok(())
+    }
+
+    async fn close_challenge_windows(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        height: i64,
+    ) -> anyhow::Result<()> {
+        let expired = storage
+            .pegouts_dal()
+            .get_expired_pegouts(PegoutStatus::ChallengeWindowOpen, height, BATCH_SIZE)
+            .await?;
+        for pegout in expired {
+            if let Err(err) = self.close_challenge_window(storage, &pegout, height).await {
+                logs::warn!(
+                    "failed closing the challenge window of pegout {}: {err:#}",
+                    pegout.id
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Closes a window past its deadline, failing its challenges, unless a challenge was
+    /// included in time and only waits for confirmations.
+    async fn close_challenge_window(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        pegout: &PegoutDetail,
+        height: i64,
+    ) -> anyhow::Result<()> {
+        let challenges = storage
+            .challenges_dal()
+            .get_challenges_by_pegout(pegout.id)
+            .await?;
+        for challenge in &challenges {
+            if self
+                .challenge_inclusion(pegout, challenge, height)
+                .await?
+                .is_some()
+            {
+                return Ok(());
+            }
+        }
+
+        let mut transaction = storage.start_transaction().await?;
+        let transition = PegoutTransition {
+            from: PegoutStatus::ChallengeWindowOpen,
+            to: PegoutStatus::ChallengeWindowClosed,
+            block_height: Some(height),
+            deadline_height: None,
+        };
+        transaction
+            .pegouts_dal()
+            .update_pegout_status(pegout.id, transition)
+            .await?;
+        for challenge in challenges {
+            if challenge.status == ChallengeStatus::Submitted {
+                transaction
+                    .challenges_dal()
+                    .update_challenge_status(challenge.id, ChallengeStatus::Failed)
+                    .await?;
+            }
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Returns the inclusion height and confirmations of a submitted challenge if its transaction
+    /// spends the kickoff of `pegout` and is included by the window deadline. Challenges the node
+    /// doesn't know yet are ignored.
+    async fn challenge_inclusion(
+        &self,
+        pegout: &PegoutDetail,
+        challenge: &Challenge,
+        height: i64,
+    ) -> anyhow::Result<Option<(i64, u32)>> {
+        let (Some(kickoff_tx_hash), Some(deadline_height)) =
+            (&pegout.kickoff_tx_hash, pegout.deadline_height)
+        else {
+            return Ok(None);
+        };
+        if challenge.status != ChallengeStatus::Submitted {
+            return Ok(None);
+        }
+        let txid = Txid::from_str(&challenge.challenge_tx_hash)
+            .with_context(|| format!("invalid txid {}", challenge.challenge_tx_hash))?;
+        let info = match self.backend.get_tx_info(txid).await {
+            Ok(info) => info,
+            Err(err) if err.is_unknown_tx() => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let spends_kickoff = info
+            .tx
+            .input
+            .iter()
+            .any(|input| input.previous_output.txid.to_string() == *kickoff_tx_hash);
+        if !spends_kickoff || info.confirmations == 0 {
+            return Ok(None);
+        }
+        let challenge_height = height - info.confirmations as i64 + 1;
+        Ok((challenge_height <= deadline_height).then_some((challenge_height, info.confirmations)))
+    }
+
+    /// Returns the height of the block including `tx_hash` once it has `confirms_threshold`
+    /// confirmations. Transactions the node doesn't know, e.g. because they were evicted from
+    /// its mempool or never reached it, are treated as not included.
+    async fn inclusion_height(&self, tx_hash: &str, height: i64) -> anyhow::Result<Option<i64>> {
+        let txid = Txid::from_str(tx_hash).with_context(|| format!("invalid txid {tx_hash}"))?;
+        let info = match self.backend.get_tx_info(txid).await {
+            Ok(info) => info,
+            Err(err) if err.is_unknown_tx() => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        if info.confirmations == 0 || info.confirmations < self.confirms_threshold {
+            return Ok(None);
+        }
+        Ok(Some(height - info.confirmations as i64 + 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        absolute::LockTime,
+        consensus::encode::serialize_hex,
+        secp256k1::rand::{self, Rng},
+        transaction::Version,
+        Address, Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+    };
+    use bitcoin_client::mock::MockBitcoinBackend;
+    use types::{
+        bridge::NewBridge,
+        evm_event::NewEvmEvent,
+        operator::{NewOperator, OPERATOR_SLASHED},
+        pegin::{NewPegin, PeginStatus},
+        pegout::PegoutStatus::*,
+    };
+
+    use super::*;
+
+    const MINER_ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+    const RECEIVE_ADDRESS: &str =
+        "bcrt1phcnl4zcl2fu047pv4wx6y058v8u0n02at6lthvm7pcf2wrvjm5tqatn90k";
+
+    fn address(address: &str) -> Address {
+        Address::from_str(address)
+            .unwrap()
+            .require_network(Network::Regtest)
+            .unwrap()
+    }
+
+    fn driver(backend: &MockBitcoinBackend, pool: &ConnectionPool) -> PegoutDriver {
+        let config = PegoutConfig {
+            poll_interval_sec: None,
+            payout_timeout_blocks: Some(3),
+            challenge_period_blocks: Some(5),
+        };
+        PegoutDriver::new(
+            Arc::new(backend.clone()),
+            pool.clone(),
+            Network::Regtest,
+            config,
+            2,
+        )
+    }
+
+    /// Burn, payout and key hashes are random, see `TestPool` for why.
+    fn random_hash() -> String {
+        hex::encode(rand::random::<[u8; 32]>())
+    }
+
+    /// Inserts a bridge on a random chain, returning the chain id.
+    async fn insert_bridge(storage: &mut StorageProcessor<'_>) -> i32 {
+        let chain_id = rand::thread_rng().gen_range(1..i32::MAX);
+        storage
+            .bridges_dal()
+            .insert_bridge(&NewBridge {
+                chain_id,
+                chain_name: "anvil".to_string(),
+                operator_manager_address: "0x0000000000000000000000000000000000000001".to_string(),
+                assertion_taproot_address: RECEIVE_ADDRESS.to_string(),
+                status: "active".to_string(),
+                bridge_contract_address: None,
+            })
+            .await
+            .unwrap();
+        chain_id
+    }
+
+    async fn insert_pegout(storage: &mut StorageProcessor<'_>) -> i32 {
+        let chain_id = insert_bridge(storage).await;
+        storage
+            .pegouts_dal()
+            .insert_pegout(&NewPegout {
+                chain_id,
+                burn_tx_hash: format!("0x{}", random_hash()),
+                event_idx: 0,
+                sender_address: "0x0000000000000000000000000000000000000002".to_string(),
+                receive_address: RECEIVE_ADDRESS.to_string(),
+                amount: 100_000,
+                status: BurnObserved,
+            })
+            .await
+            .unwrap()
+    }
+
+    async fn transition(
+        storage: &mut StorageProcessor<'_>,
+        id: i32,
+        from: PegoutStatus,
+        to: PegoutStatus,
+        deadline_height: Option<i64>,
+    ) {
+        let transition = PegoutTransition {
+            from,
+            to,
+            block_height: None,
+            deadline_height,
+        };
+        assert!(storage
+            .pegouts_dal()
+            .update_pegout_status(id, transition)
+            .await
+            .unwrap());
+    }
+
+    async fn pegout(pool: &ConnectionPool, id: i32) -> PegoutDetail {
+        let mut storage = pool.access_storage().await.unwrap();
+        storage
+            .pegouts_dal()
+            .get_pegout_by_id(id)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn missed_payouts_release_the_assignment() {
+        let pool = ConnectionPool::test_pool().await;
+        let backend = MockBitcoinBackend::default();
+        let miner = address(MINER_ADDRESS);
+        backend.mine_blocks(1, &miner);
+        let driver = driver(&backend, &pool);
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let id = insert_pegout(&mut storage).await;
+        transition(&mut storage, id, BurnObserved, OperatorAssigned, Some(4)).await;
+        drop(storage);
+
+        backend.mine_blocks(3, &miner);
+        driver.advance().await.unwrap();
+        assert_eq!(pegout(&pool, id).await.status, OperatorAssigned);
+
+        backend.mine_blocks(1, &miner);
+        driver.advance().await.unwrap();
+        let pegout = pegout(&pool, id).await;
+        assert_eq!(pegout.status, BurnObserved);
+        assert_eq!((pegout.operator_id, pegout.deadline_height), (None, None));
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let transitions = storage
+            .pegouts_dal()
+            .get_status_transitions(id)
+            .await
+            .unwrap();
+        assert_eq!(transitions.len(), 2);
+        assert_eq!(transitions[1].block_height, Some(5));
+    }
+
+    #[tokio::test]
+    async fn lost_payouts_release_the_assignment() {
+        let pool = ConnectionPool::test_pool().await;
+        let backend = MockBitcoinBackend::default();
+        backend.mine_blocks(1, &address(MINER_ADDRESS));
+        let driver = driver(&backend, &pool);
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let id = insert_pegout(&mut storage).await;
+        let payout_txid = backend.fund(&address(RECEIVE_ADDRESS), Amount::from_sat(100_000));
+        storage
+            .pegouts_dal()
+            .set_payout_tx_hash(id, &payout_txid.to_string())
+            .await
+            .unwrap();
+        transition(&mut storage, id, BurnObserved, OperatorAssigned, Some(0)).await;
+        transition(&mut storage, id, OperatorAssigned, PayoutBroadcast, Some(0)).await;
+        drop(storage);
+
+        // A late payout in the mempool may still confirm.
+        driver.advance().await.unwrap();
+        assert_eq!(pegout(&pool, id).await.status, PayoutBroadcast);
+
+        assert!(backend.remove_from_mempool(payout_txid));
+        driver.advance().await.unwrap();
+        let pegout = pegout(&pool, id).await;
+        assert_eq!(pegout.status, BurnObserved);
+        assert_eq!(pegout.operator_id, None);
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let transitions = storage
+            .pegouts_dal()
+            .get_status_transitions(id)
+            .await
+            .unwrap();
+        assert_eq!(
+            transitions
+                .iter()
+                .map(|transition| transition.to_status)
+                .collect::<Vec<_>>(),
+            [
+                OperatorAssigned,
+                PayoutBroadcast,
+                OperatorAssigned,
+                BurnObserved
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn unchallenged_pegouts_close_the_window() {
+        let pool = ConnectionPool::test_pool().await;
+        let backend = MockBitcoinBackend::default();
+        let miner = address(MINER_ADDRESS);
+        backend.mine_blocks(1, &miner);
+        let driver = driver(&backend, &pool);
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let id = insert_pegout(&mut storage).await;
+        let payout_txid = backend.fund(&address(RECEIVE_ADDRESS), Amount::from_sat(100_000));
+        storage
+            .pegouts_dal()
+            .set_payout_tx_hash(id, &payout_txid.to_string())
+            .await
+            .unwrap();
+        transition(&mut storage, id, BurnObserved, OperatorAssigned, Some(4)).await;
+        transition(&mut storage, id, OperatorAssigned, PayoutBroadcast, None).await;
+        drop(storage);
+
+        // The payout is included at height 2 and confirmed at height 3.
+        backend.mine_blocks(1, &miner);
+        driver.advance().await.unwrap();
+        assert_eq!(pegout(&pool, id).await.status, PayoutBroadcast);
+        backend.mine_blocks(1, &miner);
+        driver.advance().await.unwrap();
+        assert_eq!(pegout(&pool, id).await.status, PayoutConfirmed);
+
+        let kickoff_txid = backend.fund(&miner, Amount::from_sat(1_000));
+        let mut storage = pool.access_storage().await.unwrap();
+        storage
+            .pegouts_dal()
+            .set_kickoff_tx_hash(id, &kickoff_txid.to_string())
+            .await
+            .unwrap();
+        transition(&mut storage, id, PayoutConfirmed, KickedOff, None).await;
+        drop(storage);
+
+        // The kickoff is included at height 4, so the window lasts until height 9.
+        backend.mine_blocks(2, &miner);
+        driver.advance().await.unwrap();
+        let open = pegout(&pool, id).await;
+        assert_eq!(open.status, ChallengeWindowOpen);
+        assert_eq!(open.deadline_height, Some(9));
+
+        backend.mine_blocks(4, &miner);
+        driver.advance().await.unwrap();
+        assert_eq!(pegout(&pool, id).await.status, ChallengeWindowOpen);
+        backend.mine_blocks(1, &miner);
+        driver.advance().await.unwrap();
+        assert_eq!(pegout(&pool, id).await.status, ChallengeWindowClosed);
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let transitions = storage
+            .pegouts_dal()
+            .get_status_transitions(id)
+            .await
+            .unwrap();
+        assert_eq!(
+            transitions
+                .iter()
+                .map(|transition| transition.to_status)
+                .collect::<Vec<_>>(),
+            [
+                OperatorAssigned,
+                PayoutBroadcast,
+                PayoutConfirmed,
+                KickedOff,
+                ChallengeWindowOpen,
+                ChallengeWindowClosed
+            ]
+        );
+    }
+
+    fn burn_event(chain_id: i32, tx_hash: &str, event_idx: i32, btc_address: &str) -> NewEvmEvent {
+        let event = BridgeEvent::Burn {
+            from: "0x0000000000000000000000000000000000000002".to_string(),
+            amount: "100000".to_string(),
+            btc_address: btc_address.to_string(),
+        };
+        NewEvmEvent {
+            chain_id,
+            tx_hash: tx_hash.to_string(),
+            event_idx,
+            event_type: event.event_type().to_string(),
+            data: serde_json::to_string(&event).unwrap(),
+            status: EVM_EVENT_UNPROCESSED.to_string(),
+            block_number: 10,
+        }
+    }
+
+    #[tokio::test]
+    async fn burns_create_pegouts() {
+        let pool = ConnectionPool::test_pool().await;
+        let backend = MockBitcoinBackend::default();
+        backend.mine_blocks(1, &address(MINER_ADDRESS));
+        let driver = driver(&backend, &pool);
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let chain_id = insert_bridge(&mut storage).await;
+        let burn_tx_hash = format!("0x{}", random_hash());
+        let invalid_tx_hash = format!("0x{}", random_hash());
+        let events = [
+            burn_event(chain_id, &burn_tx_hash, 0, RECEIVE_ADDRESS),
+            burn_event(chain_id, &burn_tx_hash, 1, RECEIVE_ADDRESS),
+            burn_event(chain_id, &invalid_tx_hash, 0, "bcrt1qinvalid"),
+        ];
+        for event in &events {
+            storage.events_dal().insert_event(event).await.unwrap();
+        }
+        drop(storage);
+
+        driver.advance().await.unwrap();
+        driver.advance().await.unwrap();
+
+        // Both burns of the transaction are paid out.
+        let mut storage = pool.access_storage().await.unwrap();
+        let pegouts = storage
+            .pegouts_dal()
+            .get_pegouts_by_burn_tx_hash(&burn_tx_hash)
+            .await
+            .unwrap();
+        assert_eq!(
+            pegouts
+                .iter()
+                .map(|pegout| (pegout.event_idx, pegout.status))
+                .collect::<Vec<_>>(),
+            [(0, BurnObserved), (1, BurnObserved)]
+        );
+        assert_eq!(pegouts[0].receive_address, RECEIVE_ADDRESS);
+        assert_eq!(pegouts[0].amount, 100_000);
+        assert_eq!(
+            storage
+                .pegouts_dal()
+                .get_pegouts_by_status(BurnObserved, 10)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        let mut statuses = vec![];
+        for tx_hash in [&burn_tx_hash, &invalid_tx_hash] {
+            let events = storage
+                .events_dal()
+                .get_events_by_tx_hash(chain_id, tx_hash)
+                .await
+                .unwrap();
+            statuses.extend(events.into_iter().map(|event| event.status));
+        }
+        assert_eq!(
+            statuses,
+            [EVM_EVENT_PROCESSED, EVM_EVENT_PROCESSED, EVM_EVENT_INVALID]
+        );
+    }
+
+    /// A transaction spending the first output of `txid`, hex-encoded.
+    fn spend(txid: Txid, value: Amount) -> String {
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(txid, 0),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value,
+                script_pubkey: address(MINER_ADDRESS).script_pubkey(),
+            }],
+        };
+        serialize_hex(&tx)
+    }
+
+    /// Inserts a peg-out whose kickoff is included at height 2, opening a window until height 7.
+    async fn kicked_off_pegout(backend: &MockBitcoinBackend, pool: &ConnectionPool) -> (i32, Txid) {
+        let miner = address(MINER_ADDRESS);
+        backend.mine_blocks(1, &miner);
+        let kickoff_txid = backend.fund(&miner, Amount::from_sat(10_000));
+        backend.mine_blocks(2, &miner);
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let id = insert_pegout(&mut storage).await;
+        storage
+            .pegouts_dal()
+            .set_kickoff_tx_hash(id, &kickoff_txid.to_string())
+            .await
+            .unwrap();
+        transition(&mut storage, id, BurnObserved, OperatorAssigned, Some(4)).await;
+        transition(&mut storage, id, OperatorAssigned, PayoutBroadcast, None).await;
+        transition(&mut storage, id, PayoutBroadcast, PayoutConfirmed, None).await;
+        transition(&mut storage, id, PayoutConfirmed, KickedOff, None).await;
+        transition(&mut storage, id, KickedOff, ChallengeWindowOpen, Some(7)).await;
+        (id, kickoff_txid)
+    }
+
+    /// Assigns a peg-out to a new operator paying it from a new peg-in; returns the operator id.
+    async fn assign_operator(storage: &mut StorageProcessor<'_>, id: i32) -> i32 {
+        let pegout = storage
+            .pegouts_dal()
+            .get_pegout_by_id(id)
+            .await
+            .unwrap()
+            .unwrap();
+        let pegin_id = storage
+            .pegins_dal()
+            .insert_pegin(&NewPegin {
+                target_chain_id: pegout.chain_id,
+                public_key: "02".repeat(33),
+                sender_address: RECEIVE_ADDRESS.to_string(),
+                status: PeginStatus::Created,
+                receive_address: "0x0000000000000000000000000000000000000001".to_string(),
+                amount: 100_000,
+            })
+            .await
+            .unwrap();
+        let operator_id = storage
+            .operators_dal()
+            .insert_operator(&NewOperator {
+                address: "0x0000000000000000000000000000000000000003".to_string(),
+                chain_id: pegout.chain_id,
+                public_key: format!("02{}", random_hash()),
+                fee: 1_000,
+                status: "active".to_string(),
+                stake_amount: 10_000_000,
+                max_support_amount: 100_000_000,
+                min_support_amount: 100_000,
+                max_pegin_cnt: 10,
+                max_pegout_cnt: 10,
+                register_at: chrono::Utc::now().naive_utc(),
+            })
+            .await
+            .unwrap();
+        storage
+            .pegouts_dal()
+            .assign_operator(id, pegin_id, operator_id)
+            .await
+            .unwrap();
+        operator_id
+    }
+
+    #[tokio::test]
+    async fn confirmed_challenges_slash_the_operator() {
+        let pool = ConnectionPool::test_pool().await;
+        let backend = MockBitcoinBackend::default();
+        let miner = address(MINER_ADDRESS);
+        let driver = driver(&backend, &pool);
+        let (id, kickoff_txid) = kicked_off_pegout(&backend, &pool).await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let operator_id = assign_operator(&mut storage, id).await;
+        drop(storage);
+
+        // A challenge that doesn't spend the kickoff is ignored.
+        let unrelated_txid = backend.fund(&miner, Amount::from_sat(1_000));
+        let challenge_txid = backend
+            .post_tx(spend(kickoff_txid, Amount::from_sat(9_000)))
+            .await
+            .unwrap();
+        let mut storage = pool.access_storage().await.unwrap();
+        let mut challenge_ids = vec![];
+        for txid in [unrelated_txid, challenge_txid] {
+            let challenge_id = storage
+                .challenges_dal()
+                .insert_challenge(id, "bcrt1qchallenger", &txid.to_string())
+                .await
+                .unwrap()
+                .unwrap();
+            challenge_ids.push(challenge_id);
+        }
+        drop(storage);
+
+        // The challenge is included at height 4 and confirmed at height 5.
+        backend.mine_blocks(1, &miner);
+        driver.advance().await.unwrap();
+        assert_eq!(pegout(&pool, id).await.status, ChallengeWindowOpen);
+        backend.mine_blocks(1, &miner);
+        driver.advance().await.unwrap();
+        assert_eq!(pegout(&pool, id).await.status, Slashed);
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let mut statuses = vec![];
+        for challenge_id in challenge_ids {
+            let challenge = storage
+                .challenges_dal()
+                .get_challenge(challenge_id)
+                .await
+                .unwrap()
+                .unwrap();
+            statuses.push(challenge.status);
+        }
+        assert_eq!(
+            statuses,
+            [ChallengeStatus::Submitted, ChallengeStatus::Succeeded]
+        );
+        let transitions = storage
+            .pegouts_dal()
+            .get_status_transitions(id)
+            .await
+            .unwrap();
+        assert_eq!(transitions.last().unwrap().block_height, Some(4));
+        let operator = storage
+            .operators_dal()
+            .get_operator_by_id(operator_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(operator.status, OPERATOR_SLASHED);
+        assert_eq!(operator.slash_cnt, 1);
+        assert_eq!(operator.remaining_stake_amount, 10_000_000 - 100_000);
+    }
+
+    #[tokio::test]
+    async fn late_challenges_fail() {
+        let pool = ConnectionPool::test_pool().await;
+        let backend = MockBitcoinBackend::default();
+        let miner = address(MINER_ADDRESS);
+        let driver = driver(&backend, &pool);
+        let (id, kickoff_txid) = kicked_off_pegout(&backend, &pool).await;
+
+        backend.mine_blocks(4, &miner);
+        let challenge_txid = backend
+            .post_tx(spend(kickoff_txid, Amount::from_sat(9_000)))
+            .await
+            .unwrap();
+        let mut storage = pool.access_storage().await.unwrap();
+        let challenge_id = storage
+            .challenges_dal()
+            .insert_challenge(id, "bcrt1qchallenger", &challenge_txid.to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        drop(storage);
+
+        // The challenge is only included at height 8, past the deadline.
+        backend.mine_blocks(2, &miner);
+        driver.advance().await.unwrap();
+        assert_eq!(pegout(&pool, id).await.status, ChallengeWindowClosed);
+        let mut storage = pool.access_storage().await.unwrap();
+        let challenge = storage
+            .challenges_dal()
+            .get_challenge(challenge_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(challenge.status, ChallengeStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn failing_pegouts_dont_stall_the_others() {
+        let pool = ConnectionPool::test_pool().await;
+        let backend = MockBitcoinBackend::default();
+        let driver = driver(&backend, &pool);
+        let (id, _) = kicked_off_pegout(&backend, &pool).await;
+        let chain_id = pegout(&pool, id).await.chain_id;
+
+        // A payout the node doesn't know, e.g. evicted from its mempool, waits to be confirmed.
+        // A payout that can't be checked at all is skipped.
+        let mut storage = pool.access_storage().await.unwrap();
+        let mut stuck_ids = vec![];
+        for payout_tx_hash in [random_hash(), "invalid".to_string()] {
+            let stuck_id = storage
+                .pegouts_dal()
+                .insert_pegout(&NewPegout {
+                    chain_id,
+                    burn_tx_hash: format!("0x{}", random_hash()),
+                    event_idx: 0,
+                    sender_address: "0x0000000000000000000000000000000000000002".to_string(),
+                    receive_address: RECEIVE_ADDRESS.to_string(),
+                    amount: 100_000,
+                    status: BurnObserved,
+                })
+                .await
+                .unwrap();
+            storage
+                .pegouts_dal()
+                .set_payout_tx_hash(stuck_id, &payout_tx_hash)
+                .await
+                .unwrap();
+            transition(
+                &mut storage,
+                stuck_id,
+                BurnObserved,
+                OperatorAssigned,
+                Some(100),
+            )
+            .await;
+            transition(
+                &mut storage,
+                stuck_id,
+                OperatorAssigned,
+                PayoutBroadcast,
+                None,
+            )
+            .await;
+            stuck_ids.push(stuck_id);
+        }
+        drop(storage);
+
+        backend.mine_blocks(5, &address(MINER_ADDRESS));
+        driver.advance().await.unwrap();
+        assert_eq!(pegout(&pool, id).await.status, ChallengeWindowClosed);
+        for stuck_id in stuck_ids {
+            assert_eq!(pegout(&pool, stuck_id).await.status, PayoutBroadcast);
+        }
+    }
+}
@@ -0,0 +1,576 @@
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    future::Future,
+    hash::Hash,
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use bridge_rpc::error::Web3Error;
+use futures::{future::BoxFuture, FutureExt};
+use hyper::{
+    header::{self, HeaderMap},
+    server::conn::{AddrIncoming, AddrStream},
+    service::{make_service_fn, service_fn, Service},
+    Body, Request, Response, StatusCode,
+};
+use jsonrpsee::{
+    server::{
+        ws::is_upgrade_request, ConnectionId, RpcServiceBuilder, StopHandle, TowerService,
+        TowerServiceBuilder,
+    },
+    Methods,
+};
+
+use super::web3::backend::into_rpc_error;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Counts the permits held for each key, e.g. the connections of each IP address, and stops
+/// handing them out once a key holds `limit` of them.
+#[derive(Debug)]
+pub(crate) struct Counter<K> {
+    limit: Option<usize>,
+    counts: Mutex<HashMap<K, usize>>,
+}
+
+impl<K: Clone + Eq + Hash> Counter<K> {
+    /// Without a `limit`, permits are only counted.
+    pub(crate) fn new(limit: Option<usize>) -> Arc<Self> {
+        Arc::new(Self {
+            limit,
+            counts: Mutex::default(),
+        })
+    }
+
+    /// Returns a permit for `key`, or the limit if `key` already holds as many permits.
+    pub(crate) fn try_acquire(self: &Arc<Self>, key: K) -> Result<Permit<K>, usize> {
+        let mut counts = self.counts.lock().unwrap();
+        let count = counts.get(&key).copied().unwrap_or_default();
+        if let Some(limit) = self.limit.filter(|&limit| count >= limit) {
+            return Err(limit);
+        }
+        counts.insert(key.clone(), count + 1);
+        Ok(Permit {
+            counter: self.clone(),
+            key,
+        })
+    }
+
+    #[cfg(test)]
+    fn count(&self, key: &K) -> usize {
+        self.counts
+            .lock()
+            .unwrap()
+            .get(key)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Released when dropped.
+#[derive(Debug)]
+pub(crate) struct Permit<K: Clone + Eq + Hash> {
+    counter: Arc<Counter<K>>,
+    key: K,
+}
+
+impl<K: Clone + Eq + Hash> Drop for Permit<K> {
+    fn drop(&mut self) {
+        let mut counts = self.counter.counts.lock().unwrap();
+        if let Some(count) = counts.get_mut(&self.key) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&self.key);
+            }
+        }
+    }
+}
+
+/// Caps the subscriptions of each WebSocket connection, in total and to the same topic.
+#[derive(Debug)]
+pub(crate) struct SubscriptionLimits {
+    per_connection: Arc<Counter<ConnectionId>>,
+    per_topic: Arc<Counter<(ConnectionId, &'static str)>>,
+}
+
+/// Held by a subscription until it ends.
+#[derive(Debug)]
+pub(crate) struct SubscriptionPermit {
+    _connection: Permit<ConnectionId>,
+    _topic: Permit<(ConnectionId, &'static str)>,
+}
+
+impl SubscriptionLimits {
+    pub(crate) fn new(per_connection: Option<usize>, per_topic: Option<usize>) -> Self {
+        Self {
+            per_connection: Counter::new(per_connection),
+            per_topic: Counter::new(per_topic),
+        }
+    }
+
+    pub(crate) fn try_acquire(
+        &self,
+        connection: ConnectionId,
+        topic: &'static str,
+    ) -> Result<SubscriptionPermit, Web3Error> {
+        let connection_permit = self
+            .per_connection
+            .try_acquire(connection)
+            .map_err(|limit| Web3Error::LimitExceeded {
+                resource: "subscriptions",
+                limit,
+            })?;
+        let topic_permit = self
+            .per_topic
+            .try_acquire((connection, topic))
+            .map_err(|limit| Web3Error::LimitExceeded {
+                resource: "subscriptions to the topic",
+                limit,
+            })?;
+        Ok(SubscriptionPermit {
+            _connection: connection_permit,
+            _topic: topic_permit,
+        })
+    }
+}
+
+impl Default for SubscriptionLimits {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
+
+/// Identifies the clients of a server by IP address, and caps the connections of each.
+#[derive(Debug)]
+pub(crate) struct Clients {
+    /// Proxies trusted to report the address they received a request from in `X-Forwarded-For`.
+    trusted_proxies: HashSet<IpAddr>,
+    connections_per_ip: Arc<Counter<IpAddr>>,
+    next_connection_id: AtomicU32,
+    /// Clients of the open connections, for methods to tell who calls them.
+    connections: Mutex<HashMap<ConnectionId, IpAddr>>,
+}
+
+/// Open connection of a client, closed when dropped.
+#[derive(Debug)]
+struct Connection {
+    id: ConnectionId,
+    clients: Arc<Clients>,
+    _permit: Permit<IpAddr>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.clients.connections.lock().unwrap().remove(&self.id);
+    }
+}
+
+impl Clients {
+    pub(crate) fn new(max_connections_per_ip: Option<usize>, trusted_proxies: &[IpAddr]) -> Self {
+        Self {
+            trusted_proxies: trusted_proxies.iter().copied().collect(),
+            connections_per_ip: Counter::new(max_connections_per_ip),
+            next_connection_id: AtomicU32::new(0),
+            connections: Mutex::default(),
+        }
+    }
+
+    /// Address of the client of an open connection.
+    pub(crate) fn ip(&self, connection: ConnectionId) -> Option<IpAddr> {
+        self.connections.lock().unwrap().get(&connection).copied()
+    }
+
+    /// Opens a connection of `client`, or returns the limit if it already has as many open.
+    fn connect(self: &Arc<Self>, client: IpAddr) -> Result<Connection, usize> {
+        let permit = self.connections_per_ip.try_acquire(client)?;
+        let id = self.next_connection_id.fetch_add(1, Ordering::Relaxed);
+        self.connections
+            .lock()
+            .unwrap()
+            .insert(id as ConnectionId, client);
+        Ok(Connection {
+            id: id as ConnectionId,
+            clients: self.clone(),
+            _permit: permit,
+        })
+    }
+
+    /// Returns the address of the client a request received from `remote` was sent by.
+    fn client_ip(&self, remote: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let forwarded_for: Vec<_> = headers
+            .get_all(X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect();
+        // Each proxy appends the address it received the request from, so walk back from the
+        // last one for as long as the addresses belong to trusted proxies.
+        let mut client = remote;
+        for hop in forwarded_for.iter().rev() {
+            if !self.trusted_proxies.contains(&client) {
+                break;
+            }
+            match hop.trim().parse() {
+                Ok(hop) => client = hop,
+                Err(_) => break,
+            }
+        }
+        client
+    }
+}
+
+/// Serves `methods` like [`jsonrpsee::server::Server::start`], additionally capping the
+/// connections of each client. A WebSocket connection is counted until its session closes, an
+/// HTTP request until it is answered. `rpc_middleware` builds the RPC middleware of a client, and
+/// [`Clients::ip`] resolves the client of a connection while it is open.
+pub(crate) fn serve<RpcMiddleware, HttpMiddleware, L>(
+    incoming: AddrIncoming,
+    service_builder: TowerServiceBuilder<RpcMiddleware, HttpMiddleware>,
+    rpc_middleware: impl Fn(IpAddr) -> RpcServiceBuilder<L> + Clone + Send + Sync + 'static,
+    methods: Methods,
+    clients: Arc<Clients>,
+    stop_handle: StopHandle,
+) -> impl Future<Output = hyper::Result<()>>
+where
+    RpcMiddleware: Clone + Send + Sync + 'static,
+    HttpMiddleware: Clone + Send + Sync + 'static,
+    TowerService<L, HttpMiddleware>: Service<
+            Request<Body>,
+            Response = Response<Body>,
+            Error = BoxError,
+            Future = BoxFuture<'static, Result<Response<Body>, BoxError>>,
+        > + Send
+        + 'static,
+{
+    let shutdown = stop_handle.clone().shutdown();
+    let make_service = make_service_fn(move |connection: &AddrStream| {
+        let remote = connection.remote_addr().ip();
+        let service_builder = service_builder.clone();
+        let rpc_middleware = rpc_middleware.clone();
+        let methods = methods.clone();
+        let clients = clients.clone();
+        let stop_handle = stop_handle.clone();
+
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let client = clients.client_ip(remote, request.headers());
+                let connection = match clients.connect(client) {
+                    Ok(connection) => connection,
+                    Err(limit) => return futures::future::ok(too_many_connections(limit)).boxed(),
+                };
+                let mut service = service_builder
+                    .clone()
+                    .connection_id(connection.id as u32)
+                    .set_rpc_middleware(rpc_middleware(client))
+                    .build(methods.clone(), stop_handle.clone());
+                if is_upgrade_request(&request) {
+                    // The WebSocket session outlives the HTTP request it was upgraded from.
+                    let session_closed = service.on_session_closed();
+                    tokio::spawn(async move {
+                        session_closed.await;
+                        drop(connection);
+                    });
+                    service.call(request)
+                } else {
+                    let response = service.call(request);
+                    async move {
+                        let response = response.await;
+                        drop(connection);
+                        response
+                    }
+                    .boxed()
+                }
+            }))
+        }
+    });
+    hyper::Server::builder(incoming)
+        .serve(make_service)
+        .with_graceful_shutdown(shutdown)
+}
+
+fn too_many_connections(limit: usize) -> Response<Body> {
+    let error = into_rpc_error(Web3Error::LimitExceeded {
+        resource: "connections",
+        limit,
+    });
+    let body = serde_json::json!({ "jsonrpc": "2.0", "error": error, "id": null });
+    Response::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("response is valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, num::NonZeroU32, time::Duration};
+
+    use bitcoin::Network;
+    use bridge_rpc::{error::codes, namespaces::pubsub::TestPubSubServer};
+    use dal::connection::ConnectionPool;
+    use jsonrpsee::{
+        core::client::{ClientT, Error as ClientError, Subscription, SubscriptionClientT},
+        rpc_params,
+        server::{stop_channel, ServerBuilder, ServerHandle},
+        ws_client::{WsClient, WsClientBuilder},
+        RpcModule,
+    };
+    use serde_json::{json, Value};
+    use types::pubsub::PubSubResult;
+
+    use super::*;
+    use crate::server::{
+        pubsub::TestSubscribe,
+        web3::backend::middleware::{LimitMiddleware, RateLimits},
+    };
+
+    const PROXY: IpAddr = IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+
+    fn unlimited() -> RateLimits {
+        RateLimits::new(None, None, Arc::default(), HashMap::new())
+    }
+
+    async fn start_server(
+        methods: impl Into<Methods>,
+        clients: Arc<Clients>,
+        rate_limits: RateLimits,
+    ) -> (SocketAddr, ServerHandle) {
+        let incoming = AddrIncoming::bind(&([127, 0, 0, 1], 0).into()).unwrap();
+        let local_addr = incoming.local_addr();
+        let (stop_handle, server_handle) = stop_channel();
+        let rate_limits = Arc::new(rate_limits);
+        let rpc_middleware = move |client| {
+            let rate_limits = rate_limits.clone();
+            RpcServiceBuilder::new()
+                .layer_fn(move |svc| LimitMiddleware::new(svc, rate_limits.clone(), client))
+        };
+        tokio::spawn(serve(
+            incoming,
+            ServerBuilder::default().to_service_builder(),
+            rpc_middleware,
+            methods.into(),
+            clients,
+            stop_handle,
+        ));
+        (local_addr, server_handle)
+    }
+
+    async fn connect(addr: SocketAddr) -> Result<WsClient, ClientError> {
+        WsClientBuilder::default()
+            .build(format!("ws://{addr}"))
+            .await
+    }
+
+    async fn subscribe(
+        client: &WsClient,
+        sub_type: &str,
+    ) -> Result<Subscription<PubSubResult>, ClientError> {
+        client
+            .subscribe("test_subscribe", rpc_params![sub_type], "test_unsubscribe")
+            .await
+    }
+
+    async fn get_challenge(client: &WsClient) -> Result<String, ClientError> {
+        client.request("test_getChallenge", rpc_params![]).await
+    }
+
+    fn assert_limit_exceeded(
+        result: Result<Subscription<PubSubResult>, ClientError>,
+        resource: &str,
+    ) {
+        let Err(ClientError::Call(err)) = result else {
+            panic!("subscription wasn't rejected: {result:?}");
+        };
+        assert_eq!(err.code(), codes::RATE_LIMITED);
+        assert_eq!(
+            err.data().unwrap().get(),
+            json!({ "resource": resource, "limit": 1 }).to_string()
+        );
+    }
+
+    /// Waits for a released permit, which happens once the server notices the client went away.
+    async fn wait_for_count<K: Clone + Eq + Hash>(counter: &Counter<K>, key: &K, count: usize) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while counter.count(key) != count {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("permit wasn't released");
+    }
+
+    #[tokio::test]
+    async fn capping_subscriptions_per_connection() {
+        let pool = ConnectionPool::test_pool().await;
+        let pubsub = TestSubscribe::new(pool, Network::Regtest, None)
+            .with_subscription_limits(SubscriptionLimits::new(Some(2), Some(1)));
+        let clients = Arc::new(Clients::new(None, &[]));
+        let (addr, server) = start_server(pubsub.into_rpc(), clients, unlimited()).await;
+        let client = connect(addr).await.unwrap();
+
+        let reorgs = subscribe(&client, "bitcoinReorgs").await.unwrap();
+        assert_limit_exceeded(
+            subscribe(&client, "bitcoinReorgs").await,
+            "subscriptions to the topic",
+        );
+        let _tasks = subscribe(&client, "challengerTasks").await.unwrap();
+        // Rejected before asking committee members to authenticate.
+        let Err(ClientError::Call(err)) = subscribe(&client, "committeeTasks").await else {
+            panic!("subscription wasn't rejected");
+        };
+        assert_eq!(err.code(), codes::RATE_LIMITED);
+
+        // Other connections have limits of their own.
+        let other_client = connect(addr).await.unwrap();
+        subscribe(&other_client, "bitcoinReorgs").await.unwrap();
+
+        reorgs.unsubscribe().await.unwrap();
+        let reorgs = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                match subscribe(&client, "bitcoinReorgs").await {
+                    Ok(reorgs) => break reorgs,
+                    Err(ClientError::Call(_)) => {
+                        tokio::time::sleep(Duration::from_millis(10)).await
+                    }
+                    Err(err) => panic!("{err}"),
+                }
+            }
+        })
+        .await
+        .expect("unsubscribing didn't release the limit");
+        drop(reorgs);
+        server.stop().unwrap();
+    }
+
+    #[tokio::test]
+    async fn capping_nonces_per_ip() {
+        let pool = ConnectionPool::test_pool().await;
+        let clients = Arc::new(Clients::new(None, &[]));
+        let pubsub = TestSubscribe::new(pool, Network::Regtest, None).with_clients(clients.clone());
+        let (addr, server) = start_server(pubsub.into_rpc(), clients, unlimited()).await;
+        let client = connect(addr).await.unwrap();
+        let mut issued = 0;
+        while get_challenge(&client).await.is_ok() {
+            issued += 1;
+        }
+        assert!(issued > 0);
+        // Connecting again doesn't get the client more nonces.
+        let other_client = connect(addr).await.unwrap();
+        let Err(ClientError::Call(err)) = get_challenge(&other_client).await else {
+            panic!("nonce wasn't refused");
+        };
+        assert_eq!(err.code(), codes::RATE_LIMITED);
+        server.stop().unwrap();
+    }
+
+    #[tokio::test]
+    async fn capping_connections_per_ip() {
+        let clients = Arc::new(Clients::new(Some(1), &[]));
+        let connections_per_ip = clients.connections_per_ip.clone();
+        let (addr, server) = start_server(RpcModule::new(()), clients, unlimited()).await;
+        let localhost = addr.ip();
+
+        let client = connect(addr).await.unwrap();
+        assert_eq!(connections_per_ip.count(&localhost), 1);
+        connect(addr).await.unwrap_err();
+        assert_eq!(connections_per_ip.count(&localhost), 1);
+
+        drop(client);
+        wait_for_count(&connections_per_ip, &localhost, 0).await;
+        let _client = connect(addr).await.unwrap();
+
+        let rejected = reqwest::Client::new()
+            .post(format!("http://{addr}"))
+            .json(&json!({ "jsonrpc": "2.0", "method": "test", "id": 1 }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(rejected.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body: Value = rejected.json().await.unwrap();
+        assert_eq!(body["error"]["code"], codes::RATE_LIMITED);
+        assert_eq!(body["error"]["data"]["resource"], "connections");
+        server.stop().unwrap();
+    }
+
+    #[test]
+    fn resolving_clients_behind_trusted_proxies() {
+        let clients = Clients::new(None, &[PROXY]);
+        let client: IpAddr = "10.0.0.1".parse().unwrap();
+        let forwarded_for = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(X_FORWARDED_FOR, value.parse().unwrap());
+            headers
+        };
+
+        assert_eq!(clients.client_ip(PROXY, &HeaderMap::new()), PROXY);
+        assert_eq!(clients.client_ip(PROXY, &forwarded_for("10.0.0.1")), client);
+        // Only the hops appended by trusted proxies are believed.
+        assert_eq!(
+            clients.client_ip(PROXY, &forwarded_for("10.0.0.9, 10.0.0.1")),
+            client
+        );
+        assert_eq!(
+            clients.client_ip(PROXY, &forwarded_for("10.0.0.1, 127.0.0.1")),
+            client
+        );
+        assert_eq!(
+            clients.client_ip(PROXY, &forwarded_for("10.0.0.1, bogus")),
+            PROXY
+        );
+        assert_eq!(
+            clients.client_ip(client, &forwarded_for("10.0.0.2")),
+            client
+        );
+    }
+
+    #[tokio::test]
+    async fn rate_limiting_http_batches_by_size() {
+        let mut module = RpcModule::new(());
+        module.register_method("test_ping", |_, _| "pong").unwrap();
+        let rate_limits = RateLimits::new(NonZeroU32::new(2), None, Arc::default(), HashMap::new());
+        let clients = Arc::new(Clients::new(None, &[PROXY]));
+        let (addr, server) = start_server(module, clients, rate_limits).await;
+
+        let http = reqwest::Client::new();
+        let ping = |id| json!({ "jsonrpc": "2.0", "method": "test_ping", "id": id });
+        let responses: Vec<Value> = http
+            .post(format!("http://{addr}"))
+            .json(&json!([ping(1), ping(2), ping(3)]))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(responses[0]["result"], "pong");
+        assert_eq!(responses[1]["result"], "pong");
+        assert_eq!(responses[2]["error"]["code"], codes::RATE_LIMITED);
+        assert!(
+            responses[2]["error"]["data"]["retry_after_ms"]
+                .as_u64()
+                .unwrap()
+                > 0
+        );
+
+        // Clients behind the proxy have quotas of their own.
+        let response: Value = http
+            .post(format!("http://{addr}"))
+            .header(X_FORWARDED_FOR, "10.0.0.1")
+            .json(&ping(4))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(response["result"], "pong");
+        server.stop().unwrap();
+    }
+}
@@ -1,34 +1,55 @@
-use std::{collections::HashSet, net::SocketAddr, num::NonZeroU32, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    net::{IpAddr, SocketAddr},
+    num::NonZeroU32,
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::Context;
 use bitcoin::Network;
-use bridge_rpc::namespaces::{pubsub::TestPubSubServer, test::TestNamespaceServer};
+use bridge_rpc::namespaces::{
+    bridge::{BridgeNamespaceServer, NonceNamespaceServer},
+    chain::ChainNamespaceServer,
+    pubsub::TestPubSubServer,
+    test::TestNamespaceServer,
+};
 use dal::connection::ConnectionPool;
 use futures::future;
 use health_check::{HealthStatus, HealthUpdater, ReactiveHealthCheck};
+use hyper::server::conn::AddrIncoming;
 use jsonrpsee::{
-    server::{BatchRequestConfig, PingConfig, RpcServiceBuilder, ServerBuilder},
+    server::{stop_channel, BatchRequestConfig, PingConfig, RpcServiceBuilder, ServerBuilder},
     RpcModule,
 };
+use limits::{Clients, SubscriptionLimits};
 use pubsub::TestSubscribe;
 use serde::Deserialize;
 use state::RpcState;
 use tokio::{
-    sync::{oneshot, watch},
+    sync::{broadcast, oneshot, watch},
     task::JoinHandle,
 };
 use tower_http::cors::CorsLayer;
+use types::pubsub::PubSubResult;
 use web3::{
-    backend::{metadata::MethodTracer, middleware::LimitMiddleware},
-    namespaces::test::TestNamespace,
+    backend::{
+        metadata::MethodTracer,
+        middleware::{LimitMiddleware, RateLimits},
+    },
+    namespaces::{bridge::BridgeNamespace, chain::ChainNamespace, test::TestNamespace},
 };
 
-use crate::test::Test;
+use crate::{bridge::BridgeService, chains::ChainRegistry, test::Test};
 
+pub(crate) mod limits;
 pub mod pubsub;
 pub mod state;
 pub mod web3;
 
+/// How often the rate limiters forget the clients and methods idle for a full period.
+const RATE_LIMITS_RETENTION_INTERVAL: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Clone, Copy)]
 enum ApiTransport {
     WebSocket(SocketAddr),
@@ -39,11 +60,12 @@ enum ApiTransport {
 #[serde(rename_all = "camelCase")]
 pub enum Namespace {
     Bridge,
+    Chain,
     Pubsub,
 }
 
 impl Namespace {
-    pub const DEFAULT: &'static [Namespace] = &[Namespace::Bridge];
+    pub const DEFAULT: &'static [Namespace] = &[Namespace::Bridge, Namespace::Chain];
 }
 
 /// Handles to the initialized API server.
@@ -60,10 +82,17 @@ pub struct ApiServerHandles {
 struct OptionalApiParams {
     filters_limit: Option<usize>,
     subscriptions_limit: Option<usize>,
+    max_connections: Option<u32>,
+    max_connections_per_ip: Option<usize>,
     batch_request_size_limit: Option<usize>,
     response_body_size_limit: Option<usize>,
-    websocket_requests_per_minute_limit: Option<NonZeroU32>,
+    requests_per_minute_per_ip: Option<NonZeroU32>,
+    requests_per_minute_per_method: Option<NonZeroU32>,
+    method_weights: HashMap<String, NonZeroU32>,
+    trusted_proxies: Vec<IpAddr>,
     threads: Option<usize>,
+    bitcoin_reorgs: Option<broadcast::Sender<Vec<PubSubResult>>>,
+    chains: Option<Arc<ChainRegistry>>,
 }
 
 #[derive(Debug)]
@@ -122,16 +151,29 @@ impl ApiBuilder {
         self
     }
 
+    /// Caps the subscriptions of a WebSocket connection to the same topic.
     pub fn with_filters_limit(mut self, filters_limit: usize) -> Self {
         self.optional.filters_limit = Some(filters_limit);
         self
     }
 
+    /// Caps the subscriptions of a WebSocket connection.
     pub fn with_subscriptions_limit(mut self, subscriptions_limit: usize) -> Self {
         self.optional.subscriptions_limit = Some(subscriptions_limit);
         self
     }
 
+    pub fn with_max_connections(mut self, max_connections: u32) -> Self {
+        self.optional.max_connections = Some(max_connections);
+        self
+    }
+
+    /// Caps the connections from the same IP address.
+    pub fn with_max_connections_per_ip(mut self, max_connections_per_ip: usize) -> Self {
+        self.optional.max_connections_per_ip = Some(max_connections_per_ip);
+        self
+    }
+
     pub fn with_batch_request_size_limit(mut self, limit: usize) -> Self {
         self.optional.batch_request_size_limit = Some(limit);
         self
@@ -142,12 +184,28 @@ impl ApiBuilder {
         self
     }
 
-    pub fn with_websocket_requests_per_minute_limit(
-        mut self,
-        websocket_requests_per_minute_limit: NonZeroU32,
-    ) -> Self {
-        self.optional.websocket_requests_per_minute_limit =
-            Some(websocket_requests_per_minute_limit);
+    /// Caps the calls of each client IP address per minute, each call counting by the weight of
+    /// its method. `None` leaves them unlimited.
+    pub fn with_requests_per_minute_per_ip(mut self, limit: Option<NonZeroU32>) -> Self {
+        self.optional.requests_per_minute_per_ip = limit;
+        self
+    }
+
+    /// Caps the calls each client makes per minute to each method. `None` leaves them unlimited.
+    pub fn with_requests_per_minute_per_method(mut self, limit: Option<NonZeroU32>) -> Self {
+        self.optional.requests_per_minute_per_method = limit;
+        self
+    }
+
+    /// Weights of expensive methods towards the per-IP limit; other methods weigh 1.
+    pub fn with_method_weights(mut self, method_weights: HashMap<String, NonZeroU32>) -> Self {
+        self.optional.method_weights = method_weights;
+        self
+    }
+
+    /// Proxies whose `X-Forwarded-For` header is trusted to identify clients.
+    pub fn with_trusted_proxies(mut self, trusted_proxies: Vec<IpAddr>) -> Self {
+        self.optional.trusted_proxies = trusted_proxies;
         self
     }
 
@@ -166,6 +224,18 @@ impl ApiBuilder {
         self
     }
 
+    /// Forwards Bitcoin reorgs sent to this channel to `bitcoinReorgs` subscribers.
+    pub fn with_bitcoin_reorgs(mut self, sender: broadcast::Sender<Vec<PubSubResult>>) -> Self {
+        self.optional.bitcoin_reorgs = Some(sender);
+        self
+    }
+
+    /// Target chains RPC queries are routed to. Without it, queries for any chain are rejected.
+    pub fn with_chains(mut self, chains: Arc<ChainRegistry>) -> Self {
+        self.optional.chains = Some(chains);
+        self
+    }
+
     #[cfg(test)]
     fn with_method_tracer(mut self, method_tracer: Arc<MethodTracer>) -> Self {
         self.method_tracer = method_tracer;
@@ -204,10 +274,18 @@ impl ApiServer {
         self.health_updater.subscribe()
     }
 
-    async fn build_rpc_state(self, test: Test) -> anyhow::Result<RpcState> {
+    async fn build_rpc_state(
+        self,
+        test: Test,
+        bridge: BridgeService,
+        clients: Arc<Clients>,
+    ) -> anyhow::Result<RpcState> {
         Ok(RpcState {
             _current_method: self.method_tracer,
             _connection_pool: self.pool,
+            chains: self.optional.chains.unwrap_or_default(),
+            bridge,
+            clients,
             test,
         })
     }
@@ -215,7 +293,9 @@ impl ApiServer {
     async fn build_rpc_module(
         self,
         test: Test,
+        bridge: BridgeService,
         pub_sub: Option<TestSubscribe>,
+        clients: Arc<Clients>,
     ) -> anyhow::Result<RpcModule<()>> {
         let namespaces = self.namespaces.clone();
 
@@ -224,10 +304,22 @@ impl ApiServer {
             rpc.merge(pub_sub.into_rpc())
                 .expect("Can't merge eth pubsub namespace");
         }
+        let rpc_state = self.build_rpc_state(test, bridge, clients).await?;
         if namespaces.contains(&Namespace::Bridge) {
-            let rpc_state = self.build_rpc_state(test).await?;
-            rpc.merge(TestNamespace::new(rpc_state).into_rpc())
+            rpc.merge(TestNamespace::new(rpc_state.clone()).into_rpc())
                 .expect("Can't merge Committee namespace");
+            rpc.merge(BridgeNamespaceServer::into_rpc(BridgeNamespace::new(
+                rpc_state.clone(),
+            )))
+            .expect("Can't merge bridge namespace");
+            rpc.merge(NonceNamespaceServer::into_rpc(BridgeNamespace::new(
+                rpc_state.clone(),
+            )))
+            .expect("Can't merge bridge nonce namespace");
+        }
+        if namespaces.contains(&Namespace::Chain) {
+            rpc.merge(ChainNamespace::new(rpc_state).into_rpc())
+                .expect("Can't merge chain namespace");
         }
 
         Ok(rpc)
@@ -236,41 +328,68 @@ impl ApiServer {
     pub async fn run(
         self,
         test: Test,
+        bridge: BridgeService,
         network: Network,
         stop_receiver: watch::Receiver<bool>,
     ) -> anyhow::Result<ApiServerHandles> {
-        if self.optional.filters_limit.is_none() {
-            logs::warn!("Filters limit is not set - unlimited filters are allowed");
-        }
-
-        match (&self.transport, self.optional.subscriptions_limit) {
-            (ApiTransport::WebSocket(_), None) => {
-                logs::warn!(
-                    "`subscriptions_limit` is not set - unlimited subscriptions are allowed"
-                );
+        let optional = &self.optional;
+        match &self.transport {
+            ApiTransport::WebSocket(_) => {
+                if optional.subscriptions_limit.is_none() {
+                    logs::warn!(
+                        "`subscriptions_limit` is not set - unlimited subscriptions are allowed"
+                    );
+                }
+                if optional.filters_limit.is_none() {
+                    logs::warn!(
+                        "`filters_limit` is not set - unlimited subscriptions to a topic are allowed"
+                    );
+                }
             }
-            (ApiTransport::Http(_), Some(_)) => {
-                logs::warn!(
-                    "`subscriptions_limit` is ignored for HTTP transport, use WebSocket instead"
-                );
+            ApiTransport::Http(_) => {
+                if optional.subscriptions_limit.is_some() || optional.filters_limit.is_some() {
+                    logs::warn!(
+                        "`subscriptions_limit` and `filters_limit` are ignored for HTTP transport, \
+                         use WebSocket instead"
+                    );
+                }
             }
-            _ => {}
+        }
+        if optional.max_connections_per_ip.is_none() {
+            logs::warn!(
+                "`max_connections_per_ip` is not set - unlimited connections per IP are allowed"
+            );
         }
 
-        self.build_jsonrpsee(test, network, stop_receiver).await
+        self.build_jsonrpsee(test, bridge, network, stop_receiver)
+            .await
     }
 
     async fn build_jsonrpsee(
         self,
         test: Test,
+        bridge: BridgeService,
         network: Network,
         stop_receiver: watch::Receiver<bool>,
     ) -> anyhow::Result<ApiServerHandles> {
         let mut tasks = vec![];
+        let clients = Arc::new(Clients::new(
+            self.optional.max_connections_per_ip,
+            &self.optional.trusted_proxies,
+        ));
         let pubsub = if matches!(self.transport, ApiTransport::WebSocket(_))
             && self.namespaces.contains(&Namespace::Pubsub)
         {
-            let pubsub = TestSubscribe::new(self.pool.clone(), network);
+            let pubsub = TestSubscribe::new(
+                self.pool.clone(),
+                network,
+                self.optional.bitcoin_reorgs.clone(),
+            )
+            .with_subscription_limits(SubscriptionLimits::new(
+                self.optional.subscriptions_limit,
+                self.optional.filters_limit,
+            ))
+            .with_clients(clients.clone());
             tasks.extend(pubsub.spawn_notifiers(
                 self.pool.clone(),
                 self.polling_interval,
@@ -284,8 +403,14 @@ impl ApiServer {
         // Start the server in a separate tokio runtime from a dedicated thread.
         let health_check = self.health_updater.subscribe();
         let (local_addr_sender, local_addr) = oneshot::channel();
-        let server_task =
-            tokio::spawn(self.run_jsonrpsee_server(test, pubsub, stop_receiver, local_addr_sender));
+        let server_task = tokio::spawn(self.run_jsonrpsee_server(
+            test,
+            bridge,
+            pubsub,
+            clients,
+            stop_receiver,
+            local_addr_sender,
+        ));
 
         tasks.push(server_task);
         Ok(ApiServerHandles {
@@ -298,7 +423,9 @@ impl ApiServer {
     async fn run_jsonrpsee_server(
         self,
         test: Test,
+        bridge: BridgeService,
         pubsub: Option<TestSubscribe>,
+        clients: Arc<Clients>,
         mut stop_receiver: watch::Receiver<bool>,
         local_addr_sender: oneshot::Sender<SocketAddr>,
     ) -> anyhow::Result<()> {
@@ -318,11 +445,15 @@ impl ApiServer {
             .optional
             .response_body_size_limit
             .map_or(u32::MAX, |limit| limit as u32);
-        let websocket_requests_per_minute_limit = self.optional.websocket_requests_per_minute_limit;
-        let subscriptions_limit = self.optional.subscriptions_limit;
+        let requests_per_minute_per_ip = self.optional.requests_per_minute_per_ip;
+        let requests_per_minute_per_method = self.optional.requests_per_minute_per_method;
+        let method_weights = self.optional.method_weights.clone();
+        let max_connections = self.optional.max_connections.unwrap_or(5_000);
         let health_updater = self.health_updater.clone();
 
-        let rpc = self.build_rpc_module(test, pubsub).await?;
+        let rpc = self
+            .build_rpc_module(test, bridge, pubsub, clients.clone())
+            .await?;
         let registered_method_names = Arc::new(rpc.method_names().collect::<HashSet<_>>());
         logs::debug!(
             "Built RPC module for {transport_str} server with {} methods: {registered_method_names:?}",
@@ -342,20 +473,30 @@ impl ApiServer {
         // Assemble server middleware.
         let middleware = tower::ServiceBuilder::new().option_layer(cors);
 
-        // Settings shared by HTTP and WS servers.
-        let max_connections = !is_http
-            .then_some(subscriptions_limit)
-            .flatten()
-            .unwrap_or(5_000);
-
-        #[allow(clippy::let_and_return)] // simplifies conditional compilation
-        let rpc_middleware = RpcServiceBuilder::new()
-            .layer_fn(move |svc| svc)
-            .option_layer((!is_http).then(|| {
-                tower::layer::layer_fn(move |svc| {
-                    LimitMiddleware::new(svc, websocket_requests_per_minute_limit)
-                })
-            }));
+        let rate_limits = Arc::new(RateLimits::new(
+            requests_per_minute_per_ip,
+            requests_per_minute_per_method,
+            registered_method_names,
+            method_weights,
+        ));
+        // Forget idle clients from time to time, so that the limiter state doesn't keep growing.
+        let mut retention_stop_receiver = stop_receiver.clone();
+        let retained_rate_limits = rate_limits.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RATE_LIMITS_RETENTION_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => retained_rate_limits.retain_recent(),
+                    _ = retention_stop_receiver.changed() => break,
+                }
+            }
+        });
+        // Sessions are built per client, which the rate limits are keyed by.
+        let rpc_middleware = move |client| {
+            let rate_limits = rate_limits.clone();
+            RpcServiceBuilder::new()
+                .layer_fn(move |svc| LimitMiddleware::new(svc, rate_limits.clone(), client))
+        };
 
         let ping_config = PingConfig::default();
         ping_config.inactive_limit(Duration::from_secs(120));
@@ -363,32 +504,36 @@ impl ApiServer {
         ping_config.max_failures(3);
 
         let server_builder = ServerBuilder::default()
-            .max_connections(max_connections as u32)
+            .max_connections(max_connections)
             .set_http_middleware(middleware)
             .max_response_body_size(response_body_size_limit)
             .set_batch_request_config(batch_request_config)
-            .set_rpc_middleware(rpc_middleware)
             .enable_ws_ping(ping_config);
 
-        let (local_addr, server_handle) = if is_http {
-            // HTTP-specific settings
-            let server = server_builder
-                .http_only()
-                .build(addr)
-                .await
-                .context("Failed building HTTP JSON-RPC server")?;
-            (server.local_addr(), server.start(rpc))
+        // HTTP servers don't accept WebSocket upgrades; WS servers accept both.
+        let server_builder = if is_http {
+            server_builder.http_only()
         } else {
-            // WS specific settings
-            let server = server_builder
-                .build(addr)
-                .await
-                .context("Failed building WS JSON-RPC server")?;
-            (server.local_addr(), server.start(rpc))
+            server_builder
         };
-        let local_addr = local_addr.with_context(|| {
-            format!("Failed getting local address for {transport_str} JSON-RPC server")
-        })?;
+        let mut incoming = AddrIncoming::bind(&addr)
+            .with_context(|| format!("Failed building {transport_str} JSON-RPC server"))?;
+        incoming.set_nodelay(true);
+        let local_addr = incoming.local_addr();
+        let (stop_handle, server_handle) = stop_channel();
+        let server = limits::serve(
+            incoming,
+            server_builder.to_service_builder(),
+            rpc_middleware,
+            rpc.into(),
+            clients,
+            stop_handle,
+        );
+        tokio::spawn(async move {
+            if let Err(err) = server.await {
+                logs::error!("{transport_str} JSON-RPC server failed: {err}");
+            }
+        });
         logs::info!("Initialized {transport_str} API on {local_addr:?}");
         local_addr_sender.send(local_addr).ok();
         health_updater.update(HealthStatus::Ready.into());
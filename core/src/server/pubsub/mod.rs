@@ -1,14 +1,15 @@
-use std::time::Duration;
+use std::{collections::HashSet, sync::Arc, time::Duration};
 
 use bitcoin::Network;
+use bridge_rpc::error::Web3Error;
 use futures::FutureExt;
 use jsonrpsee::{
     core::server::SubscriptionMessage,
-    server::IdProvider,
+    server::{ConnectionId, IdProvider},
     types::{error::ErrorCode, ErrorObject, SubscriptionId},
     PendingSubscriptionSink, SendTimeoutError, SubscriptionSink,
 };
-use types::pubsub::PubSubResult;
+use types::pubsub::{PubSubResult, SubscriptionAuth, Task};
 
 use dal::connection::ConnectionPool;
 use tokio::{
@@ -19,10 +20,24 @@ use tokio::{
 };
 use web3::types::H128;
 
+use self::tasks::Subscriber;
+use crate::{
+    nonces::{Nonces, NONCE_TTL},
+    server::{
+        limits::{Clients, SubscriptionLimits},
+        web3::backend::into_rpc_error,
+    },
+};
+
 pub mod rpc;
+mod tasks;
 
 const BROADCAST_CHANNEL_CAPACITY: usize = 8192;
 const SUBSCRIPTION_SINK_SEND_TIMEOUT: Duration = Duration::from_secs(180);
+/// Number of stored tasks loaded at once when replaying them.
+const REPLAY_PAGE_SIZE: u32 = 1_000;
+/// Delay before listening to database changes again after the listener failed.
+const LISTENER_RETRY_DELAY: Duration = Duration::from_secs(1);
 pub const EVENT_TOPIC_NUMBER_LIMIT: usize = 4;
 
 #[derive(Debug, Clone, Copy)]
@@ -43,15 +58,198 @@ pub(super) enum PubSubEvent {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub(super) enum SubscriptionType {
-    Test,
+    BitcoinReorgs,
+    CommitteeTasks,
+    OperatorTasks,
+    ChallengerTasks,
+}
+
+impl SubscriptionType {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bitcoinReorgs" => Some(Self::BitcoinReorgs),
+            "committeeTasks" => Some(Self::CommitteeTasks),
+            "operatorTasks" => Some(Self::OperatorTasks),
+            "challengerTasks" => Some(Self::ChallengerTasks),
+            _ => None,
+        }
+    }
+
+    /// Name of the subscription, also the `topic` of its stored tasks.
+    fn name(self) -> &'static str {
+        match self {
+            Self::BitcoinReorgs => "bitcoinReorgs",
+            Self::CommitteeTasks => "committeeTasks",
+            Self::OperatorTasks => "operatorTasks",
+            Self::ChallengerTasks => "challengerTasks",
+        }
+    }
+}
+
+/// Tasks currently waiting on the role of `sub_type`. Reorgs are pushed by the Bitcoin watcher
+/// and never pending.
+async fn pending_tasks(
+    connection_pool: &ConnectionPool,
+    sub_type: SubscriptionType,
+) -> anyhow::Result<Vec<Task>> {
+    if sub_type == SubscriptionType::BitcoinReorgs {
+        return Ok(vec![]);
+    }
+    let mut storage = connection_pool.access_storage_tagged("api").await?;
+    let tasks = match sub_type {
+        SubscriptionType::BitcoinReorgs => unreachable!(),
+        SubscriptionType::CommitteeTasks => tasks::committee_tasks(&mut storage)
+            .await?
+            .into_iter()
+            .map(Task::Committee)
+            .collect(),
+        SubscriptionType::OperatorTasks => tasks::operator_tasks(&mut storage)
+            .await?
+            .into_iter()
+            .map(Task::Operator)
+            .collect(),
+        SubscriptionType::ChallengerTasks => tasks::challenger_tasks(&mut storage)
+            .await?
+            .into_iter()
+            .map(Task::Challenger)
+            .collect(),
+    };
+    Ok(tasks)
+}
+
+/// Marks `changes` as changed on every notification of [`dal::CHANGES_CHANNEL`], until stopped.
+async fn listen_for_changes(
+    connection_pool: ConnectionPool,
+    changes: watch::Sender<()>,
+    mut stop_receiver: watch::Receiver<bool>,
+) -> anyhow::Result<()> {
+    let mut listener = loop {
+        match connection_pool.listener(&[dal::CHANGES_CHANNEL]).await {
+            Ok(listener) => break listener,
+            Err(err) => logs::warn!("failed to listen to database changes: {err:#}"),
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(LISTENER_RETRY_DELAY) => {}
+            _ = stop_receiver.changed() => return Ok(()),
+        }
+    };
+    loop {
+        let notification = async {
+            match &mut listener {
+                Some(listener) => listener.recv().await,
+                // Test pools never notify; the notifiers poll.
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            notification = notification => {
+                if let Err(err) = notification {
+                    // The listener reconnects on the next `recv()`; changes made meanwhile are
+                    // caught up on by polling.
+                    logs::warn!("failed to receive database changes: {err:#}");
+                    tokio::time::sleep(LISTENER_RETRY_DELAY).await;
+                    continue;
+                }
+                changes.send_replace(());
+            }
+            _ = stop_receiver.changed() => break,
+        }
+    }
+    Ok(())
+}
+
+/// Position of a task subscriber in the stored tasks of its topic.
+#[derive(Debug)]
+struct TaskCursor {
+    connection_pool: ConnectionPool,
+    subscriber: Subscriber,
+    /// Id of the last task sent to the subscriber.
+    last_id: i64,
+}
+
+impl TaskCursor {
+    /// Returns whether `item` is for the subscriber and wasn't sent yet, moving the cursor past it.
+    fn advance(&mut self, item: &PubSubResult) -> bool {
+        match item.task_id() {
+            Some(id) if id <= self.last_id => false,
+            Some(id) => {
+                self.last_id = id;
+                self.subscriber.allows(item)
+            }
+            None => true,
+        }
+    }
+
+    /// Sends the stored tasks of `sub_type` after the cursor.
+    async fn replay(
+        &mut self,
+        sink: &SubscriptionSink,
+        sub_type: SubscriptionType,
+    ) -> anyhow::Result<()> {
+        loop {
+            let mut storage = self.connection_pool.access_storage_tagged("api").await?;
+            let stored = storage
+                .pubsub_tasks_dal()
+                .get_tasks_after(sub_type.name(), self.last_id, REPLAY_PAGE_SIZE)
+                .await?;
+            drop(storage);
+
+            let page_len = stored.len();
+            let items: Vec<_> = stored
+                .into_iter()
+                .map(|stored| stored.payload.0.into_result(stored.id))
+                .filter(|item| self.advance(item))
+                .collect();
+            TestSubscribe::handle_new_items(sink, sub_type, items).await?;
+            if page_len < REPLAY_PAGE_SIZE as usize {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Sends the stored tasks of `sub_type` that are still pending, for subscribers without a
+    /// cursor, and moves the cursor to the latest stored task.
+    async fn send_pending(
+        &mut self,
+        sink: &SubscriptionSink,
+        sub_type: SubscriptionType,
+    ) -> anyhow::Result<()> {
+        let mut pending: HashSet<_> = pending_tasks(&self.connection_pool, sub_type)
+            .await?
+            .into_iter()
+            .collect();
+        let mut storage = self.connection_pool.access_storage_tagged("api").await?;
+        let stored = storage
+            .pubsub_tasks_dal()
+            .get_latest_tasks(sub_type.name(), REPLAY_PAGE_SIZE)
+            .await?;
+        drop(storage);
+
+        self.last_id = stored.last().map_or(0, |stored| stored.id);
+        // A task stored more than once, e.g. pending again after a reorg, is sent with its
+        // latest id.
+        let mut items: Vec<_> = stored
+            .into_iter()
+            .rev()
+            .filter(|stored| pending.remove(&stored.payload.0))
+            .map(|stored| stored.payload.0.into_result(stored.id))
+            .filter(|item| self.subscriber.allows(item))
+            .collect();
+        items.reverse();
+        TestSubscribe::handle_new_items(sink, sub_type, items).await?;
+        Ok(())
+    }
 }
 
 /// Manager of notifications for a certain type of subscriptions.
 #[derive(Debug)]
 struct PubSubNotifier {
     sender: broadcast::Sender<Vec<PubSubResult>>,
-    _connection_pool: ConnectionPool,
+    sub_type: SubscriptionType,
+    connection_pool: ConnectionPool,
     polling_interval: Duration,
+    /// Changed whenever the tables the tasks derive from change.
+    changes: watch::Receiver<()>,
     _events_sender: Option<mpsc::UnboundedSender<PubSubEvent>>,
     _network: Network,
 }
@@ -65,61 +263,235 @@ impl PubSubNotifier {
 }
 
 impl PubSubNotifier {
-    fn send_pub_sub_results(&self, results: Vec<PubSubResult>, _sub_type: SubscriptionType) {
+    fn send_pub_sub_results(&self, results: Vec<PubSubResult>) {
         // Errors only on 0 receivers, but we want to go on if we have 0 subscribers so ignore the error.
         self.sender.send(results).ok();
     }
 
-    // broadcast presign task for committee.
-    async fn notify_new_task(self, stop_receiver: watch::Receiver<bool>) -> anyhow::Result<()> {
+    /// Stores `tasks`, assigning them their ids.
+    async fn store(&self, tasks: Vec<Task>) -> anyhow::Result<Vec<PubSubResult>> {
+        let mut storage = self.connection_pool.access_storage_tagged("api").await?;
+        let mut transaction = storage.start_transaction().await?;
+        let ids = transaction
+            .pubsub_tasks_dal()
+            .insert_tasks(self.sub_type.name(), &tasks)
+            .await?;
+        transaction.commit().await?;
+        Ok(tasks
+            .into_iter()
+            .zip(ids)
+            .map(|(task, id)| task.into_result(id))
+            .collect())
+    }
+
+    /// Tasks stored by a previous run, so that a restart doesn't announce them again.
+    async fn stored_tasks(&self) -> anyhow::Result<HashSet<Task>> {
+        let mut storage = self.connection_pool.access_storage_tagged("api").await?;
+        let stored = storage
+            .pubsub_tasks_dal()
+            .get_latest_tasks(self.sub_type.name(), REPLAY_PAGE_SIZE)
+            .await?;
+        Ok(stored.into_iter().map(|stored| stored.payload.0).collect())
+    }
+
+    /// Loads the tasks of the role on every database change, and every `polling_interval` in case
+    /// a notification was missed. Then stores and broadcasts those that weren't pending at the
+    /// previous tick. Ids are only increasing in the order subscribers see the tasks as long as a
+    /// single notifier stores the tasks of a topic.
+    async fn notify_new_tasks(
+        mut self,
+        mut stop_receiver: watch::Receiver<bool>,
+    ) -> anyhow::Result<()> {
         let mut timer = interval(self.polling_interval);
+        let mut announced = match self.stored_tasks().await {
+            Ok(stored) => stored,
+            Err(err) => {
+                logs::warn!("failed to load stored {:?}: {err:#}", self.sub_type);
+                HashSet::new()
+            }
+        };
         loop {
+            tokio::select! {
+                _ = timer.tick() => {}
+                _ = self.changes.changed() => {}
+                _ = stop_receiver.changed() => break,
+            }
             if *stop_receiver.borrow() {
-                tracing::info!("Stop signal received, pubsub_logs_notifier is shutting down");
                 break;
             }
-            timer.tick().await;
-
-            let new_tasks = self.new_task().await?;
-
-            if new_tasks.len() > 0 {
-                logs::info!("{} new tasks send successfully", new_tasks.len());
-                let tasks = new_tasks
-                    .iter()
-                    .map(|task| PubSubResult::Syncing(task.clone()))
-                    .collect();
-                self.send_pub_sub_results(tasks, SubscriptionType::Test);
 
-                logs::info!("after send pegin committee tasks");
-            } else {
-                logs::info!("no new pegin committee tasks");
+            let pending = match pending_tasks(&self.connection_pool, self.sub_type).await {
+                Ok(pending) => pending,
+                Err(err) => {
+                    logs::warn!("failed to load {:?}: {err:#}", self.sub_type);
+                    continue;
+                }
+            };
+            let new_tasks: Vec<_> = pending
+                .iter()
+                .filter(|task| !announced.contains(*task))
+                .cloned()
+                .collect();
+            if !new_tasks.is_empty() {
+                logs::info!("{} new {:?}", new_tasks.len(), self.sub_type);
+                match self.store(new_tasks).await {
+                    Ok(results) => self.send_pub_sub_results(results),
+                    Err(err) => {
+                        // Announced at the next tick.
+                        logs::warn!("failed to store {:?}: {err:#}", self.sub_type);
+                        continue;
+                    }
+                }
             }
+            // Forget finished tasks, so that the set only holds pending ones.
+            announced = pending.into_iter().collect();
         }
+        logs::info!(
+            "Stop signal received, {:?} notifier is shutting down",
+            self.sub_type
+        );
         Ok(())
     }
-
-    async fn new_task(&self) -> anyhow::Result<Vec<bool>> {
-        Ok(vec![true, false, true])
-    }
 }
 
 pub(super) struct TestSubscribe {
-    _connection_pool: ConnectionPool,
+    connection_pool: ConnectionPool,
     // task senders
-    tests: broadcast::Sender<Vec<PubSubResult>>,
+    bitcoin_reorgs: broadcast::Sender<Vec<PubSubResult>>,
+    committee_tasks: broadcast::Sender<Vec<PubSubResult>>,
+    operator_tasks: broadcast::Sender<Vec<PubSubResult>>,
+    challenger_tasks: broadcast::Sender<Vec<PubSubResult>>,
     events_sender: Option<mpsc::UnboundedSender<PubSubEvent>>,
     network: Network,
+    nonces: Nonces,
+    limits: SubscriptionLimits,
+    clients: Arc<Clients>,
 }
 
 impl TestSubscribe {
-    pub fn new(connection_pool: ConnectionPool, network: Network) -> Self {
-        let (tests, _) = broadcast::channel(BROADCAST_CHANNEL_CAPACITY);
+    /// `bitcoin_reorgs` is the channel the Bitcoin watcher reports reorgs to, if it runs.
+    pub fn new(
+        connection_pool: ConnectionPool,
+        network: Network,
+        bitcoin_reorgs: Option<broadcast::Sender<Vec<PubSubResult>>>,
+    ) -> Self {
+        let bitcoin_reorgs =
+            bitcoin_reorgs.unwrap_or_else(|| broadcast::channel(BROADCAST_CHANNEL_CAPACITY).0);
 
         Self {
-            _connection_pool: connection_pool,
-            tests,
+            connection_pool,
+            bitcoin_reorgs,
+            committee_tasks: broadcast::channel(BROADCAST_CHANNEL_CAPACITY).0,
+            operator_tasks: broadcast::channel(BROADCAST_CHANNEL_CAPACITY).0,
+            challenger_tasks: broadcast::channel(BROADCAST_CHANNEL_CAPACITY).0,
             events_sender: None,
             network,
+            nonces: Nonces::default(),
+            limits: SubscriptionLimits::default(),
+            clients: Arc::new(Clients::new(None, &[])),
+        }
+    }
+
+    pub fn with_subscription_limits(mut self, limits: SubscriptionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Clients of the server, which nonces are issued to.
+    pub(crate) fn with_clients(mut self, clients: Arc<Clients>) -> Self {
+        self.clients = clients;
+        self
+    }
+
+    /// Issues a nonce for `test_getChallenge` called over `connection`, or asks to retry once
+    /// older nonces of the client expired.
+    pub fn issue_nonce(&self, connection: ConnectionId) -> Result<String, Web3Error> {
+        let client = self.clients.ip(connection).ok_or_else(|| {
+            logs::error!("connection {connection} has no client");
+            Web3Error::InternalError
+        })?;
+        self.nonces.issue(client).ok_or(Web3Error::RateLimited {
+            retry_after_ms: Some(NONCE_TTL.as_millis() as u64),
+        })
+    }
+
+    /// Identifies the subscriber of `sub_type`. Committee and operator tasks require a signed
+    /// nonce of a registered key; other subscriptions are open to anyone.
+    async fn authenticate(
+        &self,
+        sub_type: SubscriptionType,
+        auth: Option<SubscriptionAuth>,
+    ) -> Result<Subscriber, Web3Error> {
+        if !matches!(
+            sub_type,
+            SubscriptionType::CommitteeTasks | SubscriptionType::OperatorTasks
+        ) {
+            return Ok(Subscriber::Anonymous);
+        }
+        let auth = auth.ok_or_else(|| {
+            Web3Error::Unauthorized(format!("{} requires a signed nonce", sub_type.name()))
+        })?;
+        if !self.nonces.take(&auth.nonce) {
+            return Err(Web3Error::Unauthorized(format!(
+                "nonce {} is unknown or expired",
+                auth.nonce
+            )));
+        }
+        auth.verify(sub_type.name())
+            .map_err(|err| Web3Error::Unauthorized(err.to_string()))?;
+
+        let unregistered = || {
+            Web3Error::Unauthorized(format!(
+                "{} can't subscribe to {}",
+                auth.public_key,
+                sub_type.name()
+            ))
+        };
+        let internal = |err: &dyn std::fmt::Display| {
+            logs::error!("failed to authenticate {}: {err}", auth.public_key);
+            Web3Error::InternalError
+        };
+        let mut storage = self
+            .connection_pool
+            .access_storage_tagged("api")
+            .await
+            .map_err(|err| internal(&err))?;
+        if sub_type == SubscriptionType::CommitteeTasks {
+            let member = storage
+                .bridges_dal()
+                .get_committee_member(&auth.public_key)
+                .await
+                .map_err(|err| internal(&err))?
+                .ok_or_else(unregistered)?;
+            let bridge = storage
+                .bridges_dal()
+                .get_bridge_by_id(member.bridge_id)
+                .await
+                .map_err(|err| internal(&err))?
+                .ok_or_else(unregistered)?;
+            Ok(Subscriber::CommitteeMember {
+                chain_id: bridge.chain_id,
+            })
+        } else {
+            let operator = storage
+                .operators_dal()
+                .get_operator_by_public_key(&auth.public_key)
+                .await
+                .map_err(|err| internal(&err))?
+                .ok_or_else(unregistered)?;
+            Ok(Subscriber::Operator {
+                id: operator.id,
+                chain_id: operator.chain_id,
+            })
+        }
+    }
+
+    fn sender(&self, sub_type: SubscriptionType) -> &broadcast::Sender<Vec<PubSubResult>> {
+        match sub_type {
+            SubscriptionType::BitcoinReorgs => &self.bitcoin_reorgs,
+            SubscriptionType::CommitteeTasks => &self.committee_tasks,
+            SubscriptionType::OperatorTasks => &self.operator_tasks,
+            SubscriptionType::ChallengerTasks => &self.challenger_tasks,
         }
     }
 
@@ -132,11 +504,15 @@ impl TestSubscribe {
         .await;
     }
 
-    // receiver: broadcast receiver
-    async fn _run_subscriber(
+    /// Forwards the broadcast items to the subscriber. Task subscribers have a `cursor`: the
+    /// tasks they missed when falling behind the broadcast are replayed from the database. The
+    /// subscription ends when the subscriber doesn't keep up with sending, so that it resubscribes
+    /// from its cursor.
+    async fn run_subscriber(
         sink: SubscriptionSink,
         subscription_type: SubscriptionType,
         mut receiver: broadcast::Receiver<Vec<PubSubResult>>,
+        mut cursor: Option<TaskCursor>,
     ) {
         let closed = sink.closed().fuse();
         tokio::pin!(closed);
@@ -144,7 +520,7 @@ impl TestSubscribe {
         loop {
             tokio::select! {
                 new_items_result = receiver.recv() => {
-                    let new_items = match new_items_result {
+                    let mut new_items = match new_items_result {
                         Ok(items) => items,
                         Err(broadcast::error::RecvError::Closed) => {
                             // The broadcast channel has closed because the notifier task is shut down.
@@ -153,37 +529,44 @@ impl TestSubscribe {
                             break;
                         }
                         Err(broadcast::error::RecvError::Lagged(message_count)) => {
-                            logs::error!("skipped_broadcast_message {:?} count {:?}", subscription_type, message_count);
-                            match receiver.try_recv() {
-                                Ok(latest_items) => latest_items,
-                                Err(_) => continue, // No messages available, wait for next one
+                            logs::warn!("skipped_broadcast_message {:?} count {:?}", subscription_type, message_count);
+                            let Some(cursor) = &mut cursor else {
+                                continue;
+                            };
+                            if let Err(err) = cursor.replay(&sink, subscription_type).await {
+                                logs::warn!("failed to replay {subscription_type:?}: {err:#}");
+                                break;
                             }
+                            continue;
                         }
                     };
+                    if let Some(cursor) = &mut cursor {
+                        new_items.retain(|item| cursor.advance(item));
+                    }
 
                     logs::info!("new_items {:?} count {:?}", subscription_type, new_items.len());
 
-                    let handle_result = Self::_handle_new_items(
+                    let handle_result = Self::handle_new_items(
                         &sink,
                         subscription_type,
                         new_items,
                     )
                     .await;
-                    if handle_result.is_err() {
-                        logs::error!("subscriber_send_timeouts {:?} error {:?}", subscription_type, handle_result);
-                        tokio::time::sleep(Duration::from_secs(10)).await;
-                        continue;
+                    if let Err(err) = handle_result {
+                        logs::warn!("subscriber_send_timeouts {:?} error {err}", subscription_type);
+                        break;
                     }
                 }
                 _ = &mut closed => {
                     logs::info!("run_subscriber {:?} closed", subscription_type);
+                    break;
                 }
             }
         }
         logs::info!("run_subscriber {:?} finished", subscription_type);
     }
 
-    async fn _handle_new_items(
+    async fn handle_new_items(
         sink: &SubscriptionSink,
         subscription_type: SubscriptionType,
         new_items: Vec<PubSubResult>,
@@ -203,34 +586,87 @@ impl TestSubscribe {
         Ok(())
     }
 
-    #[logs::instrument(name = "sub_bridge", skip(self, pending_sink))]
-    pub async fn sub(&self, pending_sink: PendingSubscriptionSink, sub_type: String) {
+    /// `from_cursor` is the id of the last task the subscriber received. Without it, a task
+    /// subscriber first receives the tasks still pending.
+    #[logs::instrument(name = "sub_bridge", skip(self, pending_sink, auth))]
+    pub async fn sub(
+        &self,
+        pending_sink: PendingSubscriptionSink,
+        sub_type: String,
+        from_cursor: Option<i64>,
+        auth: Option<SubscriptionAuth>,
+    ) {
         logs::info!("sub {:?}", sub_type);
-        let sub_type = match sub_type.as_str() {
-            "syncing" => {
-                let Ok(sink) = pending_sink.accept().await else {
-                    return;
-                };
-
-                tokio::spawn(async move {
-                    sink.send_timeout(
-                        SubscriptionMessage::from_json(&PubSubResult::Syncing(false)).unwrap(),
-                        SUBSCRIPTION_SINK_SEND_TIMEOUT,
-                    )
-                    .await
-                });
-                None
-            }
-            _ => {
-                Self::reject(pending_sink).await;
-                None
+        if sub_type == "syncing" {
+            let Ok(sink) = pending_sink.accept().await else {
+                return;
+            };
+
+            tokio::spawn(async move {
+                sink.send_timeout(
+                    SubscriptionMessage::from_json(&PubSubResult::Syncing(false)).unwrap(),
+                    SUBSCRIPTION_SINK_SEND_TIMEOUT,
+                )
+                .await
+            });
+            return;
+        }
+        let sub_type = match SubscriptionType::from_name(&sub_type) {
+            // Reorgs aren't stored, so they can't be replayed.
+            Some(SubscriptionType::BitcoinReorgs) if from_cursor.is_some() => None,
+            sub_type => sub_type,
+        };
+        let Some(sub_type) = sub_type else {
+            Self::reject(pending_sink).await;
+            return;
+        };
+        // Limits are checked first, so that subscribing over them doesn't use up the nonce.
+        let admitted = match self
+            .limits
+            .try_acquire(pending_sink.connection_id(), sub_type.name())
+        {
+            Ok(permit) => self
+                .authenticate(sub_type, auth)
+                .await
+                .map(|subscriber| (permit, subscriber)),
+            Err(err) => Err(err),
+        };
+        let (permit, subscriber) = match admitted {
+            Ok(admitted) => admitted,
+            Err(err) => {
+                logs::info!("rejected {sub_type:?} subscription: {err}");
+                pending_sink.reject(into_rpc_error(err)).await;
+                return;
             }
         };
 
-        if let Some(sub_type) = sub_type {
-            if let Some(sender) = &self.events_sender {
-                sender.send(PubSubEvent::Subscribed(sub_type)).ok();
+        let Ok(sink) = pending_sink.accept().await else {
+            return;
+        };
+        // Subscribe before loading stored tasks, so that none falls in between.
+        let receiver = self.sender(sub_type).subscribe();
+        let mut cursor = (sub_type != SubscriptionType::BitcoinReorgs).then(|| TaskCursor {
+            connection_pool: self.connection_pool.clone(),
+            subscriber,
+            last_id: from_cursor.unwrap_or_default(),
+        });
+        tokio::spawn(async move {
+            let _permit = permit;
+            if let Some(cursor) = &mut cursor {
+                let caught_up = match from_cursor {
+                    Some(_) => cursor.replay(&sink, sub_type).await,
+                    None => cursor.send_pending(&sink, sub_type).await,
+                };
+                if let Err(err) = caught_up {
+                    logs::warn!("failed to catch up on {sub_type:?}: {err:#}");
+                    return;
+                }
             }
+            Self::run_subscriber(sink, sub_type, receiver, cursor).await;
+        });
+
+        if let Some(sender) = &self.events_sender {
+            sender.send(PubSubEvent::Subscribed(sub_type)).ok();
         }
     }
 
@@ -241,19 +677,31 @@ impl TestSubscribe {
         polling_interval: Duration,
         stop_receiver: watch::Receiver<bool>,
     ) -> Vec<JoinHandle<anyhow::Result<()>>> {
-        let mut notifier_tasks = Vec::with_capacity(2);
-
-        let tests = PubSubNotifier {
-            sender: self.tests.clone(),
-            _connection_pool: connection_pool.clone(),
-            polling_interval,
-            _events_sender: self.events_sender.clone(),
-            _network: self.network,
-        };
-        let tests_task = tokio::spawn(tests.notify_new_task(stop_receiver.clone()));
-
-        notifier_tasks.push(tests_task);
-
+        let (changes_sender, _) = watch::channel(());
+        let mut notifier_tasks: Vec<_> = [
+            SubscriptionType::CommitteeTasks,
+            SubscriptionType::OperatorTasks,
+            SubscriptionType::ChallengerTasks,
+        ]
+        .into_iter()
+        .map(|sub_type| {
+            let notifier = PubSubNotifier {
+                sender: self.sender(sub_type).clone(),
+                sub_type,
+                connection_pool: connection_pool.clone(),
+                polling_interval,
+                changes: changes_sender.subscribe(),
+                _events_sender: self.events_sender.clone(),
+                _network: self.network,
+            };
+            tokio::spawn(notifier.notify_new_tasks(stop_receiver.clone()))
+        })
+        .collect();
+        notifier_tasks.push(tokio::spawn(listen_for_changes(
+            connection_pool,
+            changes_sender,
+            stop_receiver,
+        )));
         notifier_tasks
     }
 }
@@ -1,10 +1,14 @@
 use bridge_rpc::namespaces::pubsub::TestPubSubServer;
 use jsonrpsee::{
     core::{RpcResult, SubscriptionResult},
+    server::ConnectionDetails,
     PendingSubscriptionSink,
 };
 
+use types::pubsub::SubscriptionAuth;
+
 use super::TestSubscribe;
+use crate::server::web3::backend::into_rpc_error;
 
 #[async_trait::async_trait]
 impl TestPubSubServer for TestSubscribe {
@@ -12,11 +16,17 @@ impl TestPubSubServer for TestSubscribe {
         &self,
         pending: PendingSubscriptionSink,
         sub_type: String,
+        from_cursor: Option<i64>,
+        auth: Option<SubscriptionAuth>,
     ) -> SubscriptionResult {
-        self.sub(pending, sub_type).await;
+        self.sub(pending, sub_type, from_cursor, auth).await;
         Ok(())
     }
 
+    async fn get_challenge(&self, connection: ConnectionDetails) -> RpcResult<String> {
+        self.issue_nonce(connection.id()).map_err(into_rpc_error)
+    }
+
     async fn send_message(&self, _message: String) -> RpcResult<()> {
         Ok(())
     }
@@ -0,0 +1,509 @@
+//! Tasks pushed to committee members, operators and challengers, derived from the peg-ins and
+//! peg-outs waiting on them.
+
+use std::collections::HashMap;
+
+use dal::{error::DalResult, StorageProcessor};
+use types::{
+    pegin::PeginStatus,
+    pegout::PegoutStatus,
+    pubsub::{ChallengerTask, CommitteeTask, OperatorTask, PubSubResult, UnsignedTransaction},
+};
+
+/// Maximum number of rows each query of a notifier tick loads.
+const TASKS_LIMIT: u32 = 1_000;
+
+/// Deposited peg-ins to accept, operator transactions of confirmed peg-ins to sign, and accepted
+/// peg-ins whose deposit was reorged out.
+pub(super) async fn committee_tasks(
+    storage: &mut StorageProcessor<'_>,
+) -> DalResult<Vec<CommitteeTask>> {
+    let pegins = storage
+        .pegins_dal()
+        .get_pegins_by_status(PeginStatus::Deposited, TASKS_LIMIT)
+        .await?;
+    let mut tasks: Vec<_> = pegins
+        .into_iter()
+        .filter_map(|pegin| {
+            Some(CommitteeTask::AcceptPegin {
+                pegin_id: pegin.id,
+                target_chain_id: pegin.target_chain_id,
+                pegin_tx_hash: pegin.pegin_tx_hash?,
+                amount: pegin.amount,
+            })
+        })
+        .collect();
+
+    let chain_ids: HashMap<_, _> = storage
+        .pegins_dal()
+        .get_pegins_by_status(PeginStatus::Confirmed, TASKS_LIMIT)
+        .await?
+        .into_iter()
+        .filter(|pegin| pegin.deposit_reorged_at.is_none())
+        .map(|pegin| (pegin.id, pegin.target_chain_id))
+        .collect();
+    let txs = storage
+        .presigned_transactions_dal()
+        .get_unsigned_transactions(PeginStatus::Confirmed, TASKS_LIMIT)
+        .await?;
+    let mut signing: Vec<CommitteeTask> = vec![];
+    for tx in txs {
+        let (Some(tx_operator_id), Some(&target_chain_id)) =
+            (tx.operator_id, chain_ids.get(&tx.pegin_id))
+        else {
+            continue;
+        };
+        let unsigned = UnsignedTransaction {
+            txid: tx.txid,
+            tx_type: tx.tx_type,
+            raw_hex: tx.raw_hex,
+        };
+        // Transactions come sorted by peg-in and operator.
+        match signing.last_mut() {
+            Some(CommitteeTask::SignPeginTransactions {
+                pegin_id,
+                operator_id,
+                transactions,
+                ..
+            }) if *pegin_id == tx.pegin_id && *operator_id == tx_operator_id => {
+                transactions.push(unsigned);
+            }
+            _ => signing.push(CommitteeTask::SignPeginTransactions {
+                pegin_id: tx.pegin_id,
+                target_chain_id,
+                operator_id: tx_operator_id,
+                transactions: vec![unsigned],
+            }),
+        }
+    }
+    tasks.extend(signing);
+
+    let reorged = storage.pegins_dal().get_reorged_pegins(TASKS_LIMIT).await?;
+    tasks.extend(reorged.into_iter().filter_map(|pegin| {
+        Some(CommitteeTask::DepositReorged {
+            pegin_id: pegin.id,
+            target_chain_id: pegin.target_chain_id,
+            pegin_tx_hash: pegin.pegin_tx_hash?,
+            status: pegin.status,
+        })
+    }));
+    Ok(tasks)
+}
+
+/// Confirmed peg-ins to prepare transactions for, and the next step of operated peg-outs.
+pub(super) async fn operator_tasks(
+    storage: &mut StorageProcessor<'_>,
+) -> DalResult<Vec<OperatorTask>> {
+    let pegins = storage
+        .pegins_dal()
+        .get_pegins_by_status(PeginStatus::Confirmed, TASKS_LIMIT)
+        .await?;
+    let mut tasks: Vec<_> = pegins
+        .into_iter()
+        .filter(|pegin| pegin.deposit_reorged_at.is_none())
+        .filter_map(|pegin| {
+            Some(OperatorTask::PreparePegin {
+                pegin_id: pegin.id,
+                target_chain_id: pegin.target_chain_id,
+                pegin_tx_hash: pegin.pegin_tx_hash?,
+                amount: pegin.amount,
+            })
+        })
+        .collect();
+
+    for status in [
+        PegoutStatus::OperatorAssigned,
+        PegoutStatus::PayoutConfirmed,
+        PegoutStatus::ChallengeWindowClosed,
+    ] {
+        let pegouts = storage
+            .pegouts_dal()
+            .get_pegouts_by_status(status, TASKS_LIMIT)
+            .await?;
+        tasks.extend(pegouts.into_iter().filter_map(|pegout| {
+            let operator_id = pegout.operator_id?;
+            Some(match status {
+                PegoutStatus::OperatorAssigned => OperatorTask::PayPegout {
+                    pegout_id: pegout.id,
+                    operator_id,
+                    receive_address: pegout.receive_address,
+                    amount: pegout.amount,
+                    deadline_height: pegout.deadline_height,
+                },
+                PegoutStatus::PayoutConfirmed => OperatorTask::KickOff {
+                    pegout_id: pegout.id,
+                    operator_id,
+                    payout_tx_hash: pegout.payout_tx_hash?,
+                },
+                _ => OperatorTask::Take {
+                    pegout_id: pegout.id,
+                    operator_id,
+                },
+            })
+        }));
+    }
+    Ok(tasks)
+}
+
+/// Kickoffs that can still be challenged.
+pub(super) async fn challenger_tasks(
+    storage: &mut StorageProcessor<'_>,
+) -> DalResult<Vec<ChallengerTask>> {
+    let pegouts = storage
+        .pegouts_dal()
+        .get_pegouts_by_status(PegoutStatus::ChallengeWindowOpen, TASKS_LIMIT)
+        .await?;
+    Ok(pegouts
+        .into_iter()
+        .filter_map(|pegout| {
+            Some(ChallengerTask::VerifyKickoff {
+                pegout_id: pegout.id,
+                operator_id: pegout.operator_id?,
+                kickoff_tx_hash: pegout.kickoff_tx_hash?,
+                deadline_height: pegout.deadline_height?,
+            })
+        })
+        .collect())
+}
+
+/// Who a subscription delivers to. Registered subscribers only get the tasks of their chain and
+/// their own peg-outs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Subscriber {
+    /// Challengers and reorg subscribers, which aren't registered.
+    Anonymous,
+    CommitteeMember {
+        chain_id: i32,
+    },
+    Operator {
+        id: i32,
+        chain_id: i32,
+    },
+}
+
+impl Subscriber {
+    pub(super) fn allows(&self, item: &PubSubResult) -> bool {
+        match (self, item) {
+            (Self::CommitteeMember { chain_id }, PubSubResult::CommitteeTask(item)) => {
+                match &item.task {
+                    CommitteeTask::AcceptPegin {
+                        target_chain_id, ..
+                    }
+                    | CommitteeTask::SignPeginTransactions {
+                        target_chain_id, ..
+                    }
+                    | CommitteeTask::DepositReorged {
+                        target_chain_id, ..
+                    } => target_chain_id == chain_id,
+                }
+            }
+            (Self::Operator { id, chain_id }, PubSubResult::OperatorTask(item)) => {
+                match &item.task {
+                    OperatorTask::PreparePegin {
+                        target_chain_id, ..
+                    } => target_chain_id == chain_id,
+                    OperatorTask::PayPegout { operator_id, .. }
+                    | OperatorTask::KickOff { operator_id, .. }
+                    | OperatorTask::Take { operator_id, .. } => operator_id == id,
+                }
+            }
+            (_, PubSubResult::CommitteeTask(_) | PubSubResult::OperatorTask(_)) => false,
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::secp256k1::rand::{self, Rng};
+    use dal::connection::ConnectionPool;
+    use types::{
+        bridge::NewBridge,
+        operator::NewOperator,
+        pegin::NewPegin,
+        pegout::{NewPegout, PegoutTransition},
+        presigned_tx::PresignedTxType,
+        pubsub::Task,
+    };
+
+    use super::*;
+
+    const CHAIN_ID: i32 = 31337;
+
+    fn random_hash() -> String {
+        hex::encode(rand::random::<[u8; 32]>())
+    }
+
+    async fn insert_pegin(
+        storage: &mut StorageProcessor<'_>,
+        chain_id: i32,
+        status: PeginStatus,
+    ) -> i32 {
+        let id = storage
+            .pegins_dal()
+            .insert_pegin(&NewPegin {
+                target_chain_id: chain_id,
+                public_key: "02".repeat(33),
+                sender_address: "bcrt1qsender".to_string(),
+                status: PeginStatus::Created,
+                receive_address: "0x0000000000000000000000000000000000000001".to_string(),
+                amount: 100_000,
+            })
+            .await
+            .unwrap();
+        storage
+            .pegins_dal()
+            .set_pegin_tx(id, &format!("pegin{id}"), "0200")
+            .await
+            .unwrap();
+        let path = [
+            PeginStatus::Created,
+            PeginStatus::Deposited,
+            PeginStatus::Confirmed,
+        ];
+        for step in path.windows(2).take_while(|step| step[0] != status) {
+            storage
+                .pegins_dal()
+                .update_pegin_status(id, step[0], step[1])
+                .await
+                .unwrap();
+        }
+        id
+    }
+
+    /// Inserts a peg-out paid by `operator_id` from a fresh peg-in and advances it to `status`.
+    async fn insert_pegout(
+        storage: &mut StorageProcessor<'_>,
+        chain_id: i32,
+        operator_id: i32,
+        status: PegoutStatus,
+    ) -> i32 {
+        let pegin_id = insert_pegin(storage, chain_id, PeginStatus::Created).await;
+        let id = storage
+            .pegouts_dal()
+            .insert_pegout(&NewPegout {
+                chain_id,
+                burn_tx_hash: format!("0x{}", random_hash()),
+                event_idx: 0,
+                sender_address: "0x0000000000000000000000000000000000000001".to_string(),
+                receive_address: "bcrt1qreceiver".to_string(),
+                amount: 50_000,
+                status: PegoutStatus::BurnObserved,
+            })
+            .await
+            .unwrap();
+        let dal = &mut storage.pegouts_dal();
+        dal.assign_operator(id, pegin_id, operator_id)
+            .await
+            .unwrap();
+        dal.set_payout_tx_hash(id, "payout").await.unwrap();
+        dal.set_kickoff_tx_hash(id, "kickoff").await.unwrap();
+        let path = [
+            PegoutStatus::BurnObserved,
+            PegoutStatus::OperatorAssigned,
+            PegoutStatus::PayoutBroadcast,
+            PegoutStatus::PayoutConfirmed,
+            PegoutStatus::KickedOff,
+            PegoutStatus::ChallengeWindowOpen,
+        ];
+        for step in path.windows(2).take_while(|step| step[0] != status) {
+            let transition = PegoutTransition {
+                from: step[0],
+                to: step[1],
+                block_height: None,
+                deadline_height: Some(150),
+            };
+            dal.update_pegout_status(id, transition).await.unwrap();
+        }
+        id
+    }
+
+    #[tokio::test]
+    async fn deriving_tasks_from_pending_work() {
+        let pool = ConnectionPool::test_pool().await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let chain_id = rand::thread_rng().gen_range(1..i32::MAX);
+        storage
+            .bridges_dal()
+            .insert_bridge(&NewBridge {
+                chain_id,
+                chain_name: "anvil".to_string(),
+                operator_manager_address: "0x0000000000000000000000000000000000000001".to_string(),
+                assertion_taproot_address: "bcrt1q".to_string(),
+                status: "active".to_string(),
+                bridge_contract_address: None,
+            })
+            .await
+            .unwrap();
+        let operator_id = storage
+            .operators_dal()
+            .insert_operator(&NewOperator {
+                address: "0x0000000000000000000000000000000000000002".to_string(),
+                chain_id,
+                public_key: format!("02{}", random_hash()),
+                fee: 1_000,
+                status: "active".to_string(),
+                stake_amount: 10_000_000,
+                max_support_amount: 100_000_000,
+                min_support_amount: 10_000,
+                max_pegin_cnt: 10,
+                max_pegout_cnt: 10,
+                register_at: chrono::Utc::now().naive_utc(),
+            })
+            .await
+            .unwrap();
+
+        let deposited = insert_pegin(&mut storage, chain_id, PeginStatus::Deposited).await;
+        let confirmed = insert_pegin(&mut storage, chain_id, PeginStatus::Confirmed).await;
+        // Transactions of a peg-in are listed by txid.
+        let mut txids = [random_hash(), random_hash()];
+        txids.sort();
+        for txid in &txids {
+            storage
+                .presigned_transactions_dal()
+                .insert_presigned_transaction(
+                    txid,
+                    PresignedTxType::Take,
+                    confirmed,
+                    Some(operator_id),
+                    "0200",
+                )
+                .await
+                .unwrap();
+        }
+        let unsigned = |txid: &str| UnsignedTransaction {
+            txid: txid.to_string(),
+            tx_type: PresignedTxType::Take,
+            raw_hex: "0200".to_string(),
+        };
+        assert_eq!(
+            committee_tasks(&mut storage).await.unwrap(),
+            [
+                CommitteeTask::AcceptPegin {
+                    pegin_id: deposited,
+                    target_chain_id: chain_id,
+                    pegin_tx_hash: format!("pegin{deposited}"),
+                    amount: 100_000,
+                },
+                CommitteeTask::SignPeginTransactions {
+                    pegin_id: confirmed,
+                    target_chain_id: chain_id,
+                    operator_id,
+                    transactions: txids.iter().map(|txid| unsigned(txid)).collect(),
+                },
+            ]
+        );
+
+        let kicked_off = insert_pegout(
+            &mut storage,
+            chain_id,
+            operator_id,
+            PegoutStatus::PayoutConfirmed,
+        )
+        .await;
+        let challengeable = insert_pegout(
+            &mut storage,
+            chain_id,
+            operator_id,
+            PegoutStatus::ChallengeWindowOpen,
+        )
+        .await;
+        assert_eq!(
+            operator_tasks(&mut storage).await.unwrap(),
+            [
+                OperatorTask::PreparePegin {
+                    pegin_id: confirmed,
+                    target_chain_id: chain_id,
+                    pegin_tx_hash: format!("pegin{confirmed}"),
+                    amount: 100_000,
+                },
+                OperatorTask::KickOff {
+                    pegout_id: kicked_off,
+                    operator_id,
+                    payout_tx_hash: "payout".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            challenger_tasks(&mut storage).await.unwrap(),
+            [ChallengerTask::VerifyKickoff {
+                pegout_id: challengeable,
+                operator_id,
+                kickoff_tx_hash: "kickoff".to_string(),
+                deadline_height: 150,
+            }]
+        );
+
+        let reorged_tx_hash = format!("pegin{confirmed}");
+        storage
+            .pegins_dal()
+            .flag_reorged_deposits(&[reorged_tx_hash.clone()])
+            .await
+            .unwrap();
+        assert_eq!(
+            committee_tasks(&mut storage).await.unwrap(),
+            [
+                CommitteeTask::AcceptPegin {
+                    pegin_id: deposited,
+                    target_chain_id: chain_id,
+                    pegin_tx_hash: format!("pegin{deposited}"),
+                    amount: 100_000,
+                },
+                CommitteeTask::DepositReorged {
+                    pegin_id: confirmed,
+                    target_chain_id: chain_id,
+                    pegin_tx_hash: reorged_tx_hash,
+                    status: PeginStatus::Confirmed,
+                },
+            ]
+        );
+        assert!(!operator_tasks(&mut storage)
+            .await
+            .unwrap()
+            .iter()
+            .any(|task| matches!(task, OperatorTask::PreparePegin { .. })));
+    }
+
+    #[test]
+    fn scoping_tasks_to_subscribers() {
+        let accept = Task::Committee(CommitteeTask::AcceptPegin {
+            pegin_id: 1,
+            target_chain_id: CHAIN_ID,
+            pegin_tx_hash: "pegin".to_string(),
+            amount: 100_000,
+        })
+        .into_result(1);
+        let kick_off = Task::Operator(OperatorTask::KickOff {
+            pegout_id: 1,
+            operator_id: 2,
+            payout_tx_hash: "payout".to_string(),
+        })
+        .into_result(2);
+        let verify = Task::Challenger(ChallengerTask::VerifyKickoff {
+            pegout_id: 1,
+            operator_id: 2,
+            kickoff_tx_hash: "kickoff".to_string(),
+            deadline_height: 150,
+        })
+        .into_result(3);
+
+        let member = Subscriber::CommitteeMember { chain_id: CHAIN_ID };
+        assert!(member.allows(&accept));
+        assert!(!Subscriber::CommitteeMember { chain_id: 1 }.allows(&accept));
+        assert!(!member.allows(&kick_off));
+
+        let operator = Subscriber::Operator {
+            id: 2,
+            chain_id: CHAIN_ID,
+        };
+        assert!(operator.allows(&kick_off));
+        assert!(!Subscriber::Operator {
+            id: 3,
+            chain_id: CHAIN_ID
+        }
+        .allows(&kick_off));
+
+        assert!(Subscriber::Anonymous.allows(&verify));
+        assert!(!Subscriber::Anonymous.allows(&accept));
+    }
+}
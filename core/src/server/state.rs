@@ -1,14 +1,32 @@
 use std::sync::Arc;
 
+use bridge_rpc::error::Web3Error;
 use dal::connection::ConnectionPool;
 
-use crate::test::Test;
+use crate::{
+    bridge::BridgeService,
+    chains::{Chain, ChainRegistry},
+    test::Test,
+};
 
-use super::web3::backend::metadata::MethodTracer;
+use super::{limits::Clients, web3::backend::metadata::MethodTracer};
 
 #[derive(Debug, Clone)]
 pub struct RpcState {
     pub(super) _current_method: Arc<MethodTracer>,
     pub(super) _connection_pool: ConnectionPool,
+    pub(super) chains: Arc<ChainRegistry>,
+    pub(super) bridge: BridgeService,
+    /// Clients of the server, for methods to tell who calls them.
+    pub(super) clients: Arc<Clients>,
     pub test: Test,
 }
+
+impl RpcState {
+    /// Resolves the target chain of a query, rejecting chains the node doesn't serve.
+    pub fn chain(&self, chain_id: i32) -> Result<&Chain, Web3Error> {
+        self.chains
+            .get(chain_id)
+            .ok_or(Web3Error::UnsupportedChain(chain_id))
+    }
+}
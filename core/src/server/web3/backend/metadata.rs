@@ -36,7 +36,6 @@ pub(crate) struct MethodTracer {
     inner: ThreadLocal<CurrentMethodInner>,
 }
 
-
 #[derive(Debug)]
 pub(super) struct MethodCall {
     tracer: Arc<MethodTracer>,
@@ -1,40 +1,141 @@
 use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    net::IpAddr,
     num::NonZeroU32,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 
+use bridge_rpc::error::Web3Error;
 use futures::Future;
 use governor::{
-    clock::DefaultClock,
-    middleware::NoOpMiddleware,
-    state::{InMemoryState, NotKeyed},
-    Quota, RateLimiter,
+    clock::{Clock, DefaultClock},
+    state::keyed::DefaultKeyedStateStore,
+    NegativeMultiDecision, Quota, RateLimiter,
 };
 use jsonrpsee::{
     server::middleware::rpc::{layer::ResponseFuture, RpcServiceT},
-    types::{error::ErrorCode, ErrorObject, Request},
+    types::Request,
     MethodResponse,
 };
 
-use super::metadata::MethodCall;
+use super::{into_rpc_error, metadata::MethodCall};
 
 use pin_project_lite::pin_project;
 
+type KeyedRateLimiter<K> = RateLimiter<K, DefaultKeyedStateStore<K>, DefaultClock>;
+
+fn keyed_limiter<K: Clone + Eq + Hash>(
+    requests_per_minute: NonZeroU32,
+    clock: &DefaultClock,
+) -> KeyedRateLimiter<K> {
+    RateLimiter::new(
+        Quota::per_minute(requests_per_minute),
+        Default::default(),
+        clock,
+    )
+}
+
+/// Rate limits shared by all sessions of a server.
+///
+/// Every client IP address gets `requests_per_minute_per_ip`, which each call draws from by the
+/// weight of its method, so that expensive methods such as history queries run out sooner. On
+/// top of that, each client IP address can call each method at most
+/// `requests_per_minute_per_method` times a minute, so that a single client can't hammer one
+/// method with its whole quota, nor exhaust the method for the others. Each call of a batch
+/// passes through the limits on its own, so a batch counts by its size.
+#[derive(Debug)]
+pub(crate) struct RateLimits {
+    clock: DefaultClock,
+    per_ip: Option<KeyedRateLimiter<IpAddr>>,
+    per_method: Option<KeyedRateLimiter<(IpAddr, &'static str)>>,
+    /// Methods served by the server; calls of other methods are only limited per IP address.
+    methods: Arc<HashSet<&'static str>>,
+    method_weights: HashMap<String, NonZeroU32>,
+}
+
+impl RateLimits {
+    pub(crate) fn new(
+        requests_per_minute_per_ip: Option<NonZeroU32>,
+        requests_per_minute_per_method: Option<NonZeroU32>,
+        methods: Arc<HashSet<&'static str>>,
+        method_weights: HashMap<String, NonZeroU32>,
+    ) -> Self {
+        let clock = DefaultClock::default();
+        Self {
+            per_ip: requests_per_minute_per_ip.map(|limit| keyed_limiter(limit, &clock)),
+            per_method: requests_per_minute_per_method.map(|limit| keyed_limiter(limit, &clock)),
+            clock,
+            methods,
+            method_weights,
+        }
+    }
+
+    fn check(&self, client: IpAddr, method: &str) -> Result<(), Web3Error> {
+        if let Some(per_ip) = &self.per_ip {
+            let weight = self
+                .method_weights
+                .get(method)
+                .copied()
+                .unwrap_or(NonZeroU32::MIN);
+            self.check_key(per_ip, &client, weight)?;
+        }
+        if let (Some(per_method), Some(method)) = (&self.per_method, self.methods.get(method)) {
+            self.check_key(per_method, &(client, *method), NonZeroU32::MIN)?;
+        }
+        Ok(())
+    }
+
+    fn check_key<K: Clone + Eq + Hash>(
+        &self,
+        limiter: &KeyedRateLimiter<K>,
+        key: &K,
+        cost: NonZeroU32,
+    ) -> Result<(), Web3Error> {
+        match limiter.check_key_n(key, cost) {
+            Ok(()) => Ok(()),
+            Err(NegativeMultiDecision::BatchNonConforming(_, not_until)) => {
+                let retry_after = not_until.wait_time_from(self.clock.now());
+                Err(Web3Error::RateLimited {
+                    retry_after_ms: Some(retry_after.as_millis() as u64),
+                })
+            }
+            // The cost is above the quota, so waiting won't help.
+            Err(NegativeMultiDecision::InsufficientCapacity(_)) => Err(Web3Error::RateLimited {
+                retry_after_ms: None,
+            }),
+        }
+    }
+
+    /// Forgets the clients and methods whose limits were fully replenished.
+    pub(crate) fn retain_recent(&self) {
+        if let Some(per_ip) = &self.per_ip {
+            per_ip.retain_recent();
+        }
+        if let Some(per_method) = &self.per_method {
+            per_method.retain_recent();
+        }
+    }
+}
+
 /// A rate-limiting middleware.
 ///
-/// `jsonrpsee` will allocate the instance of this struct once per session.
+/// `jsonrpsee` will allocate the instance of this struct once per session: a WebSocket
+/// connection, or an HTTP request.
 pub(crate) struct LimitMiddleware<S> {
     inner: S,
-    rate_limiter: Option<RateLimiter<NotKeyed, InMemoryState, DefaultClock, NoOpMiddleware>>,
+    rate_limits: Arc<RateLimits>,
+    client: IpAddr,
 }
 
 impl<S> LimitMiddleware<S> {
-    pub(crate) fn new(inner: S, requests_per_minute_limit: Option<NonZeroU32>) -> Self {
+    pub(crate) fn new(inner: S, rate_limits: Arc<RateLimits>, client: IpAddr) -> Self {
         Self {
             inner,
-            rate_limiter: requests_per_minute_limit
-                .map(|limit| RateLimiter::direct(Quota::per_minute(limit))),
+            rate_limits,
+            client,
         }
     }
 }
@@ -46,26 +147,10 @@ where
     type Future = ResponseFuture<S::Future>;
 
     fn call(&self, request: Request<'a>) -> Self::Future {
-        if let Some(rate_limiter) = &self.rate_limiter {
-            let num_requests = NonZeroU32::MIN; // 1 request, no batches possible
-
-            // Note: if required, we can extract data on rate limiting from the error.
-            if rate_limiter.check_n(num_requests).is_err() {
-                // METRICS.rate_limited[&self.transport].inc();
-
-                let rp = MethodResponse::error(
-                    request.id,
-                    ErrorObject::borrowed(
-                        ErrorCode::ServerError(
-                            reqwest::StatusCode::TOO_MANY_REQUESTS.as_u16().into(),
-                        )
-                        .code(),
-                        "Too many requests",
-                        None,
-                    ),
-                );
-                return ResponseFuture::ready(rp);
-            }
+        if let Err(err) = self.rate_limits.check(self.client, &request.method) {
+            // METRICS.rate_limited[&self.transport].inc();
+            let rp = MethodResponse::error(request.id, into_rpc_error(err));
+            return ResponseFuture::ready(rp);
         }
         ResponseFuture::future(self.inner.call(request))
     }
@@ -96,3 +181,69 @@ impl<F: Future<Output = MethodResponse>> Future for WithMethodCall<F> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+    const BOB: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
+
+    fn limit(limit: u32) -> Option<NonZeroU32> {
+        NonZeroU32::new(limit)
+    }
+
+    fn assert_rate_limited(result: Result<(), Web3Error>) {
+        let Err(Web3Error::RateLimited { retry_after_ms }) = result else {
+            panic!("call wasn't rate limited: {result:?}");
+        };
+        let retry_after_ms = retry_after_ms.expect("retry delay is known");
+        assert!(
+            retry_after_ms > 0 && retry_after_ms <= 60_000,
+            "{retry_after_ms}"
+        );
+    }
+
+    #[test]
+    fn limiting_calls_per_ip_by_method_weight() {
+        let weights = HashMap::from([("bridge_getPegoutHistory".to_string(), limit(3).unwrap())]);
+        let limits = RateLimits::new(limit(4), None, Arc::default(), weights);
+
+        limits.check(ALICE, "bridge_getPegoutHistory").unwrap();
+        limits.check(ALICE, "bridge_getPegin").unwrap();
+        assert_rate_limited(limits.check(ALICE, "bridge_getPegoutHistory"));
+        assert_rate_limited(limits.check(ALICE, "bridge_getPegin"));
+
+        // Other clients have quotas of their own.
+        limits.check(BOB, "bridge_getPegoutHistory").unwrap();
+    }
+
+    #[test]
+    fn limiting_calls_per_method() {
+        let methods = Arc::new(HashSet::from(["bridge_getPegin", "bridge_getPegout"]));
+        let limits = RateLimits::new(None, limit(1), methods, HashMap::new());
+
+        limits.check(ALICE, "bridge_getPegin").unwrap();
+        assert_rate_limited(limits.check(ALICE, "bridge_getPegin"));
+        limits.check(ALICE, "bridge_getPegout").unwrap();
+        // One client exhausting a method doesn't lock the others out of it.
+        limits.check(BOB, "bridge_getPegin").unwrap();
+        assert_rate_limited(limits.check(BOB, "bridge_getPegin"));
+        // Unknown methods aren't tracked, so that they can't grow the limiter state.
+        limits.check(ALICE, "bridge_unknown").unwrap();
+        limits.check(ALICE, "bridge_unknown").unwrap();
+    }
+
+    #[test]
+    fn weights_above_the_quota_are_never_served() {
+        let weights = HashMap::from([("bridge_getPegoutHistory".to_string(), limit(5).unwrap())]);
+        let limits = RateLimits::new(limit(4), None, Arc::default(), weights);
+        let result = limits.check(ALICE, "bridge_getPegoutHistory");
+        assert!(matches!(
+            result,
+            Err(Web3Error::RateLimited {
+                retry_after_ms: None
+            })
+        ));
+    }
+}
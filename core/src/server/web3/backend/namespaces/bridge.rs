@@ -1,7 +1,10 @@
 use crate::server::web3::{backend::into_rpc_error, namespaces::bridge::BridgeNamespace};
 use bitcoin::ScriptBuf;
-use bridge_rpc::namespaces::bridge::BridgeNamespaceServer;
-use jsonrpsee::core::{async_trait, RpcResult};
+use bridge_rpc::namespaces::bridge::{BridgeNamespaceServer, NonceNamespaceServer};
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    server::ConnectionDetails,
+};
 use types::challenger::{
     ChallengeStatus, QueryChallengeDataReponse, QueryChallengeDataRequest, StartChallengeResponse,
 };
@@ -13,11 +16,11 @@ use types::pegout::{
 use types::pubsub::{
     ChallengeCommitteeTaskResponse, OperatorPegoutTxRequest, PeginCommitteeTaskResponse,
     PeginOperatorTaskResponse, PeginReceivedRequest, PegoutOperatorTaskResponse,
-    PegoutReceivedRequest,
+    PegoutReceivedRequest, SignedRequest,
 };
 use types::rpc::{
-    LPPeginRequest, LPPegoutRequest, PeginEventRangeRequest, PeginRequestFrontend,
-    PeginTakeTxMsgReponse, PeginTakeTxMsgRequest, PegoutRequest, PegouttEventRangeRequest,
+    LPPeginRequest, LPPegoutRequest, PeginEventRangeRequest, PeginTakeTxMsgReponse,
+    PeginTakeTxMsgRequest, PegoutRequest, PegouttEventRangeRequest,
 };
 use types::{
     operator::{Operator, OperatorFilter},
@@ -81,8 +84,12 @@ impl BridgeNamespaceServer for BridgeNamespace {
             .map_err(into_rpc_error)
     }
 
-    async fn get_pegout_detail_by_burn_tx(&self, burn_tx_hash: String) -> RpcResult<PegoutDetail> {
-        self.query_pegout_detail_by_burn_tx_hash_impl(burn_tx_hash)
+    async fn get_pegout_detail_by_burn_tx(
+        &self,
+        burn_tx_hash: String,
+        event_idx: Option<u32>,
+    ) -> RpcResult<PegoutDetail> {
+        self.query_pegout_detail_by_burn_tx_hash_impl(burn_tx_hash, event_idx)
             .await
             .map_err(into_rpc_error)
     }
@@ -113,7 +120,7 @@ impl BridgeNamespaceServer for BridgeNamespace {
 
     async fn try_lock_pegin_utxo(
         &self,
-        request: LPPegoutRequest,
+        request: SignedRequest<LPPegoutRequest>,
     ) -> RpcResult<Option<PeginOperation>> {
         self.try_lock_pegin_utxo_impl(request)
             .await
@@ -183,7 +190,7 @@ impl BridgeNamespaceServer for BridgeNamespace {
 
     async fn submit_pegin_operator_presigned_transactions(
         &self,
-        presigned_txs: PeginOperatorTaskResponse,
+        presigned_txs: SignedRequest<PeginOperatorTaskResponse>,
     ) -> RpcResult<()> {
         self.submit_pegin_operator_presigned_transactions_impl(presigned_txs)
             .await
@@ -192,7 +199,7 @@ impl BridgeNamespaceServer for BridgeNamespace {
 
     async fn submit_pegout_operator_response(
         &self,
-        pegout_response: PegoutOperatorTaskResponse,
+        pegout_response: SignedRequest<PegoutOperatorTaskResponse>,
     ) -> RpcResult<()> {
         self.submit_pegout_operator_response_impl(pegout_response)
             .await
@@ -201,20 +208,26 @@ impl BridgeNamespaceServer for BridgeNamespace {
 
     async fn submit_operator_pegout_tx(
         &self,
-        pegout_request: OperatorPegoutTxRequest,
+        pegout_request: SignedRequest<OperatorPegoutTxRequest>,
     ) -> RpcResult<()> {
         self.submit_operator_pegout_tx_impl(pegout_request)
             .await
             .map_err(into_rpc_error)
     }
 
-    async fn submit_pegout_received(&self, request: PegoutReceivedRequest) -> RpcResult<()> {
+    async fn submit_pegout_received(
+        &self,
+        request: SignedRequest<PegoutReceivedRequest>,
+    ) -> RpcResult<()> {
         self.submit_pegout_received_impl(request)
             .await
             .map_err(into_rpc_error)
     }
 
-    async fn submit_pegin_received(&self, request: PeginReceivedRequest) -> RpcResult<()> {
+    async fn submit_pegin_received(
+        &self,
+        request: SignedRequest<PeginReceivedRequest>,
+    ) -> RpcResult<()> {
         self.submit_pegin_received_impl(request)
             .await
             .map_err(into_rpc_error)
@@ -238,3 +251,10 @@ impl BridgeNamespaceServer for BridgeNamespace {
             .map_err(into_rpc_error)
     }
 }
+
+#[async_trait]
+impl NonceNamespaceServer for BridgeNamespace {
+    async fn get_nonce(&self, connection: ConnectionDetails) -> RpcResult<String> {
+        self.get_nonce_impl(connection.id()).map_err(into_rpc_error)
+    }
+}
@@ -0,0 +1,16 @@
+use bridge_rpc::namespaces::chain::ChainNamespaceServer;
+use jsonrpsee::core::{async_trait, RpcResult};
+use types::bridge::Bridge;
+
+use crate::server::web3::{backend::into_rpc_error, namespaces::chain::ChainNamespace};
+
+#[async_trait]
+impl ChainNamespaceServer for ChainNamespace {
+    async fn supported_chains(&self) -> RpcResult<Vec<Bridge>> {
+        Ok(self.supported_chains_impl())
+    }
+
+    async fn get_bridge(&self, chain_id: i32) -> RpcResult<Bridge> {
+        self.get_bridge_impl(chain_id).map_err(into_rpc_error)
+    }
+}
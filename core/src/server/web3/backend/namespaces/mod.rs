@@ -1 +1,3 @@
+pub mod bridge;
+pub mod chain;
 pub mod test;
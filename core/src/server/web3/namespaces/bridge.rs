@@ -0,0 +1,166 @@
+use bitcoin::ScriptBuf;
+use bridge_rpc::error::Web3Error;
+use jsonrpsee::server::ConnectionId;
+use types::{
+    operator::{Operator, OperatorFilter},
+    pegin::{PeginDetails, PeginEventDetails, PeginOperation},
+    pegout::{PegoutDetail, PegoutEventDetail, PegoutInfo},
+    pubsub::SignedRequest,
+    rpc::{
+        LPPeginRequest, LPPegoutRequest, PeginCreateRequest, PeginCreateResponse,
+        PeginEventRangeRequest, PeginRequest, PeginTakeTxMsgReponse, PeginTakeTxMsgRequest,
+        PegoutRequest, PegouttEventRangeRequest,
+    },
+};
+
+use crate::{nonces::NONCE_TTL, server::state::RpcState};
+
+#[derive(Debug, Clone)]
+pub struct BridgeNamespace {
+    pub state: RpcState,
+}
+
+impl BridgeNamespace {
+    pub fn new(state: RpcState) -> Self {
+        Self { state }
+    }
+}
+
+impl BridgeNamespace {
+    /// Issues a nonce to the client of `connection`, or asks to retry once older nonces of the
+    /// client expired.
+    pub fn get_nonce_impl(&self, connection: ConnectionId) -> Result<String, Web3Error> {
+        let client = self.state.clients.ip(connection).ok_or_else(|| {
+            logs::error!("connection {connection} has no client");
+            Web3Error::InternalError
+        })?;
+        self.state
+            .bridge
+            .issue_nonce(client)
+            .ok_or(Web3Error::RateLimited {
+                retry_after_ms: Some(NONCE_TTL.as_millis() as u64),
+            })
+    }
+
+    pub async fn get_pegin_multi_sig_script_impl(
+        &self,
+        pubkey: &str,
+    ) -> Result<ScriptBuf, Web3Error> {
+        Ok(self.state.bridge.get_pegin_multi_sig_script(pubkey).await?)
+    }
+
+    pub async fn query_operators_impl(
+        &self,
+        filter: OperatorFilter,
+    ) -> Result<Vec<Operator>, Web3Error> {
+        Ok(self.state.bridge.query_operators(filter).await?)
+    }
+
+    pub async fn query_pegin_take_tx_sign_msg_impl(
+        &self,
+        request: PeginTakeTxMsgRequest,
+    ) -> Result<PeginTakeTxMsgReponse, Web3Error> {
+        Ok(self
+            .state
+            .bridge
+            .query_pegin_take_tx_sign_msg(request)
+            .await?)
+    }
+
+    pub async fn create_pegin_impl(
+        &self,
+        request: PeginCreateRequest,
+    ) -> Result<PeginCreateResponse, Web3Error> {
+        Ok(self.state.bridge.create_pegin(request).await?)
+    }
+
+    pub async fn submit_pegin_impl(&self, request: PeginRequest) -> Result<u32, Web3Error> {
+        Ok(self.state.bridge.submit_pegin(request).await?)
+    }
+
+    pub async fn submit_lp_pegin_impl(&self, request: LPPeginRequest) -> Result<u32, Web3Error> {
+        Ok(self.state.bridge.submit_lp_pegin(request).await?)
+    }
+
+    pub async fn query_pegin_details_impl(&self, pegin_id: u32) -> Result<PeginDetails, Web3Error> {
+        Ok(self.state.bridge.pegin(pegin_id as i32).await?)
+    }
+
+    pub async fn query_pegout_detail_by_id_impl(
+        &self,
+        pegout_id: u32,
+    ) -> Result<PegoutDetail, Web3Error> {
+        Ok(self.state.bridge.pegout(pegout_id as i32).await?)
+    }
+
+    pub async fn query_pegout_detail_by_burn_tx_hash_impl(
+        &self,
+        burn_tx_hash: String,
+        event_idx: Option<u32>,
+    ) -> Result<PegoutDetail, Web3Error> {
+        Ok(self
+            .state
+            .bridge
+            .pegout_by_burn_tx_hash(&burn_tx_hash, event_idx.map(|idx| idx as i32))
+            .await?)
+    }
+
+    pub async fn get_pegout_history_impl(
+        &self,
+        request: PegoutRequest,
+    ) -> Result<Vec<PegoutInfo>, Web3Error> {
+        Ok(self.state.bridge.get_pegout_history(request).await?)
+    }
+
+    pub async fn get_pegin_history_impl(
+        &self,
+        address: &str,
+    ) -> Result<Vec<PeginDetails>, Web3Error> {
+        Ok(self.state.bridge.get_pegin_history(address).await?)
+    }
+
+    pub async fn get_avaliable_pegout_amount_impl(
+        &self,
+        amount: u64,
+        n: u32,
+    ) -> Result<Vec<u64>, Web3Error> {
+        Ok(self
+            .state
+            .bridge
+            .get_available_pegout_amounts(amount, n)
+            .await?)
+    }
+
+    pub async fn query_lp_pending_pegouts_impl(&self) -> Result<Vec<PegoutInfo>, Web3Error> {
+        Ok(self.state.bridge.query_lp_pending_pegouts().await?)
+    }
+
+    pub async fn try_lock_pegin_utxo_impl(
+        &self,
+        request: SignedRequest<LPPegoutRequest>,
+    ) -> Result<Option<PeginOperation>, Web3Error> {
+        Ok(self.state.bridge.try_lock_pegin_utxo(request).await?)
+    }
+
+    pub async fn query_pegin_events_by_range_impl(
+        &self,
+        request: PeginEventRangeRequest,
+    ) -> Result<Vec<PeginEventDetails>, Web3Error> {
+        Ok(self
+            .state
+            .bridge
+            .query_pegin_events_by_range(request)
+            .await?)
+    }
+
+    pub async fn query_pegout_events_by_range_impl(
+        &self,
+        request: PegouttEventRangeRequest,
+    ) -> Result<Vec<PegoutEventDetail>, Web3Error> {
+        Ok(self
+            .state
+            .bridge
+            .query_pegout_events_by_range(request)
+            .await?)
+    }
+}
@@ -0,0 +1,28 @@
+use crate::server::state::RpcState;
+use bridge_rpc::error::Web3Error;
+use types::bridge::Bridge;
+
+#[derive(Debug, Clone)]
+pub struct ChainNamespace {
+    pub state: RpcState,
+}
+
+impl ChainNamespace {
+    pub fn new(state: RpcState) -> Self {
+        Self { state }
+    }
+}
+
+impl ChainNamespace {
+    pub fn supported_chains_impl(&self) -> Vec<Bridge> {
+        self.state
+            .chains
+            .chains()
+            .map(|chain| chain.bridge.clone())
+            .collect()
+    }
+
+    pub fn get_bridge_impl(&self, chain_id: i32) -> Result<Bridge, Web3Error> {
+        Ok(self.state.chain(chain_id)?.bridge.clone())
+    }
+}
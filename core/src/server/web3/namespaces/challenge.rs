@@ -1,17 +1,9 @@
-use crate::server::web3::backend::into_rpc_error;
 use crate::server::web3::namespaces::bridge::BridgeNamespace;
 use bridge_rpc::error::Web3Error;
-use jsonrpsee::core::{async_trait, RpcResult};
 use types::challenger::{
     ChallengeStatus, QueryChallengeDataReponse, QueryChallengeDataRequest, StartChallengeResponse,
 };
 use types::pegout::{PegoutOperationRangeResponse, PegoutOperationRangerRequest};
-use types::{
-    operator::{Operator, OperatorFilter},
-    pegin::PeginDetails,
-    rpc::{PeginCreateRequest, PeginCreateResponse, PeginRequest},
-};
-// ) -> Result<ScriptBuf, Web3Error> {
 
 impl BridgeNamespace {
     // 1. query pegout operation: [from_pegout_id, len], len<=50 (limited) order by created time
@@ -1 +1,5 @@
+pub mod bridge;
+pub mod chain;
+pub mod challenge;
+pub mod pubsub;
 pub mod test;
@@ -1,8 +1,80 @@
 use bridge_rpc::error::Web3Error;
+use types::pubsub::{
+    ChallengeCommitteeTaskResponse, OperatorPegoutTxRequest, PeginCommitteeTaskResponse,
+    PeginOperatorTaskResponse, PeginReceivedRequest, PegoutOperatorTaskResponse,
+    PegoutReceivedRequest, SignedRequest,
+};
 
 use super::bridge::BridgeNamespace;
 
+/// Responses to the tasks operators and committee members are notified of over pubsub.
 impl BridgeNamespace {
     pub async fn submit_pegin_operator_presigned_transactions_impl(
-    
+        &self,
+        presigned_txs: SignedRequest<PeginOperatorTaskResponse>,
+    ) -> Result<(), Web3Error> {
+        Ok(self
+            .state
+            .bridge
+            .submit_pegin_operator_presigned_transactions(presigned_txs)
+            .await?)
+    }
+
+    pub async fn submit_pegout_operator_response_impl(
+        &self,
+        pegout_response: SignedRequest<PegoutOperatorTaskResponse>,
+    ) -> Result<(), Web3Error> {
+        Ok(self
+            .state
+            .bridge
+            .submit_pegout_operator_response(pegout_response)
+            .await?)
+    }
+
+    pub async fn submit_operator_pegout_tx_impl(
+        &self,
+        pegout_request: SignedRequest<OperatorPegoutTxRequest>,
+    ) -> Result<(), Web3Error> {
+        Ok(self
+            .state
+            .bridge
+            .submit_operator_pegout_tx(pegout_request)
+            .await?)
+    }
+
+    pub async fn submit_pegout_received_impl(
+        &self,
+        request: SignedRequest<PegoutReceivedRequest>,
+    ) -> Result<(), Web3Error> {
+        Ok(self.state.bridge.submit_pegout_received(request).await?)
+    }
+
+    pub async fn submit_pegin_received_impl(
+        &self,
+        request: SignedRequest<PeginReceivedRequest>,
+    ) -> Result<(), Web3Error> {
+        Ok(self.state.bridge.submit_pegin_received(request).await?)
+    }
+
+    pub async fn submit_pegin_committee_presigned_transactions_impl(
+        &self,
+        presigned_txs: PeginCommitteeTaskResponse,
+    ) -> Result<(), Web3Error> {
+        Ok(self
+            .state
+            .bridge
+            .submit_pegin_committee_presigned_transactions(presigned_txs)
+            .await?)
+    }
+
+    pub async fn submit_challenge_committee_presigned_transactions_impl(
+        &self,
+        response: ChallengeCommitteeTaskResponse,
+    ) -> Result<(), Web3Error> {
+        Ok(self
+            .state
+            .bridge
+            .submit_challenge_committee_presigned_transactions(response)
+            .await?)
+    }
 }
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS bridges (
+    id SERIAL PRIMARY KEY,
+    chain_id INTEGER NOT NULL UNIQUE,
+    chain_name TEXT NOT NULL,
+    operator_manager_address TEXT NOT NULL,
+    assertion_taproot_address TEXT NOT NULL,
+    status TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now()
+);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS committees (
+    bridge_id INTEGER NOT NULL REFERENCES bridges (id),
+    public_key TEXT NOT NULL UNIQUE,
+    address TEXT NOT NULL UNIQUE,
+    "index" INTEGER NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (bridge_id, public_key),
+    UNIQUE (bridge_id, "index")
+);
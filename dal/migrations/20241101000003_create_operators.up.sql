@@ -0,0 +1,22 @@
+CREATE TABLE IF NOT EXISTS operators (
+    id SERIAL PRIMARY KEY,
+    address TEXT NOT NULL,
+    chain_id INTEGER NOT NULL REFERENCES bridges (chain_id),
+    public_key TEXT NOT NULL UNIQUE,
+    fee BIGINT NOT NULL,
+    status TEXT NOT NULL,
+    initial_stake_amount BIGINT NOT NULL,
+    remaining_stake_amount BIGINT NOT NULL,
+    max_support_amount BIGINT NOT NULL,
+    min_support_amount BIGINT NOT NULL,
+    max_pegin_cnt INTEGER NOT NULL,
+    max_pegout_cnt INTEGER NOT NULL,
+    pegin_cnt INTEGER NOT NULL DEFAULT 0,
+    pegout_cnt INTEGER NOT NULL DEFAULT 0,
+    slash_cnt INTEGER NOT NULL DEFAULT 0,
+    register_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS operators_chain_id_status_idx ON operators (chain_id, status);
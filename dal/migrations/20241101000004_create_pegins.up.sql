@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS pegins (
+    id SERIAL PRIMARY KEY,
+    target_chain_id INTEGER NOT NULL REFERENCES bridges (chain_id),
+    public_key TEXT NOT NULL,
+    sender_address TEXT NOT NULL,
+    status TEXT NOT NULL,
+    pegin_tx_hash TEXT UNIQUE,
+    receive_address TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    raw_pegin_hex TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS pegins_sender_address_idx ON pegins (sender_address);
+CREATE INDEX IF NOT EXISTS pegins_status_idx ON pegins (status);
+CREATE INDEX IF NOT EXISTS pegins_created_at_idx ON pegins (created_at);
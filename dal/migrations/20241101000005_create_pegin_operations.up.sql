@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS pegin_operations (
+    id SERIAL PRIMARY KEY,
+    pegin_id INTEGER NOT NULL REFERENCES pegins (id),
+    operator_id INTEGER NOT NULL REFERENCES operators (id),
+    raw_take_tx TEXT NOT NULL,
+    status TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now(),
+    UNIQUE (pegin_id, operator_id)
+);
+
+CREATE INDEX IF NOT EXISTS pegin_operations_operator_id_idx ON pegin_operations (operator_id);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS pegouts (
+    id SERIAL PRIMARY KEY,
+    pegin_id INTEGER NOT NULL REFERENCES pegins (id),
+    operator_id INTEGER REFERENCES operators (id),
+    status TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS pegouts_pegin_id_idx ON pegouts (pegin_id);
+CREATE INDEX IF NOT EXISTS pegouts_status_idx ON pegouts (status);
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS presigned_transactions (
+    txid TEXT PRIMARY KEY,
+    tx_type TEXT NOT NULL,
+    pegin_id INTEGER NOT NULL REFERENCES pegins (id),
+    operator_id INTEGER REFERENCES operators (id),
+    status TEXT NOT NULL,
+    raw_hex TEXT NOT NULL,
+    signed_committee_cnt INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS presigned_transactions_pegin_id_idx ON presigned_transactions (pegin_id);
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS events (
+    chain_id INTEGER NOT NULL REFERENCES bridges (chain_id),
+    tx_hash TEXT NOT NULL,
+    event_idx INTEGER NOT NULL,
+    event_type TEXT NOT NULL,
+    data TEXT NOT NULL,
+    status TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (chain_id, tx_hash, event_idx)
+);
+
+CREATE INDEX IF NOT EXISTS events_event_type_status_idx ON events (event_type, status);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS evm_transactions (
+    chain_id INTEGER NOT NULL REFERENCES bridges (chain_id),
+    tx_hash TEXT NOT NULL,
+    tx_type TEXT NOT NULL,
+    status TEXT NOT NULL,
+    data TEXT NOT NULL,
+    extra_data TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (chain_id, tx_hash)
+);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS bitcoin_transactions (
+    tx_hash TEXT PRIMARY KEY,
+    tx_type TEXT NOT NULL,
+    status TEXT NOT NULL,
+    data TEXT NOT NULL,
+    extra_data TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS bitcoin_transactions_tx_type_status_idx ON bitcoin_transactions (tx_type, status);
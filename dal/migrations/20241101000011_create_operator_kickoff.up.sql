@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS operator_kickoff (
+    pre_tx_id TEXT NOT NULL,
+    pre_tx_vout INTEGER NOT NULL,
+    operator_address TEXT NOT NULL,
+    status TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (pre_tx_id, pre_tx_vout)
+);
+
+CREATE INDEX IF NOT EXISTS operator_kickoff_operator_address_idx ON operator_kickoff (operator_address);
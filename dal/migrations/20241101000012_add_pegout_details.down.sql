@@ -0,0 +1,15 @@
+DROP INDEX IF EXISTS pegouts_created_at_idx;
+DROP INDEX IF EXISTS pegouts_sender_address_idx;
+DROP INDEX IF EXISTS pegouts_pegin_id_key;
+DROP INDEX IF EXISTS pegouts_burn_tx_hash_idx;
+
+ALTER TABLE pegouts
+    DROP COLUMN IF EXISTS payout_tx_hash,
+    DROP COLUMN IF EXISTS amount,
+    DROP COLUMN IF EXISTS receive_address,
+    DROP COLUMN IF EXISTS sender_address,
+    DROP COLUMN IF EXISTS event_idx,
+    DROP COLUMN IF EXISTS burn_tx_hash,
+    DROP COLUMN IF EXISTS chain_id;
+
+ALTER TABLE pegouts ALTER COLUMN pegin_id SET NOT NULL;
@@ -0,0 +1,41 @@
+ALTER TABLE pegouts ALTER COLUMN pegin_id DROP NOT NULL;
+
+ALTER TABLE pegouts
+    ADD COLUMN chain_id INTEGER REFERENCES bridges (chain_id),
+    ADD COLUMN burn_tx_hash TEXT,
+    ADD COLUMN event_idx INTEGER,
+    ADD COLUMN sender_address TEXT,
+    ADD COLUMN receive_address TEXT,
+    ADD COLUMN amount BIGINT,
+    ADD COLUMN payout_tx_hash TEXT;
+
+-- Existing peg-outs predate burn tracking, so they take their details from the peg-in they
+-- were created for and pay its depositor back. They have no burn, and the id keeps their burn
+-- keys apart.
+UPDATE pegouts
+SET chain_id = pegins.target_chain_id,
+    burn_tx_hash = '',
+    event_idx = pegouts.id,
+    sender_address = pegins.receive_address,
+    receive_address = pegins.sender_address,
+    amount = pegins.amount
+FROM pegins
+WHERE pegins.id = pegouts.pegin_id;
+
+ALTER TABLE pegouts
+    ALTER COLUMN chain_id SET NOT NULL,
+    ALTER COLUMN burn_tx_hash SET NOT NULL,
+    ALTER COLUMN event_idx SET NOT NULL,
+    ALTER COLUMN sender_address SET NOT NULL,
+    ALTER COLUMN receive_address SET NOT NULL,
+    ALTER COLUMN amount SET NOT NULL;
+
+-- Every burn event starts a peg-out, and a transaction can hold several burns.
+ALTER TABLE pegouts
+    ADD CONSTRAINT pegouts_burn_event_key UNIQUE (chain_id, burn_tx_hash, event_idx);
+CREATE INDEX IF NOT EXISTS pegouts_burn_tx_hash_idx ON pegouts (burn_tx_hash);
+-- A peg-in UTXO reimburses a single peg-out.
+CREATE UNIQUE INDEX IF NOT EXISTS pegouts_pegin_id_key ON pegouts (pegin_id)
+    WHERE pegin_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS pegouts_sender_address_idx ON pegouts (sender_address);
+CREATE INDEX IF NOT EXISTS pegouts_created_at_idx ON pegouts (created_at);
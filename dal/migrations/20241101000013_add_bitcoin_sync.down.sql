@@ -0,0 +1,8 @@
+DROP TABLE IF EXISTS sync_cursors;
+
+DROP INDEX IF EXISTS bitcoin_transactions_block_height_idx;
+
+ALTER TABLE bitcoin_transactions
+    DROP COLUMN IF EXISTS confirmations,
+    DROP COLUMN IF EXISTS block_hash,
+    DROP COLUMN IF EXISTS block_height;
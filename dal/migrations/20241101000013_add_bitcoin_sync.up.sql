@@ -0,0 +1,13 @@
+ALTER TABLE bitcoin_transactions
+    ADD COLUMN block_height BIGINT,
+    ADD COLUMN block_hash TEXT,
+    ADD COLUMN confirmations INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS bitcoin_transactions_block_height_idx ON bitcoin_transactions (block_height);
+
+CREATE TABLE IF NOT EXISTS sync_cursors (
+    cursor_name TEXT PRIMARY KEY,
+    block_number BIGINT NOT NULL,
+    block_hash TEXT NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT now()
+);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS bitcoin_blocks (
+    height BIGINT PRIMARY KEY,
+    block_hash TEXT NOT NULL UNIQUE,
+    parent_hash TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
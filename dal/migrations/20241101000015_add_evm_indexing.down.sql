@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS events_chain_id_block_number_idx;
+
+ALTER TABLE events DROP COLUMN IF EXISTS block_number;
+
+ALTER TABLE bridges DROP COLUMN IF EXISTS bridge_contract_address;
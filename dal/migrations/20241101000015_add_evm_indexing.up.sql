@@ -0,0 +1,5 @@
+ALTER TABLE bridges ADD COLUMN bridge_contract_address TEXT;
+
+ALTER TABLE events ADD COLUMN block_number BIGINT;
+
+CREATE INDEX IF NOT EXISTS events_chain_id_block_number_idx ON events (chain_id, block_number);
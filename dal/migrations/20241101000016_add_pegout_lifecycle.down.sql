@@ -0,0 +1,9 @@
+DROP TABLE IF EXISTS pegout_status_transitions;
+
+DROP INDEX IF EXISTS pegouts_status_deadline_height_idx;
+
+UPDATE pegouts SET status = 'created' WHERE status = 'burn_observed';
+
+ALTER TABLE pegouts
+    DROP COLUMN IF EXISTS deadline_height,
+    DROP COLUMN IF EXISTS kickoff_tx_hash;
@@ -0,0 +1,19 @@
+ALTER TABLE pegouts
+    ADD COLUMN kickoff_tx_hash TEXT,
+    ADD COLUMN deadline_height BIGINT;
+
+UPDATE pegouts SET status = 'burn_observed' WHERE status = 'created';
+
+CREATE INDEX IF NOT EXISTS pegouts_status_deadline_height_idx ON pegouts (status, deadline_height);
+
+CREATE TABLE IF NOT EXISTS pegout_status_transitions (
+    id BIGSERIAL PRIMARY KEY,
+    pegout_id INTEGER NOT NULL REFERENCES pegouts (id),
+    from_status TEXT NOT NULL,
+    to_status TEXT NOT NULL,
+    block_height BIGINT,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS pegout_status_transitions_pegout_id_idx
+    ON pegout_status_transitions (pegout_id);
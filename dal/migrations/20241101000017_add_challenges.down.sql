@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS presigned_transaction_signatures;
+DROP TABLE IF EXISTS challenges;
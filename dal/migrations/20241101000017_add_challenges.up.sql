@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS challenges (
+    id SERIAL PRIMARY KEY,
+    pegout_id INTEGER NOT NULL REFERENCES pegouts (id),
+    challenger_address TEXT NOT NULL,
+    challenge_tx_hash TEXT NOT NULL UNIQUE,
+    status TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS challenges_pegout_id_idx ON challenges (pegout_id);
+
+CREATE TABLE IF NOT EXISTS presigned_transaction_signatures (
+    txid TEXT NOT NULL REFERENCES presigned_transactions (txid),
+    public_key TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (txid, public_key)
+);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS pubsub_tasks (
+    id BIGSERIAL PRIMARY KEY,
+    topic TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS pubsub_tasks_topic_id_idx ON pubsub_tasks (topic, id);
@@ -0,0 +1,4 @@
+DROP TRIGGER IF EXISTS presigned_transactions_notify_changes ON presigned_transactions;
+DROP TRIGGER IF EXISTS pegouts_notify_changes ON pegouts;
+DROP TRIGGER IF EXISTS pegins_notify_changes ON pegins;
+DROP FUNCTION IF EXISTS notify_bridge_changes();
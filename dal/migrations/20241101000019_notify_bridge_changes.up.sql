@@ -0,0 +1,21 @@
+-- Wakes the pubsub notifiers as soon as the tables their tasks derive from change. Statement-level
+-- triggers notify once per statement; Postgres folds identical notifications of a transaction
+-- into one, delivered on commit.
+CREATE OR REPLACE FUNCTION notify_bridge_changes() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('bridge_changes', TG_TABLE_NAME);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER pegins_notify_changes
+    AFTER INSERT OR UPDATE ON pegins
+    FOR EACH STATEMENT EXECUTE FUNCTION notify_bridge_changes();
+
+CREATE TRIGGER pegouts_notify_changes
+    AFTER INSERT OR UPDATE ON pegouts
+    FOR EACH STATEMENT EXECUTE FUNCTION notify_bridge_changes();
+
+CREATE TRIGGER presigned_transactions_notify_changes
+    AFTER INSERT OR UPDATE ON presigned_transactions
+    FOR EACH STATEMENT EXECUTE FUNCTION notify_bridge_changes();
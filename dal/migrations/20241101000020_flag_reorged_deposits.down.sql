@@ -0,0 +1,2 @@
+ALTER TABLE pegins
+    DROP COLUMN IF EXISTS deposit_reorged_at;
@@ -0,0 +1,2 @@
+ALTER TABLE pegins
+    ADD COLUMN deposit_reorged_at TIMESTAMP;
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS pegin_acceptances (
+    pegin_id INTEGER NOT NULL REFERENCES pegins (id),
+    public_key TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (pegin_id, public_key)
+);
@@ -0,0 +1,44 @@
+use crate::{error::DalResult, StorageProcessor};
+
+/// Hashes of the Bitcoin blocks processed by the chain follower, used to detect reorgs.
+#[derive(Debug)]
+pub struct BitcoinBlocksDal<'a, 'c> {
+    pub(crate) storage: &'a mut StorageProcessor<'c>,
+}
+
+impl BitcoinBlocksDal<'_, '_> {
+    pub async fn insert_block(
+        &mut self,
+        height: i64,
+        block_hash: &str,
+        parent_hash: &str,
+    ) -> DalResult<()> {
+        sqlx::query(
+            "INSERT INTO bitcoin_blocks (height, block_hash, parent_hash) VALUES ($1, $2, $3)",
+        )
+        .bind(height)
+        .bind(block_hash)
+        .bind(parent_hash)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_block_hash(&mut self, height: i64) -> DalResult<Option<String>> {
+        Ok(
+            sqlx::query_scalar("SELECT block_hash FROM bitcoin_blocks WHERE height = $1")
+                .bind(height)
+                .fetch_optional(self.storage.conn())
+                .await?,
+        )
+    }
+
+    /// Returns the number of deleted blocks.
+    pub async fn delete_blocks_above(&mut self, height: i64) -> DalResult<u64> {
+        let result = sqlx::query("DELETE FROM bitcoin_blocks WHERE height > $1")
+            .bind(height)
+            .execute(self.storage.conn())
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,201 @@
+use types::bitcoin_tx::{BitcoinTransaction, NewBitcoinTransaction};
+
+use crate::{error::DalResult, StorageProcessor};
+
+#[derive(Debug)]
+pub struct BitcoinTransactionsDal<'a, 'c> {
+    pub(crate) storage: &'a mut StorageProcessor<'c>,
+}
+
+impl BitcoinTransactionsDal<'_, '_> {
+    /// Inserts a transaction, or moves an already known one to the block of `tx`.
+    pub async fn upsert_bitcoin_transaction(
+        &mut self,
+        tx: &NewBitcoinTransaction,
+    ) -> DalResult<()> {
+        sqlx::query(
+            "INSERT INTO bitcoin_transactions \
+             (tx_hash, tx_type, status, data, extra_data, block_height, block_hash, confirmations) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+             ON CONFLICT (tx_hash) DO UPDATE SET \
+             status = EXCLUDED.status, block_height = EXCLUDED.block_height, \
+             block_hash = EXCLUDED.block_hash, confirmations = EXCLUDED.confirmations, \
+             updated_at = now()",
+        )
+        .bind(&tx.tx_hash)
+        .bind(&tx.tx_type)
+        .bind(&tx.status)
+        .bind(&tx.data)
+        .bind(&tx.extra_data)
+        .bind(tx.block_height)
+        .bind(&tx.block_hash)
+        .bind(tx.confirmations)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_bitcoin_transaction(
+        &mut self,
+        tx_hash: &str,
+    ) -> DalResult<Option<BitcoinTransaction>> {
+        Ok(
+            sqlx::query_as("SELECT * FROM bitcoin_transactions WHERE tx_hash = $1")
+                .bind(tx_hash)
+                .fetch_optional(self.storage.conn())
+                .await?,
+        )
+    }
+
+    pub async fn get_bitcoin_transactions_by_status(
+        &mut self,
+        tx_type: &str,
+        status: &str,
+    ) -> DalResult<Vec<BitcoinTransaction>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM bitcoin_transactions \
+             WHERE tx_type = $1 AND status = $2 \
+             ORDER BY block_height, tx_hash",
+        )
+        .bind(tx_type)
+        .bind(status)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    pub async fn update_confirmations(
+        &mut self,
+        tx_hash: &str,
+        confirmations: i32,
+        status: &str,
+    ) -> DalResult<bool> {
+        let result = sqlx::query(
+            "UPDATE bitcoin_transactions \
+             SET confirmations = $2, status = $3, updated_at = now() \
+             WHERE tx_hash = $1",
+        )
+        .bind(tx_hash)
+        .bind(confirmations)
+        .bind(status)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Detaches transactions confirmed above `height` from their blocks after a reorg, and
+    /// returns their hashes. They stay `pending` until they are seen in a block again.
+    pub async fn revert_blocks_above(&mut self, height: i64) -> DalResult<Vec<String>> {
+        Ok(sqlx::query_scalar(
+            "UPDATE bitcoin_transactions \
+             SET status = 'pending', block_height = NULL, block_hash = NULL, confirmations = 0, \
+             updated_at = now() \
+             WHERE block_height > $1 \
+             RETURNING tx_hash",
+        )
+        .bind(height)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use types::bitcoin_tx::{
+        NewBitcoinTransaction, PeginDeposit, BITCOIN_TX_CONFIRMED, BITCOIN_TX_PENDING,
+        PEGIN_DEPOSIT_TX_TYPE,
+    };
+
+    use crate::{
+        connection::ConnectionPool,
+        tests::{insert_bridge, new_pegin, random_hash},
+    };
+
+    fn deposit(
+        chain_id: i32,
+        tx_hash: &str,
+        amount: i64,
+        block_height: i64,
+    ) -> NewBitcoinTransaction {
+        let outputs = [PeginDeposit {
+            chain_id,
+            vout: 0,
+            amount,
+        }];
+        NewBitcoinTransaction {
+            tx_hash: tx_hash.to_string(),
+            tx_type: PEGIN_DEPOSIT_TX_TYPE.to_string(),
+            status: BITCOIN_TX_PENDING.to_string(),
+            data: "0200".to_string(),
+            extra_data: Some(serde_json::to_string(&outputs).unwrap()),
+            block_height: Some(block_height),
+            block_hash: Some(format!("{block_height:064x}")),
+            confirmations: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn confirmed_deposits_mark_pegins_deposited() {
+        let pool = ConnectionPool::test_pool().await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let chain_id = insert_bridge(&mut storage).await;
+
+        let (paid, underpaid) = (random_hash(), random_hash());
+        let mut pegin_ids = vec![];
+        for tx_hash in [&paid, &underpaid] {
+            let id = storage
+                .pegins_dal()
+                .insert_pegin(&new_pegin(chain_id, "bcrt1qsender", 100_000))
+                .await
+                .unwrap();
+            storage
+                .pegins_dal()
+                .set_pegin_tx(id, tx_hash, "0200")
+                .await
+                .unwrap();
+            pegin_ids.push(id);
+        }
+
+        let dal = &mut storage.bitcoin_transactions_dal();
+        dal.upsert_bitcoin_transaction(&deposit(chain_id, &paid, 100_000, 10))
+            .await
+            .unwrap();
+        dal.upsert_bitcoin_transaction(&deposit(chain_id, &underpaid, 99_999, 10))
+            .await
+            .unwrap();
+        // Pending deposits don't count yet.
+        assert!(storage
+            .pegins_dal()
+            .mark_confirmed_deposits()
+            .await
+            .unwrap()
+            .is_empty());
+
+        for tx_hash in [&paid, &underpaid] {
+            assert!(storage
+                .bitcoin_transactions_dal()
+                .update_confirmations(tx_hash, 6, BITCOIN_TX_CONFIRMED)
+                .await
+                .unwrap());
+        }
+        let deposited = storage
+            .pegins_dal()
+            .mark_confirmed_deposits()
+            .await
+            .unwrap();
+        assert_eq!(deposited, [pegin_ids[0]]);
+
+        // Re-indexing the transaction in a later block moves it there.
+        storage
+            .bitcoin_transactions_dal()
+            .upsert_bitcoin_transaction(&deposit(chain_id, &paid, 100_000, 11))
+            .await
+            .unwrap();
+        let tx = storage
+            .bitcoin_transactions_dal()
+            .get_bitcoin_transaction(&paid)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((tx.block_height, tx.confirmations), (Some(11), 1));
+    }
+}
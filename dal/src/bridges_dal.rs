@@ -0,0 +1,92 @@
+use types::bridge::{Bridge, CommitteeMember, NewBridge};
+
+use crate::{error::DalResult, StorageProcessor};
+
+#[derive(Debug)]
+pub struct BridgesDal<'a, 'c> {
+    pub(crate) storage: &'a mut StorageProcessor<'c>,
+}
+
+impl BridgesDal<'_, '_> {
+    pub async fn insert_bridge(&mut self, bridge: &NewBridge) -> DalResult<i32> {
+        Ok(sqlx::query_scalar(
+            "INSERT INTO bridges \
+             (chain_id, chain_name, operator_manager_address, assertion_taproot_address, status, \
+             bridge_contract_address) \
+             VALUES ($1, $2, $3, $4, $5, $6) \
+             RETURNING id",
+        )
+        .bind(bridge.chain_id)
+        .bind(&bridge.chain_name)
+        .bind(&bridge.operator_manager_address)
+        .bind(&bridge.assertion_taproot_address)
+        .bind(&bridge.status)
+        .bind(&bridge.bridge_contract_address)
+        .fetch_one(self.storage.conn())
+        .await?)
+    }
+
+    pub async fn get_bridge_by_id(&mut self, id: i32) -> DalResult<Option<Bridge>> {
+        Ok(sqlx::query_as("SELECT * FROM bridges WHERE id = $1")
+            .bind(id)
+            .fetch_optional(self.storage.conn())
+            .await?)
+    }
+
+    pub async fn get_bridge_by_chain_id(&mut self, chain_id: i32) -> DalResult<Option<Bridge>> {
+        Ok(sqlx::query_as("SELECT * FROM bridges WHERE chain_id = $1")
+            .bind(chain_id)
+            .fetch_optional(self.storage.conn())
+            .await?)
+    }
+
+    pub async fn get_active_bridges(&mut self) -> DalResult<Vec<Bridge>> {
+        Ok(
+            sqlx::query_as("SELECT * FROM bridges WHERE status = 'active' ORDER BY chain_id")
+                .fetch_all(self.storage.conn())
+                .await?,
+        )
+    }
+
+    pub async fn insert_committee_member(
+        &mut self,
+        bridge_id: i32,
+        public_key: &str,
+        address: &str,
+        index: i32,
+    ) -> DalResult<()> {
+        sqlx::query(
+            "INSERT INTO committees (bridge_id, public_key, address, \"index\") \
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(bridge_id)
+        .bind(public_key)
+        .bind(address)
+        .bind(index)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(())
+    }
+
+    /// Returns the committee of a bridge in script order.
+    pub async fn get_committee(&mut self, bridge_id: i32) -> DalResult<Vec<CommitteeMember>> {
+        Ok(
+            sqlx::query_as("SELECT * FROM committees WHERE bridge_id = $1 ORDER BY \"index\"")
+                .bind(bridge_id)
+                .fetch_all(self.storage.conn())
+                .await?,
+        )
+    }
+
+    pub async fn get_committee_member(
+        &mut self,
+        public_key: &str,
+    ) -> DalResult<Option<CommitteeMember>> {
+        Ok(
+            sqlx::query_as("SELECT * FROM committees WHERE public_key = $1")
+                .bind(public_key)
+                .fetch_optional(self.storage.conn())
+                .await?,
+        )
+    }
+}
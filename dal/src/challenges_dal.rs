@@ -0,0 +1,61 @@
+use types::challenger::{Challenge, ChallengeStatus};
+
+use crate::{error::DalResult, StorageProcessor};
+
+#[derive(Debug)]
+pub struct ChallengesDal<'a, 'c> {
+    pub(crate) storage: &'a mut StorageProcessor<'c>,
+}
+
+impl ChallengesDal<'_, '_> {
+    /// Records a `submitted` challenge. Returns `None` if its transaction is already recorded.
+    pub async fn insert_challenge(
+        &mut self,
+        pegout_id: i32,
+        challenger_address: &str,
+        challenge_tx_hash: &str,
+    ) -> DalResult<Option<i32>> {
+        Ok(sqlx::query_scalar(
+            "INSERT INTO challenges (pegout_id, challenger_address, challenge_tx_hash, status) \
+             VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (challenge_tx_hash) DO NOTHING \
+             RETURNING id",
+        )
+        .bind(pegout_id)
+        .bind(challenger_address)
+        .bind(challenge_tx_hash)
+        .bind(ChallengeStatus::Submitted.as_ref())
+        .fetch_optional(self.storage.conn())
+        .await?)
+    }
+
+    pub async fn get_challenge(&mut self, id: i32) -> DalResult<Option<Challenge>> {
+        Ok(sqlx::query_as("SELECT * FROM challenges WHERE id = $1")
+            .bind(id)
+            .fetch_optional(self.storage.conn())
+            .await?)
+    }
+
+    pub async fn get_challenges_by_pegout(&mut self, pegout_id: i32) -> DalResult<Vec<Challenge>> {
+        Ok(
+            sqlx::query_as("SELECT * FROM challenges WHERE pegout_id = $1 ORDER BY id")
+                .bind(pegout_id)
+                .fetch_all(self.storage.conn())
+                .await?,
+        )
+    }
+
+    pub async fn update_challenge_status(
+        &mut self,
+        id: i32,
+        status: ChallengeStatus,
+    ) -> DalResult<bool> {
+        let result =
+            sqlx::query("UPDATE challenges SET status = $2, updated_at = now() WHERE id = $1")
+                .bind(id)
+                .bind(status.as_ref())
+                .execute(self.storage.conn())
+                .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
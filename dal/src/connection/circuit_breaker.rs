@@ -0,0 +1,181 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use health_check::{async_trait, CheckHealth, Health, HealthStatus};
+use serde_json::json;
+
+use crate::error::{DalError, DalResult};
+
+/// Consecutive failed acquisitions after which the breaker opens.
+const FAILURE_THRESHOLD: u32 = 3;
+/// How long an open breaker rejects requests before letting a probe through.
+const OPEN_DURATION: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+enum State {
+    Closed {
+        consecutive_failures: u32,
+    },
+    Open {
+        until: Instant,
+    },
+    /// The open period has elapsed; the next acquisition decides whether the breaker closes.
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: State,
+    last_error: Option<String>,
+}
+
+/// Pool-level circuit breaker.
+///
+/// Once acquiring a connection fails [`FAILURE_THRESHOLD`] times in a row, callers get
+/// [`DalError::CircuitOpen`] immediately instead of piling up on a database that is down.
+/// The breaker doubles as a health check, so an outage shows up on `/health`.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    inner: Arc<Mutex<Inner>>,
+    failure_threshold: u32,
+    open_duration: Duration,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(FAILURE_THRESHOLD, OPEN_DURATION)
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, open_duration: Duration) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                state: State::Closed {
+                    consecutive_failures: 0,
+                },
+                last_error: None,
+            })),
+            failure_threshold,
+            open_duration,
+        }
+    }
+
+    /// Returns an error if requests should not reach the database right now.
+    pub fn check(&self) -> DalResult<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if let State::Open { until } = inner.state {
+            let now = Instant::now();
+            if now < until {
+                return Err(DalError::CircuitOpen {
+                    retry_in: until - now,
+                });
+            }
+            inner.state = State::HalfOpen;
+        }
+        Ok(())
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if !matches!(inner.state, State::Closed { .. }) {
+            logs::info!("database is reachable again, closing circuit breaker");
+        }
+        inner.state = State::Closed {
+            consecutive_failures: 0,
+        };
+    }
+
+    pub fn record_failure(&self, err: &DalError) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.last_error = Some(err.to_string());
+        let should_open = match &mut inner.state {
+            State::Closed {
+                consecutive_failures,
+            } => {
+                *consecutive_failures += 1;
+                *consecutive_failures >= self.failure_threshold
+            }
+            State::HalfOpen => true,
+            State::Open { .. } => false,
+        };
+        if should_open {
+            logs::warn!(
+                "opening database circuit breaker for {:?}: {err}",
+                self.open_duration
+            );
+            inner.state = State::Open {
+                until: Instant::now() + self.open_duration,
+            };
+        }
+    }
+}
+
+#[async_trait]
+impl CheckHealth for CircuitBreaker {
+    fn name(&self) -> &'static str {
+        "database_pool"
+    }
+
+    async fn check_health(&self) -> Health {
+        let inner = self.inner.lock().unwrap();
+        let (status, state) = match inner.state {
+            State::Closed { .. } => (HealthStatus::Ready, "closed"),
+            State::Open { .. } => (HealthStatus::NotReady, "open"),
+            State::HalfOpen => (HealthStatus::NotReady, "half_open"),
+        };
+        Health::from(status).with_details(json!({
+            "circuit_breaker": state,
+            "last_error": inner.last_error,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    fn failure() -> DalError {
+        DalError::Query(sqlx::Error::PoolTimedOut)
+    }
+
+    #[tokio::test]
+    async fn breaker_opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(50));
+        breaker.record_failure(&failure());
+        breaker.record_success();
+        breaker.record_failure(&failure());
+        assert!(breaker.check().is_ok());
+        assert_matches!(breaker.check_health().await.status(), HealthStatus::Ready);
+
+        breaker.record_failure(&failure());
+        assert_matches!(breaker.check(), Err(DalError::CircuitOpen { .. }));
+        assert_matches!(
+            breaker.check_health().await.status(),
+            HealthStatus::NotReady
+        );
+    }
+
+    #[tokio::test]
+    async fn half_open_breaker_is_decided_by_next_attempt() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(20));
+        breaker.record_failure(&failure());
+        assert!(breaker.check().is_err());
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(breaker.check().is_ok());
+        // A failed probe re-opens the breaker right away.
+        breaker.record_failure(&failure());
+        assert!(breaker.check().is_err());
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(breaker.check().is_ok());
+        breaker.record_success();
+        assert!(breaker.check().is_ok());
+        assert_matches!(breaker.check_health().await.status(), HealthStatus::Ready);
+    }
+}
@@ -1,11 +1,13 @@
 use std::fmt;
 
 use sqlx::{pool::PoolConnection, PgConnection, Postgres, Transaction};
+use tokio::sync::OwnedMutexGuard;
 
 pub enum ConnectionHolder<'a> {
     Pooled(PoolConnection<Postgres>),
     Direct(PgConnection),
     Transaction(Transaction<'a, Postgres>),
+    TestTransaction(OwnedMutexGuard<Transaction<'static, Postgres>>),
 }
 
 impl<'a> fmt::Debug for ConnectionHolder<'a> {
@@ -14,6 +16,7 @@ impl<'a> fmt::Debug for ConnectionHolder<'a> {
             Self::Pooled(_) => write!(f, "Pooled connection"),
             Self::Direct(_) => write!(f, "Direct connection"),
             Self::Transaction(_) => write!(f, "Database Transaction"),
+            Self::TestTransaction(_) => write!(f, "Test Database Transaction"),
         }
     }
 }
@@ -1,27 +1,49 @@
 use std::time::Duration;
 
+use anyhow::Context;
+use config::database::DatabaseConfig;
+use rand::Rng;
 use sqlx::{
     pool::PoolConnection,
-    postgres::{PgConnectOptions, PgPoolOptions},
+    postgres::{PgConnectOptions, PgListener, PgPoolOptions},
     PgPool, Postgres,
 };
 
-use crate::{get_master_database_url, StorageProcessor};
+use crate::{
+    error::{DalError, DalResult},
+    migrations::MIGRATOR,
+    StorageProcessor,
+};
+
+pub use self::{
+    circuit_breaker::CircuitBreaker,
+    replica::{ReplicaPool, ReplicaRouting},
+    test_pool::TestPool,
+};
 
+mod circuit_breaker;
 pub mod holder;
+mod replica;
+mod test_pool;
 
-const DATABASE_POOL_SIZE: u32 = 50;
+const DB_CONNECTION_RETRIES: u32 = 4;
+const BACKOFF_BASE: Duration = Duration::from_millis(100);
+const BACKOFF_MAX: Duration = Duration::from_secs(5);
 
 #[derive(Debug, Clone, Copy)]
 pub enum DbVariant {
     Master,
+    /// Streaming replica of the master, as set up by `scripts/init_main_db.sh`. Read-only.
+    Replica,
 }
 
 #[derive(Debug)]
 pub struct ConnectionPoolBuilder {
+    config: DatabaseConfig,
     db: DbVariant,
     max_size: Option<u32>,
     statement_timeout: Option<Duration>,
+    replica_routing: Option<ReplicaRouting>,
 }
 
 impl ConnectionPoolBuilder {
@@ -35,102 +57,274 @@ impl ConnectionPoolBuilder {
         self
     }
 
-    pub async fn build(&self) -> ConnectionPool {
+    /// Routes read-only requesters to the [`DbVariant::Replica`] database while it keeps up with
+    /// the master. Only applies to [`DbVariant::Master`] pools.
+    pub fn set_replica_routing(&mut self, routing: Option<ReplicaRouting>) -> &mut Self {
+        self.replica_routing = routing;
+        self
+    }
+
+    pub async fn build(&self) -> DalResult<ConnectionPool> {
         let db_url = match self.db {
-            DbVariant::Master => get_master_database_url(),
+            DbVariant::Master => self.config.master_url.as_str(),
+            DbVariant::Replica => self.replica_url()?,
         };
-        self.build_inner(&db_url).await
+        self.build_inner(db_url).await
+    }
+
+    pub async fn build_inner(&self, db_url: &str) -> DalResult<ConnectionPool> {
+        let pool = self.connect(self.db, db_url).await?;
+        let replica = match (&self.replica_routing, self.db) {
+            (Some(routing), DbVariant::Master) => {
+                let replica_pool = self
+                    .connect(DbVariant::Replica, self.replica_url()?)
+                    .await?;
+                Some(ReplicaPool::new(replica_pool, routing.clone()))
+            }
+            (Some(_), DbVariant::Replica) => {
+                logs::warn!("replica routing is ignored for a pool connected to the replica");
+                None
+            }
+            (None, _) => None,
+        };
+        Ok(ConnectionPool::Real {
+            pool,
+            replica,
+            circuit_breaker: CircuitBreaker::default(),
+        })
+    }
+
+    fn replica_url(&self) -> DalResult<&str> {
+        self.config
+            .replica_url
+            .as_deref()
+            .ok_or(DalError::MissingUrl("replica_url"))
     }
 
-    pub async fn build_inner(&self, db_url: &str) -> ConnectionPool {
-        let max_connections = self.max_size.unwrap_or_else(|| DATABASE_POOL_SIZE);
-        let options = PgPoolOptions::new().max_connections(max_connections);
-        let mut connect_options: PgConnectOptions = db_url.parse().unwrap_or_else(|e| {
-            panic!("Failed parsing {:?} database URL: {}", self.db, e);
-        });
+    async fn connect(&self, db: DbVariant, db_url: &str) -> DalResult<PgPool> {
+        let max_connections = self.max_size.unwrap_or_else(|| self.config.pool_size());
+        let options = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .acquire_timeout(self.config.acquire_timeout());
+        let mut connect_options: PgConnectOptions = db_url
+            .parse()
+            .map_err(|source| DalError::InvalidUrl { db, source })?;
         if let Some(timeout) = self.statement_timeout {
             let timeout_string = format!("{}s", timeout.as_secs());
             connect_options = connect_options.options([("statement_timeout", timeout_string)]);
         }
         connect_options = connect_options.statement_cache_capacity(0);
-        let pool = options
+        options
             .connect_with(connect_options)
             .await
-            .unwrap_or_else(|err| {
-                panic!("Failed connecting to {:?}, error: {}", self.db, err);
-            });
-        ConnectionPool::Real(pool)
+            .map_err(|source| DalError::Connect { db, source })
     }
 }
 
 #[derive(Debug, Clone)]
 pub enum ConnectionPool {
-    Real(PgPool),
-    Test(PgPool),
+    Real {
+        pool: PgPool,
+        replica: Option<ReplicaPool>,
+        circuit_breaker: CircuitBreaker,
+    },
+    Test(TestPool),
 }
 
 impl ConnectionPool {
-    pub fn builder(db: DbVariant) -> ConnectionPoolBuilder {
+    pub fn builder(config: &DatabaseConfig, db: DbVariant) -> ConnectionPoolBuilder {
         ConnectionPoolBuilder {
+            config: config.clone(),
             db,
             max_size: None,
-            statement_timeout: None,
+            statement_timeout: config.statement_timeout(),
+            replica_routing: None,
         }
     }
 
-    pub fn singleton(db: DbVariant) -> ConnectionPoolBuilder {
+    pub fn singleton(config: &DatabaseConfig, db: DbVariant) -> ConnectionPoolBuilder {
         ConnectionPoolBuilder {
+            config: config.clone(),
             db,
             max_size: Some(1),
-            statement_timeout: None,
+            statement_timeout: config.statement_timeout(),
+            replica_routing: None,
         }
     }
 
-    pub async fn access_storage(&self) -> StorageProcessor {
+    /// Creates a pool for integration tests against the configured `test_url`,
+    /// applying migrations first. See [`TestPool`] for the isolation guarantees.
+    pub async fn test_pool() -> ConnectionPool {
+        let pool = TestPool::new()
+            .await
+            .unwrap_or_else(|err| panic!("Failed creating test connection pool: {err:#}"));
+        ConnectionPool::Test(pool)
+    }
+
+    pub async fn access_storage(&self) -> DalResult<StorageProcessor<'_>> {
         self.access_storage_inner(None).await
     }
 
-    pub async fn access_storage_tagged(&self, requester: &'static str) -> StorageProcessor<'_> {
+    pub async fn access_storage_tagged(
+        &self,
+        requester: &'static str,
+    ) -> DalResult<StorageProcessor<'_>> {
         self.access_storage_inner(Some(requester)).await
     }
 
-    async fn access_storage_inner(&self, _requester: Option<&'static str>) -> StorageProcessor<'_> {
+    async fn access_storage_inner(
+        &self,
+        requester: Option<&'static str>,
+    ) -> DalResult<StorageProcessor<'_>> {
         match self {
-            ConnectionPool::Real(real_pool) => {
-                let conn = Self::acquire_connection_retried(real_pool).await;
-                StorageProcessor::from_pool(conn)
+            ConnectionPool::Real {
+                pool,
+                replica,
+                circuit_breaker,
+            } => {
+                circuit_breaker.check()?;
+                let pool = match replica {
+                    Some(replica) => replica.route(requester).await.unwrap_or(pool),
+                    None => pool,
+                };
+                match Self::acquire_connection_retried(pool).await {
+                    Ok(conn) => {
+                        circuit_breaker.record_success();
+                        Ok(StorageProcessor::from_pool(conn))
+                    }
+                    Err(err) => {
+                        circuit_breaker.record_failure(&err);
+                        Err(err)
+                    }
+                }
             }
-            ConnectionPool::Test(_test_pool) => {
-                panic!("test pool not supported!")
+            ConnectionPool::Test(test_pool) => Ok(StorageProcessor::from_test_transaction(
+                test_pool.acquire().await,
+            )),
+        }
+    }
+
+    async fn acquire_connection_retried(pool: &PgPool) -> DalResult<PoolConnection<Postgres>> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match pool.acquire().await {
+                Ok(connection) => return Ok(connection),
+                Err(source) if attempt > DB_CONNECTION_RETRIES => {
+                    return Err(DalError::Acquire {
+                        attempts: attempt,
+                        source,
+                    });
+                }
+                Err(err) => {
+                    let delay = backoff_delay(attempt);
+                    logs::warn!(
+                        "failed getting a DB connection (attempt {attempt}), retrying in {delay:?}: {err}"
+                    );
+                    tokio::time::sleep(delay).await;
+                }
             }
         }
     }
 
-    async fn acquire_connection_retried(pool: &PgPool) -> PoolConnection<Postgres> {
-        const DB_CONNECTION_RETRIES: u32 = 3;
-        const BACKOFF_INTERVAL: Duration = Duration::from_secs(1);
+    /// Listens to `channels` on a dedicated connection, which reconnects on its own when lost.
+    /// Notifications are only sent by the master database, so the pool must be connected to it.
+    /// Returns `None` for test pools, whose writes are never committed and thus never notify.
+    pub async fn listener(&self, channels: &[&str]) -> DalResult<Option<PgListener>> {
+        match self {
+            ConnectionPool::Real { pool, .. } => {
+                let mut listener = PgListener::connect_with(pool).await?;
+                listener.listen_all(channels.iter().copied()).await?;
+                Ok(Some(listener))
+            }
+            ConnectionPool::Test(_) => Ok(None),
+        }
+    }
 
-        let mut retry_count = 0;
-        while retry_count < DB_CONNECTION_RETRIES {
-            let connection = pool.acquire().await;
-            match connection {
-                Ok(connection) => return connection,
-                Err(_) => {
-                    retry_count += 1;
-                }
-            };
+    /// Circuit breaker guarding the pool; doubles as the pool's health check.
+    /// Test pools don't have one.
+    pub fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        match self {
+            ConnectionPool::Real {
+                circuit_breaker, ..
+            } => Some(circuit_breaker),
+            ConnectionPool::Test(_) => None,
+        }
+    }
+
+    /// Applies all pending migrations from `dal/migrations`.
+    pub async fn run_migrations(&self) -> anyhow::Result<()> {
+        match self {
+            ConnectionPool::Real { pool, .. } => MIGRATOR
+                .run(pool)
+                .await
+                .context("failed to run database migrations"),
+            // Test databases are migrated when the pool is created.
+            ConnectionPool::Test(_) => Ok(()),
+        }
+    }
 
-            tokio::time::sleep(BACKOFF_INTERVAL).await;
+    /// Returns the number of open connections and how many of them are idle.
+    pub fn stats(&self) -> (u32, usize) {
+        match self {
+            ConnectionPool::Real { pool, .. } => (pool.size(), pool.num_idle()),
+            // The test connection is held open for the lifetime of the pool.
+            ConnectionPool::Test(_) => (1, 0),
         }
-        pool.acquire()
-            .await
-            .unwrap_or_else(|err| panic!("Failed getting a DB connection: {}", err))
     }
 
     pub fn max_size(&self) -> u32 {
         match self {
-            ConnectionPool::Real(pool) => pool.options().get_max_connections(),
-            ConnectionPool::Test(pool) => pool.options().get_max_connections(),
+            ConnectionPool::Real { pool, .. } => pool.options().get_max_connections(),
+            ConnectionPool::Test(_) => 1,
+        }
+    }
+}
+
+/// Exponential backoff with full jitter: a random delay up to `BACKOFF_BASE * 2^(attempt - 1)`,
+/// capped at `BACKOFF_MAX`, so that clients recovering from the same outage don't retry in lockstep.
+fn backoff_delay(attempt: u32) -> Duration {
+    let exp_backoff = BACKOFF_BASE
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(BACKOFF_MAX);
+    exp_backoff.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CHANGES_CHANNEL;
+
+    #[test]
+    fn backoff_delay_grows_up_to_cap() {
+        for attempt in 1..=32 {
+            let cap = (BACKOFF_BASE * 2u32.pow(attempt.min(16) - 1)).min(BACKOFF_MAX);
+            assert!(backoff_delay(attempt) <= cap);
         }
     }
+
+    #[tokio::test]
+    async fn writes_notify_listeners() {
+        // Migrates the test database.
+        ConnectionPool::test_pool().await;
+        let config = DatabaseConfig::load_config().unwrap();
+        let test_url = config.test_url.clone().unwrap();
+        let pool = ConnectionPool::builder(&config, DbVariant::Master)
+            .build_inner(&test_url)
+            .await
+            .unwrap();
+        let mut listener = pool.listener(&[CHANGES_CHANNEL]).await.unwrap().unwrap();
+
+        // Statement-level triggers fire even if no row changes, so this commits nothing.
+        let mut storage = pool.access_storage().await.unwrap();
+        sqlx::query("UPDATE pegouts SET updated_at = updated_at WHERE false")
+            .execute(storage.conn())
+            .await
+            .unwrap();
+        let notification = tokio::time::timeout(Duration::from_secs(5), listener.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(notification.payload(), "pegouts");
+    }
 }
@@ -0,0 +1,166 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use sqlx::PgPool;
+
+/// Replication lag of the replica in seconds. Zero if the replica has replayed everything it has
+/// received (an idle primary doesn't produce new transactions to replay), or if it isn't in
+/// recovery at all; `NULL` if it has never replayed a transaction.
+const REPLICATION_LAG_QUERY: &str = "SELECT CASE \
+     WHEN NOT pg_is_in_recovery() THEN 0 \
+     WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0 \
+     ELSE EXTRACT(EPOCH FROM now() - pg_last_xact_replay_timestamp()) \
+     END::FLOAT8";
+
+/// How long a measured replication lag is trusted before it is queried again.
+const LAG_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Routing of read queries to a read replica.
+#[derive(Debug, Clone)]
+pub struct ReplicaRouting {
+    /// Requesters (as passed to `access_storage_tagged`) that only read and tolerate stale data.
+    pub read_only_requesters: HashSet<&'static str>,
+    /// Read-only requesters fall back to the master pool while the replica lags more than this.
+    pub max_replication_lag: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LagCheck {
+    checked_at: Instant,
+    is_fresh: bool,
+}
+
+/// Read replica pool together with its routing rules.
+#[derive(Debug, Clone)]
+pub struct ReplicaPool {
+    pool: PgPool,
+    routing: Arc<ReplicaRouting>,
+    last_lag_check: Arc<Mutex<Option<LagCheck>>>,
+}
+
+impl ReplicaPool {
+    pub(super) fn new(pool: PgPool, routing: ReplicaRouting) -> Self {
+        Self {
+            pool,
+            routing: Arc::new(routing),
+            last_lag_check: Arc::default(),
+        }
+    }
+
+    /// Returns the replica pool if `requester` may be served by it right now.
+    pub(super) async fn route(&self, requester: Option<&'static str>) -> Option<&PgPool> {
+        let requester = requester?;
+        if !self.routing.read_only_requesters.contains(requester) {
+            return None;
+        }
+        self.is_fresh().await.then_some(&self.pool)
+    }
+
+    async fn is_fresh(&self) -> bool {
+        let cached = *self.last_lag_check.lock().unwrap();
+        if let Some(check) = cached {
+            if check.checked_at.elapsed() < LAG_CHECK_INTERVAL {
+                return check.is_fresh;
+            }
+        }
+
+        let is_fresh = self.is_acceptable_lag(self.replication_lag().await);
+        *self.last_lag_check.lock().unwrap() = Some(LagCheck {
+            checked_at: Instant::now(),
+            is_fresh,
+        });
+        is_fresh
+    }
+
+    fn is_acceptable_lag(&self, lag: sqlx::Result<Option<Duration>>) -> bool {
+        match lag {
+            Ok(Some(lag)) if lag > self.routing.max_replication_lag => {
+                logs::warn!("replica lags {lag:?} behind, reading from master");
+                false
+            }
+            Ok(Some(_)) => true,
+            Ok(None) => {
+                logs::warn!("replica has not replayed any transactions yet, reading from master");
+                false
+            }
+            Err(err) => {
+                logs::warn!("failed to measure replication lag, reading from master: {err}");
+                false
+            }
+        }
+    }
+
+    async fn replication_lag(&self) -> sqlx::Result<Option<Duration>> {
+        let lag: Option<f64> = sqlx::query_scalar(REPLICATION_LAG_QUERY)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(lag.map(|secs| Duration::from_secs_f64(secs.max(0.0))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use config::database::DatabaseConfig;
+    use sqlx::postgres::PgPoolOptions;
+
+    use super::*;
+    use crate::connection::ConnectionPool;
+
+    const READER: &str = "reader";
+
+    fn routing() -> ReplicaRouting {
+        ReplicaRouting {
+            read_only_requesters: HashSet::from([READER]),
+            max_replication_lag: Duration::from_secs(5),
+        }
+    }
+
+    /// The test database isn't in recovery, so it's a replica that never lags.
+    async fn fresh_replica() -> ReplicaPool {
+        // Migrates the test database.
+        ConnectionPool::test_pool().await;
+        let test_url = DatabaseConfig::load_config().unwrap().test_url.unwrap();
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&test_url)
+            .await
+            .unwrap();
+        ReplicaPool::new(pool, routing())
+    }
+
+    #[tokio::test]
+    async fn routing_read_only_requesters_to_a_fresh_replica() {
+        let replica = fresh_replica().await;
+        assert!(replica.route(Some(READER)).await.is_some());
+        assert!(replica.route(Some("writer")).await.is_none());
+        assert!(replica.route(None).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn stale_replicas_fall_back_to_master() {
+        let replica = fresh_replica().await;
+        assert!(!replica.is_acceptable_lag(Ok(Some(Duration::from_secs(6)))));
+        assert!(!replica.is_acceptable_lag(Ok(None)));
+        assert!(replica.is_acceptable_lag(Ok(Some(Duration::from_secs(5)))));
+
+        // A stale measurement is trusted until it's checked again.
+        *replica.last_lag_check.lock().unwrap() = Some(LagCheck {
+            checked_at: Instant::now(),
+            is_fresh: false,
+        });
+        assert!(replica.route(Some(READER)).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn unreachable_replicas_fall_back_to_master() {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(500))
+            .connect_lazy("postgres://bridge@127.0.0.1:1/bitvm-bridge")
+            .unwrap();
+        let replica = ReplicaPool::new(pool, routing());
+        assert!(replica.route(Some(READER)).await.is_none());
+    }
+}
@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use config::database::DatabaseConfig;
+use sqlx::{migrate::MigrateDatabase, postgres::PgPoolOptions, Postgres, Transaction};
+use tokio::sync::{Mutex, OnceCell, OwnedMutexGuard};
+
+use crate::migrations::MIGRATOR;
+
+/// Test databases are migrated at most once per process; the migrator takes an advisory lock,
+/// so concurrently running test binaries are fine as well.
+static TEST_DATABASE_MIGRATED: OnceCell<()> = OnceCell::const_new();
+
+/// Connection "pool" for integration tests.
+///
+/// All storage processors handed out by a `TestPool` share a single connection wrapped into a
+/// transaction that is never committed. Every test therefore sees only its own writes, and
+/// nothing has to be cleaned up afterwards.
+///
+/// Tests run concurrently, so their fixtures must not share unique values such as chain ids or
+/// transaction hashes: an uncommitted row still blocks inserting the same unique value from
+/// another transaction until it's rolled back.
+///
+/// Since the connection is shared, a test must not hold two storage processors from the same
+/// pool at once; the second `access_storage()` call would wait forever.
+#[derive(Debug, Clone)]
+pub struct TestPool {
+    connection: Arc<Mutex<Transaction<'static, Postgres>>>,
+}
+
+impl TestPool {
+    pub async fn new() -> anyhow::Result<Self> {
+        let db_url = DatabaseConfig::load_config()
+            .context("failed loading database config")?
+            .test_url
+            .context("test_url is not configured")?;
+        TEST_DATABASE_MIGRATED
+            .get_or_try_init(|| Self::prepare_database(&db_url))
+            .await?;
+
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&db_url)
+            .await
+            .context("failed connecting to test database")?;
+        // The transaction owns its pool connection, so it outlives `pool`.
+        let transaction = pool
+            .begin()
+            .await
+            .context("failed starting test transaction")?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(transaction)),
+        })
+    }
+
+    async fn prepare_database(db_url: &str) -> anyhow::Result<()> {
+        if !Postgres::database_exists(db_url).await? {
+            Postgres::create_database(db_url)
+                .await
+                .context("failed creating test database")?;
+        }
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(db_url)
+            .await
+            .context("failed connecting to test database")?;
+        MIGRATOR
+            .run(&pool)
+            .await
+            .context("failed migrating test database")?;
+        pool.close().await;
+        Ok(())
+    }
+
+    pub(crate) async fn acquire(&self) -> OwnedMutexGuard<Transaction<'static, Postgres>> {
+        self.connection.clone().lock_owned().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        connection::ConnectionPool,
+        tests::{insert_bridge, new_pegin},
+    };
+
+    #[tokio::test]
+    async fn test_pools_are_isolated() {
+        let first_pool = ConnectionPool::test_pool().await;
+        let second_pool = ConnectionPool::test_pool().await;
+
+        let mut storage = first_pool.access_storage().await.unwrap();
+        let chain_id = insert_bridge(&mut storage).await;
+        let id = storage
+            .pegins_dal()
+            .insert_pegin(&new_pegin(chain_id, "bcrt1qsender", 1))
+            .await
+            .unwrap();
+        drop(storage);
+
+        // Writes survive across `access_storage()` calls on the same pool...
+        let mut storage = first_pool.access_storage().await.unwrap();
+        assert!(storage
+            .pegins_dal()
+            .get_pegin_by_id(id)
+            .await
+            .unwrap()
+            .is_some());
+        // ...but are never visible to other pools.
+        let mut other_storage = second_pool.access_storage().await.unwrap();
+        assert!(other_storage
+            .pegins_dal()
+            .get_pegin_by_id(id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
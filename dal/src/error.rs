@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use types::error::InvalidTransition;
+
+use crate::connection::DbVariant;
+
+pub type DalResult<T> = Result<T, DalError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DalError {
+    #[error("{0} is not configured")]
+    MissingUrl(&'static str),
+    #[error("invalid {db:?} database URL: {source}")]
+    InvalidUrl {
+        db: DbVariant,
+        #[source]
+        source: sqlx::Error,
+    },
+    #[error("failed connecting to {db:?} database: {source}")]
+    Connect {
+        db: DbVariant,
+        #[source]
+        source: sqlx::Error,
+    },
+    #[error("failed getting a DB connection after {attempts} attempts: {source}")]
+    Acquire {
+        attempts: u32,
+        #[source]
+        source: sqlx::Error,
+    },
+    #[error("database is unavailable, next attempt in {retry_in:?}")]
+    CircuitOpen { retry_in: Duration },
+    #[error("StorageProcessor::{0} can only be invoked after calling StorageProcessor::start_transaction")]
+    NotInTransaction(&'static str),
+    #[error(transparent)]
+    InvalidTransition(#[from] InvalidTransition),
+    #[error(transparent)]
+    Query(#[from] sqlx::Error),
+}
+
+impl DalError {
+    /// Whether a query failed because it would have duplicated a unique value.
+    pub fn is_unique_violation(&self) -> bool {
+        matches!(self, Self::Query(sqlx::Error::Database(err)) if err.is_unique_violation())
+    }
+}
@@ -0,0 +1,189 @@
+use types::evm_event::{EvmEvent, NewEvmEvent};
+
+use crate::{error::DalResult, StorageProcessor};
+
+#[derive(Debug)]
+pub struct EventsDal<'a, 'c> {
+    pub(crate) storage: &'a mut StorageProcessor<'c>,
+}
+
+impl EventsDal<'_, '_> {
+    /// Returns `false` if the event is already stored, so that logs can be re-indexed safely.
+    pub async fn insert_event(&mut self, event: &NewEvmEvent) -> DalResult<bool> {
+        let result = sqlx::query(
+            "INSERT INTO events \
+             (chain_id, tx_hash, event_idx, event_type, data, status, block_number) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7) \
+             ON CONFLICT (chain_id, tx_hash, event_idx) DO NOTHING",
+        )
+        .bind(event.chain_id)
+        .bind(&event.tx_hash)
+        .bind(event.event_idx)
+        .bind(&event.event_type)
+        .bind(&event.data)
+        .bind(&event.status)
+        .bind(event.block_number)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_events_by_tx_hash(
+        &mut self,
+        chain_id: i32,
+        tx_hash: &str,
+    ) -> DalResult<Vec<EvmEvent>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM events WHERE chain_id = $1 AND tx_hash = $2 ORDER BY event_idx",
+        )
+        .bind(chain_id)
+        .bind(tx_hash)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    /// Returns events in chain order, so that workers apply them in the order they happened.
+    pub async fn get_events_by_status(
+        &mut self,
+        chain_id: i32,
+        event_type: &str,
+        status: &str,
+        limit: u32,
+    ) -> DalResult<Vec<EvmEvent>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM events \
+             WHERE chain_id = $1 AND event_type = $2 AND status = $3 \
+             ORDER BY block_number, event_idx \
+             LIMIT $4",
+        )
+        .bind(chain_id)
+        .bind(event_type)
+        .bind(status)
+        .bind(limit as i64)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    /// Returns events of `event_type` in the inclusive block range, in chain order.
+    pub async fn get_events_by_block_range(
+        &mut self,
+        chain_id: i32,
+        event_type: &str,
+        from_block: i64,
+        to_block: i64,
+    ) -> DalResult<Vec<EvmEvent>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM events \
+             WHERE chain_id = $1 AND event_type = $2 AND block_number BETWEEN $3 AND $4 \
+             ORDER BY block_number, event_idx",
+        )
+        .bind(chain_id)
+        .bind(event_type)
+        .bind(from_block)
+        .bind(to_block)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    pub async fn update_event_status(
+        &mut self,
+        chain_id: i32,
+        tx_hash: &str,
+        event_idx: i32,
+        status: &str,
+    ) -> DalResult<bool> {
+        let result = sqlx::query(
+            "UPDATE events SET status = $4, updated_at = now() \
+             WHERE chain_id = $1 AND tx_hash = $2 AND event_idx = $3",
+        )
+        .bind(chain_id)
+        .bind(tx_hash)
+        .bind(event_idx)
+        .bind(status)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use types::evm_event::{BridgeEvent, NewEvmEvent, EVM_EVENT_UNPROCESSED};
+
+    use crate::{
+        connection::ConnectionPool,
+        tests::{insert_bridge, random_hash},
+    };
+
+    fn burn_event(chain_id: i32, tx_hash: &str, event_idx: i32, block_number: i64) -> NewEvmEvent {
+        let event = BridgeEvent::Burn {
+            from: "0x0000000000000000000000000000000000000001".to_string(),
+            amount: "100000".to_string(),
+            btc_address: "bcrt1qtest".to_string(),
+        };
+        NewEvmEvent {
+            chain_id,
+            tx_hash: tx_hash.to_string(),
+            event_idx,
+            event_type: event.event_type().to_string(),
+            data: serde_json::to_string(&event).unwrap(),
+            status: EVM_EVENT_UNPROCESSED.to_string(),
+            block_number,
+        }
+    }
+
+    #[tokio::test]
+    async fn inserting_events_is_idempotent() {
+        let pool = ConnectionPool::test_pool().await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let chain_id = insert_bridge(&mut storage).await;
+
+        let tx_hash = format!("0x{}", random_hash());
+        let dal = &mut storage.events_dal();
+        assert!(dal
+            .insert_event(&burn_event(chain_id, &tx_hash, 3, 10))
+            .await
+            .unwrap());
+        assert!(!dal
+            .insert_event(&burn_event(chain_id, &tx_hash, 3, 10))
+            .await
+            .unwrap());
+        assert!(dal
+            .insert_event(&burn_event(chain_id, &tx_hash, 1, 10))
+            .await
+            .unwrap());
+        assert!(dal
+            .insert_event(&burn_event(chain_id, "0x01", 0, 9))
+            .await
+            .unwrap());
+
+        let events = dal
+            .get_events_by_status(chain_id, "burn", EVM_EVENT_UNPROCESSED, 10)
+            .await
+            .unwrap();
+        let order: Vec<_> = events
+            .iter()
+            .map(|event| (event.block_number, event.event_idx))
+            .collect();
+        assert_eq!(order, [(Some(9), 0), (Some(10), 1), (Some(10), 3)]);
+
+        assert!(dal
+            .update_event_status(chain_id, &tx_hash, 1, "processed")
+            .await
+            .unwrap());
+        let events = dal.get_events_by_tx_hash(chain_id, &tx_hash).await.unwrap();
+        let statuses: Vec<_> = events.iter().map(|event| event.status.as_str()).collect();
+        assert_eq!(statuses, ["processed", EVM_EVENT_UNPROCESSED]);
+
+        let in_range = dal
+            .get_events_by_block_range(chain_id, "burn", 10, 20)
+            .await
+            .unwrap();
+        assert_eq!(in_range.len(), 2);
+        assert!(dal
+            .get_events_by_block_range(chain_id, "mint", 0, 20)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
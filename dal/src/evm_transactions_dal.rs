@@ -0,0 +1,42 @@
+use types::evm_event::{EvmTransaction, NewEvmTransaction};
+
+use crate::{error::DalResult, StorageProcessor};
+
+#[derive(Debug)]
+pub struct EvmTransactionsDal<'a, 'c> {
+    pub(crate) storage: &'a mut StorageProcessor<'c>,
+}
+
+impl EvmTransactionsDal<'_, '_> {
+    /// Returns `false` if the transaction is already stored.
+    pub async fn insert_evm_transaction(&mut self, tx: &NewEvmTransaction) -> DalResult<bool> {
+        let result = sqlx::query(
+            "INSERT INTO evm_transactions (chain_id, tx_hash, tx_type, status, data, extra_data) \
+             VALUES ($1, $2, $3, $4, $5, $6) \
+             ON CONFLICT (chain_id, tx_hash) DO NOTHING",
+        )
+        .bind(tx.chain_id)
+        .bind(&tx.tx_hash)
+        .bind(&tx.tx_type)
+        .bind(&tx.status)
+        .bind(&tx.data)
+        .bind(&tx.extra_data)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_evm_transaction(
+        &mut self,
+        chain_id: i32,
+        tx_hash: &str,
+    ) -> DalResult<Option<EvmTransaction>> {
+        Ok(
+            sqlx::query_as("SELECT * FROM evm_transactions WHERE chain_id = $1 AND tx_hash = $2")
+                .bind(chain_id)
+                .bind(tx_hash)
+                .fetch_optional(self.storage.conn())
+                .await?,
+        )
+    }
+}
@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use health_check::{Health, HealthStatus, HealthUpdater, ReactiveHealthCheck};
+use serde::Serialize;
+use tokio::sync::watch;
+
+use crate::{connection::ConnectionPool, error::DalResult};
+
+/// Upper bound on a single probe, so that a hanging database shows up as unhealthy.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize)]
+struct DatabaseHealthDetails {
+    pool_size: u32,
+    max_size: u32,
+    idle: usize,
+    in_use: usize,
+    last_error: Option<String>,
+}
+
+/// Periodically runs a trivial query against the database and reports the outcome, together
+/// with pool usage, as the `database` component on the health server.
+#[derive(Debug)]
+pub struct DatabaseHealthTask {
+    pool: ConnectionPool,
+    interval: Duration,
+    health_updater: HealthUpdater,
+    last_error: Option<String>,
+}
+
+impl DatabaseHealthTask {
+    pub fn new(pool: ConnectionPool, interval: Duration) -> (Self, ReactiveHealthCheck) {
+        let (health_check, health_updater) = ReactiveHealthCheck::new("database");
+        let this = Self {
+            pool,
+            interval,
+            health_updater,
+            last_error: None,
+        };
+        (this, health_check)
+    }
+
+    pub async fn run(mut self, mut stop_receiver: watch::Receiver<bool>) -> anyhow::Result<()> {
+        let mut timer = tokio::time::interval(self.interval);
+        loop {
+            tokio::select! {
+                _ = timer.tick() => {}
+                _ = stop_receiver.changed() => break,
+            }
+            if *stop_receiver.borrow() {
+                break;
+            }
+            let health = self.check().await;
+            self.health_updater.update(health);
+        }
+        logs::info!("Stop signal received, database health check is shutting down");
+        Ok(())
+    }
+
+    async fn check(&mut self) -> Health {
+        let status = match tokio::time::timeout(PROBE_TIMEOUT, self.probe()).await {
+            Ok(Ok(())) => HealthStatus::Ready,
+            Ok(Err(err)) => {
+                logs::warn!("database health check failed: {err}");
+                self.last_error = Some(err.to_string());
+                HealthStatus::NotReady
+            }
+            Err(_) => {
+                logs::warn!("database health check timed out after {PROBE_TIMEOUT:?}");
+                self.last_error = Some(format!("probe timed out after {PROBE_TIMEOUT:?}"));
+                HealthStatus::NotReady
+            }
+        };
+
+        let (pool_size, idle) = self.pool.stats();
+        Health::from(status).with_details(DatabaseHealthDetails {
+            pool_size,
+            max_size: self.pool.max_size(),
+            idle,
+            in_use: (pool_size as usize).saturating_sub(idle),
+            last_error: self.last_error.clone(),
+        })
+    }
+
+    async fn probe(&self) -> DalResult<()> {
+        let mut storage = self.pool.access_storage().await?;
+        sqlx::query("SELECT 1").execute(storage.conn()).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use health_check::CheckHealth;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn reachable_database_is_ready() {
+        let pool = ConnectionPool::test_pool().await;
+        let (mut task, health_check) = DatabaseHealthTask::new(pool, Duration::from_secs(1));
+
+        let health = task.check().await;
+        assert_matches!(health.status(), HealthStatus::Ready);
+        assert!(task.last_error.is_none());
+
+        task.health_updater.update(health);
+        assert_matches!(
+            health_check.check_health().await.status(),
+            HealthStatus::Ready
+        );
+    }
+}
@@ -0,0 +1,5 @@
+use sqlx::migrate::Migrator;
+
+/// Schema migrations from `dal/migrations`, embedded into the binary at compile time so that
+/// a fresh node can bootstrap its schema without `sqlx-cli` or the shell scripts.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
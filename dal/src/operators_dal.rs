@@ -0,0 +1,203 @@
+use types::operator::{NewOperator, Operator, OperatorFilter, OPERATOR_SLASHED};
+
+use crate::{error::DalResult, StorageProcessor};
+
+#[derive(Debug)]
+pub struct OperatorsDal<'a, 'c> {
+    pub(crate) storage: &'a mut StorageProcessor<'c>,
+}
+
+impl OperatorsDal<'_, '_> {
+    pub async fn insert_operator(&mut self, operator: &NewOperator) -> DalResult<i32> {
+        Ok(sqlx::query_scalar(
+            "INSERT INTO operators \
+             (address, chain_id, public_key, fee, status, initial_stake_amount, \
+              remaining_stake_amount, max_support_amount, min_support_amount, \
+              max_pegin_cnt, max_pegout_cnt, register_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $6, $7, $8, $9, $10, $11) \
+             RETURNING id",
+        )
+        .bind(&operator.address)
+        .bind(operator.chain_id)
+        .bind(&operator.public_key)
+        .bind(operator.fee)
+        .bind(&operator.status)
+        .bind(operator.stake_amount)
+        .bind(operator.max_support_amount)
+        .bind(operator.min_support_amount)
+        .bind(operator.max_pegin_cnt)
+        .bind(operator.max_pegout_cnt)
+        .bind(operator.register_at)
+        .fetch_one(self.storage.conn())
+        .await?)
+    }
+
+    pub async fn get_operator_by_id(&mut self, id: i32) -> DalResult<Option<Operator>> {
+        Ok(sqlx::query_as("SELECT * FROM operators WHERE id = $1")
+            .bind(id)
+            .fetch_optional(self.storage.conn())
+            .await?)
+    }
+
+    pub async fn get_operator_by_public_key(
+        &mut self,
+        public_key: &str,
+    ) -> DalResult<Option<Operator>> {
+        Ok(
+            sqlx::query_as("SELECT * FROM operators WHERE public_key = $1")
+                .bind(public_key)
+                .fetch_optional(self.storage.conn())
+                .await?,
+        )
+    }
+
+    pub async fn get_operators_by_address(&mut self, address: &str) -> DalResult<Vec<Operator>> {
+        Ok(
+            sqlx::query_as("SELECT * FROM operators WHERE address = $1 ORDER BY id")
+                .bind(address)
+                .fetch_all(self.storage.conn())
+                .await?,
+        )
+    }
+
+    /// Lists operators matching `filter`, cheapest first.
+    pub async fn get_operators(&mut self, filter: &OperatorFilter) -> DalResult<Vec<Operator>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM operators \
+             WHERE ($1::INTEGER IS NULL OR chain_id = $1) \
+             AND ($2::TEXT IS NULL OR status = $2) \
+             AND ($3::BIGINT IS NULL OR $3 BETWEEN min_support_amount AND max_support_amount) \
+             ORDER BY fee, id",
+        )
+        .bind(filter.chain_id)
+        .bind(filter.status.as_deref())
+        .bind(filter.amount)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    /// Returns `false` if there is no operator with the given id.
+    pub async fn update_operator_status(&mut self, id: i32, status: &str) -> DalResult<bool> {
+        let result =
+            sqlx::query("UPDATE operators SET status = $2, updated_at = now() WHERE id = $1")
+                .bind(id)
+                .bind(status)
+                .execute(self.storage.conn())
+                .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn increment_pegin_cnt(&mut self, id: i32) -> DalResult<bool> {
+        let result = sqlx::query(
+            "UPDATE operators SET pegin_cnt = pegin_cnt + 1, updated_at = now() WHERE id = $1",
+        )
+        .bind(id)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn increment_pegout_cnt(&mut self, id: i32) -> DalResult<bool> {
+        let result = sqlx::query(
+            "UPDATE operators SET pegout_cnt = pegout_cnt + 1, updated_at = now() WHERE id = $1",
+        )
+        .bind(id)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Records a slash of `amount` from the operator's remaining stake. The operator is marked
+    /// slashed, so it isn't assigned peg-outs anymore.
+    pub async fn slash(&mut self, id: i32, amount: i64) -> DalResult<bool> {
+        let result = sqlx::query(
+            "UPDATE operators \
+             SET status = $3, \
+                 slash_cnt = slash_cnt + 1, \
+                 remaining_stake_amount = GREATEST(remaining_stake_amount - $2, 0), \
+                 updated_at = now() \
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(amount)
+        .bind(OPERATOR_SLASHED)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use types::operator::{OperatorFilter, OPERATOR_SLASHED};
+
+    use crate::{
+        connection::ConnectionPool,
+        tests::{insert_bridge, new_operator, random_public_key},
+    };
+
+    #[tokio::test]
+    async fn filtering_operators() {
+        let pool = ConnectionPool::test_pool().await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let chain_id = insert_bridge(&mut storage).await;
+
+        let active_key = random_public_key();
+        let active_id = storage
+            .operators_dal()
+            .insert_operator(&new_operator(chain_id, &active_key))
+            .await
+            .unwrap();
+        let inactive_id = storage
+            .operators_dal()
+            .insert_operator(&new_operator(chain_id, &random_public_key()))
+            .await
+            .unwrap();
+        storage
+            .operators_dal()
+            .update_operator_status(inactive_id, "inactive")
+            .await
+            .unwrap();
+
+        let filter = OperatorFilter {
+            chain_id: Some(chain_id),
+            status: Some("active".to_string()),
+            amount: Some(1_000_000),
+        };
+        let operators = storage
+            .operators_dal()
+            .get_operators(&filter)
+            .await
+            .unwrap();
+        assert_eq!(
+            operators.iter().map(|op| op.id).collect::<Vec<_>>(),
+            [active_id]
+        );
+
+        let too_large = OperatorFilter {
+            amount: Some(1_000_000_000),
+            ..OperatorFilter::default()
+        };
+        assert!(storage
+            .operators_dal()
+            .get_operators(&too_large)
+            .await
+            .unwrap()
+            .is_empty());
+
+        storage
+            .operators_dal()
+            .slash(active_id, 1_000)
+            .await
+            .unwrap();
+        let operator = storage
+            .operators_dal()
+            .get_operator_by_public_key(&active_key)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(operator.status, OPERATOR_SLASHED);
+        assert_eq!(operator.slash_cnt, 1);
+        assert_eq!(operator.remaining_stake_amount, 10_000_000 - 1_000);
+    }
+}
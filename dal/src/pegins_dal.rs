@@ -0,0 +1,663 @@
+use types::{
+    pagination::Pagination,
+    pegin::{NewPegin, PeginDetails, PeginOperation, PeginStatus},
+};
+
+use crate::{error::DalResult, StorageProcessor};
+
+#[derive(Debug)]
+pub struct PeginsDal<'a, 'c> {
+    pub(crate) storage: &'a mut StorageProcessor<'c>,
+}
+
+impl PeginsDal<'_, '_> {
+    pub async fn insert_pegin(&mut self, pegin: &NewPegin) -> DalResult<i32> {
+        Ok(sqlx::query_scalar(
+            "INSERT INTO pegins \
+             (target_chain_id, public_key, sender_address, status, receive_address, amount) \
+             VALUES ($1, $2, $3, $4, $5, $6) \
+             RETURNING id",
+        )
+        .bind(pegin.target_chain_id)
+        .bind(&pegin.public_key)
+        .bind(&pegin.sender_address)
+        .bind(pegin.status.as_ref())
+        .bind(&pegin.receive_address)
+        .bind(pegin.amount)
+        .fetch_one(self.storage.conn())
+        .await?)
+    }
+
+    pub async fn get_pegin_by_id(&mut self, id: i32) -> DalResult<Option<PeginDetails>> {
+        Ok(sqlx::query_as("SELECT * FROM pegins WHERE id = $1")
+            .bind(id)
+            .fetch_optional(self.storage.conn())
+            .await?)
+    }
+
+    pub async fn get_pegin_by_tx_hash(
+        &mut self,
+        pegin_tx_hash: &str,
+    ) -> DalResult<Option<PeginDetails>> {
+        Ok(
+            sqlx::query_as("SELECT * FROM pegins WHERE pegin_tx_hash = $1")
+                .bind(pegin_tx_hash)
+                .fetch_optional(self.storage.conn())
+                .await?,
+        )
+    }
+
+    /// Returns the oldest peg-ins in the given status first, so that workers process them in order.
+    pub async fn get_pegins_by_status(
+        &mut self,
+        status: PeginStatus,
+        limit: u32,
+    ) -> DalResult<Vec<PeginDetails>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM pegins WHERE status = $1 ORDER BY created_at, id LIMIT $2",
+        )
+        .bind(status.as_ref())
+        .bind(limit as i64)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    pub async fn get_pegins_by_sender_address(
+        &mut self,
+        sender_address: &str,
+        pagination: Pagination,
+    ) -> DalResult<Vec<PeginDetails>> {
+        let before = pagination.before;
+        Ok(sqlx::query_as(
+            "SELECT * FROM pegins \
+             WHERE sender_address = $1 \
+             AND ($2::TIMESTAMP IS NULL OR (created_at, id) < ($2, $3)) \
+             ORDER BY created_at DESC, id DESC \
+             LIMIT $4",
+        )
+        .bind(sender_address)
+        .bind(before.map(|cursor| cursor.created_at))
+        .bind(before.map(|cursor| cursor.id))
+        .bind(pagination.limit())
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    pub async fn get_pegins(&mut self, pagination: Pagination) -> DalResult<Vec<PeginDetails>> {
+        let before = pagination.before;
+        Ok(sqlx::query_as(
+            "SELECT * FROM pegins \
+             WHERE ($1::TIMESTAMP IS NULL OR (created_at, id) < ($1, $2)) \
+             ORDER BY created_at DESC, id DESC \
+             LIMIT $3",
+        )
+        .bind(before.map(|cursor| cursor.created_at))
+        .bind(before.map(|cursor| cursor.id))
+        .bind(pagination.limit())
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    /// Moves a peg-in from `expected` to `next`, failing if the lifecycle doesn't allow it.
+    /// Returns `false` if the peg-in isn't in `expected` (anymore), e.g. because a concurrent
+    /// worker advanced it first.
+    pub async fn update_pegin_status(
+        &mut self,
+        id: i32,
+        expected: PeginStatus,
+        next: PeginStatus,
+    ) -> DalResult<bool> {
+        expected.transition(next)?;
+        let result = sqlx::query(
+            "UPDATE pegins SET status = $3, updated_at = now() WHERE id = $1 AND status = $2",
+        )
+        .bind(id)
+        .bind(expected.as_ref())
+        .bind(next.as_ref())
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Records that the committee member with `public_key` accepted the deposit of a peg-in and
+    /// returns how many members accepted it so far. The peg-in row is locked first, so that
+    /// members accepting concurrently count each other.
+    pub async fn accept_pegin(&mut self, id: i32, public_key: &str) -> DalResult<i64> {
+        sqlx::query("SELECT 1 FROM pegins WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .execute(self.storage.conn())
+            .await?;
+        sqlx::query(
+            "INSERT INTO pegin_acceptances (pegin_id, public_key) VALUES ($1, $2) \
+             ON CONFLICT (pegin_id, public_key) DO NOTHING",
+        )
+        .bind(id)
+        .bind(public_key)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(
+            sqlx::query_scalar("SELECT COUNT(*) FROM pegin_acceptances WHERE pegin_id = $1")
+                .bind(id)
+                .fetch_one(self.storage.conn())
+                .await?,
+        )
+    }
+
+    /// Attaches the signed peg-in transaction submitted by the user.
+    pub async fn set_pegin_tx(
+        &mut self,
+        id: i32,
+        pegin_tx_hash: &str,
+        raw_pegin_hex: &str,
+    ) -> DalResult<bool> {
+        let result = sqlx::query(
+            "UPDATE pegins \
+             SET pegin_tx_hash = $2, raw_pegin_hex = $3, updated_at = now() \
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(pegin_tx_hash)
+        .bind(raw_pegin_hex)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Moves `created` peg-ins to `deposited` once their `pegin_tx_hash` is a confirmed
+    /// deposit paying at least `amount` to the bridge of the target chain.
+    /// Returns the ids of the updated peg-ins.
+    pub async fn mark_confirmed_deposits(&mut self) -> DalResult<Vec<i32>> {
+        let (expected, next) = (PeginStatus::Created, PeginStatus::Deposited);
+        expected.transition(next)?;
+        let ids = sqlx::query_scalar(
+            "UPDATE pegins p SET status = $2, updated_at = now() \
+             FROM bitcoin_transactions b \
+             WHERE b.tx_hash = p.pegin_tx_hash \
+             AND b.tx_type = 'pegin_deposit' AND b.status = 'confirmed' \
+             AND p.status = $1 \
+             AND ( \
+                 SELECT COALESCE(SUM((output->>'amount')::BIGINT), 0) \
+                 FROM jsonb_array_elements(b.extra_data::jsonb) output \
+                 WHERE (output->>'chain_id')::INTEGER = p.target_chain_id \
+             ) >= p.amount \
+             RETURNING p.id",
+        )
+        .bind(expected.as_ref())
+        .bind(next.as_ref())
+        .fetch_all(self.storage.conn())
+        .await?;
+        Ok(ids)
+    }
+
+    /// Moves `deposited` peg-ins back to `created` when their deposit is no longer confirmed
+    /// after a reorg. Returns the ids of the updated peg-ins.
+    pub async fn revert_deposits(&mut self, tx_hashes: &[String]) -> DalResult<Vec<i32>> {
+        let (expected, next) = (PeginStatus::Deposited, PeginStatus::Created);
+        expected.transition(next)?;
+        let ids = sqlx::query_scalar(
+            "UPDATE pegins SET status = $3, updated_at = now() \
+             WHERE status = $2 AND pegin_tx_hash = ANY($1) \
+             RETURNING id",
+        )
+        .bind(tx_hashes)
+        .bind(expected.as_ref())
+        .bind(next.as_ref())
+        .fetch_all(self.storage.conn())
+        .await?;
+        Ok(ids)
+    }
+
+    /// Flags peg-ins the committee already accepted when their deposit is no longer confirmed
+    /// after a reorg. Their status is kept, as the deposit usually confirms again on the new
+    /// chain. Returns the ids of the newly flagged peg-ins.
+    pub async fn flag_reorged_deposits(&mut self, tx_hashes: &[String]) -> DalResult<Vec<i32>> {
+        let accepted = [
+            PeginStatus::Confirmed,
+            PeginStatus::PresignCollected,
+            PeginStatus::Minted,
+        ]
+        .map(|status| status.as_ref().to_string());
+        let ids = sqlx::query_scalar(
+            "UPDATE pegins SET deposit_reorged_at = now(), updated_at = now() \
+             WHERE status = ANY($2) AND pegin_tx_hash = ANY($1) \
+             AND deposit_reorged_at IS NULL \
+             RETURNING id",
+        )
+        .bind(tx_hashes)
+        .bind(&accepted[..])
+        .fetch_all(self.storage.conn())
+        .await?;
+        Ok(ids)
+    }
+
+    /// Clears the flag of reorged peg-ins whose deposit is confirmed again. Returns the ids of
+    /// the updated peg-ins.
+    pub async fn clear_reconfirmed_deposits(&mut self) -> DalResult<Vec<i32>> {
+        let ids = sqlx::query_scalar(
+            "UPDATE pegins p SET deposit_reorged_at = NULL, updated_at = now() \
+             FROM bitcoin_transactions b \
+             WHERE b.tx_hash = p.pegin_tx_hash \
+             AND b.tx_type = 'pegin_deposit' AND b.status = 'confirmed' \
+             AND p.deposit_reorged_at IS NOT NULL \
+             RETURNING p.id",
+        )
+        .fetch_all(self.storage.conn())
+        .await?;
+        Ok(ids)
+    }
+
+    /// Peg-ins whose deposit is flagged as reorged, oldest flag first.
+    pub async fn get_reorged_pegins(&mut self, limit: u32) -> DalResult<Vec<PeginDetails>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM pegins WHERE deposit_reorged_at IS NOT NULL \
+             ORDER BY deposit_reorged_at, id LIMIT $1",
+        )
+        .bind(limit as i64)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    pub async fn insert_pegin_operation(
+        &mut self,
+        pegin_id: i32,
+        operator_id: i32,
+        raw_take_tx: &str,
+        status: &str,
+    ) -> DalResult<i32> {
+        Ok(sqlx::query_scalar(
+            "INSERT INTO pegin_operations (pegin_id, operator_id, raw_take_tx, status) \
+             VALUES ($1, $2, $3, $4) \
+             RETURNING id",
+        )
+        .bind(pegin_id)
+        .bind(operator_id)
+        .bind(raw_take_tx)
+        .bind(status)
+        .fetch_one(self.storage.conn())
+        .await?)
+    }
+
+    pub async fn get_pegin_operations(&mut self, pegin_id: i32) -> DalResult<Vec<PeginOperation>> {
+        Ok(
+            sqlx::query_as("SELECT * FROM pegin_operations WHERE pegin_id = $1 ORDER BY id")
+                .bind(pegin_id)
+                .fetch_all(self.storage.conn())
+                .await?,
+        )
+    }
+
+    pub async fn get_pegin_operation(
+        &mut self,
+        pegin_id: i32,
+        operator_id: i32,
+    ) -> DalResult<Option<PeginOperation>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM pegin_operations WHERE pegin_id = $1 AND operator_id = $2",
+        )
+        .bind(pegin_id)
+        .bind(operator_id)
+        .fetch_optional(self.storage.conn())
+        .await?)
+    }
+
+    /// Distinct amounts of at least `min_amount` held by minted peg-ins that no peg-out is paid
+    /// from yet and whose deposit isn't reorged, smallest first.
+    pub async fn get_available_pegin_amounts(
+        &mut self,
+        min_amount: i64,
+        limit: u32,
+    ) -> DalResult<Vec<i64>> {
+        Ok(sqlx::query_scalar(
+            "SELECT DISTINCT amount FROM pegins p \
+             WHERE status = $1 AND amount >= $2 AND deposit_reorged_at IS NULL \
+             AND NOT EXISTS (SELECT 1 FROM pegouts WHERE pegin_id = p.id) \
+             ORDER BY amount \
+             LIMIT $3",
+        )
+        .bind(PeginStatus::Minted.as_ref())
+        .bind(min_amount)
+        .bind(limit as i64)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    /// Locks the oldest minted peg-in of exactly `amount` that no peg-out is paid from yet, whose
+    /// deposit isn't reorged and that `operator_id` has a take transaction for. Peg-ins locked by
+    /// concurrent transactions are skipped, so this has to run in a transaction that assigns the
+    /// peg-out before committing. A peg-in assigned by a transaction that committed meanwhile may
+    /// still be returned; assigning it again then fails with a unique violation.
+    pub async fn lock_available_pegin(
+        &mut self,
+        amount: i64,
+        operator_id: i32,
+    ) -> DalResult<Option<PeginOperation>> {
+        Ok(sqlx::query_as(
+            "SELECT o.* FROM pegin_operations o \
+             JOIN pegins p ON p.id = o.pegin_id \
+             WHERE o.operator_id = $3 AND p.status = $1 AND p.amount = $2 \
+             AND p.deposit_reorged_at IS NULL \
+             AND NOT EXISTS (SELECT 1 FROM pegouts WHERE pegin_id = p.id) \
+             ORDER BY p.created_at, p.id \
+             LIMIT 1 \
+             FOR UPDATE OF p SKIP LOCKED",
+        )
+        .bind(PeginStatus::Minted.as_ref())
+        .bind(amount)
+        .bind(operator_id)
+        .fetch_optional(self.storage.conn())
+        .await?)
+    }
+
+    pub async fn update_pegin_operation_status(
+        &mut self,
+        id: i32,
+        status: &str,
+    ) -> DalResult<bool> {
+        let result = sqlx::query(
+            "UPDATE pegin_operations SET status = $2, updated_at = now() WHERE id = $1",
+        )
+        .bind(id)
+        .bind(status)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use types::{
+        pagination::{PageCursor, Pagination},
+        pegin::PeginStatus,
+    };
+
+    use crate::{
+        connection::ConnectionPool,
+        error::DalError,
+        tests::{
+            insert_bridge, new_operator, new_pegin, new_pegout, random_hash, random_public_key,
+        },
+        StorageProcessor,
+    };
+
+    async fn insert_minted_pegin(
+        storage: &mut StorageProcessor<'_>,
+        chain_id: i32,
+        amount: i64,
+    ) -> i32 {
+        let id = storage
+            .pegins_dal()
+            .insert_pegin(&new_pegin(chain_id, "bcrt1qsender", amount))
+            .await
+            .unwrap();
+        for (expected, next) in [
+            (PeginStatus::Created, PeginStatus::Deposited),
+            (PeginStatus::Deposited, PeginStatus::Confirmed),
+            (PeginStatus::Confirmed, PeginStatus::PresignCollected),
+            (PeginStatus::PresignCollected, PeginStatus::Minted),
+        ] {
+            storage
+                .pegins_dal()
+                .update_pegin_status(id, expected, next)
+                .await
+                .unwrap();
+        }
+        id
+    }
+
+    #[tokio::test]
+    async fn inserting_and_querying_pegins() {
+        let pool = ConnectionPool::test_pool().await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let chain_id = insert_bridge(&mut storage).await;
+
+        let id = storage
+            .pegins_dal()
+            .insert_pegin(&new_pegin(chain_id, "bcrt1qsender", 100_000))
+            .await
+            .unwrap();
+        let pegin = storage
+            .pegins_dal()
+            .get_pegin_by_id(id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pegin.amount, 100_000);
+        assert_eq!(pegin.status, PeginStatus::Created);
+        assert_eq!(pegin.pegin_tx_hash, None);
+
+        let tx_hash = random_hash();
+        assert!(storage
+            .pegins_dal()
+            .set_pegin_tx(id, &tx_hash, "0200")
+            .await
+            .unwrap());
+        let by_hash = storage
+            .pegins_dal()
+            .get_pegin_by_tx_hash(&tx_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(by_hash.id, id);
+
+        assert!(storage
+            .pegins_dal()
+            .update_pegin_status(id, PeginStatus::Created, PeginStatus::Deposited)
+            .await
+            .unwrap());
+        let deposited = storage
+            .pegins_dal()
+            .get_pegins_by_status(PeginStatus::Deposited, 10)
+            .await
+            .unwrap();
+        assert_eq!(deposited.len(), 1);
+        assert!(!storage
+            .pegins_dal()
+            .update_pegin_status(id + 1, PeginStatus::Created, PeginStatus::Deposited)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn advancing_pegin_status() {
+        let pool = ConnectionPool::test_pool().await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let chain_id = insert_bridge(&mut storage).await;
+        let id = storage
+            .pegins_dal()
+            .insert_pegin(&new_pegin(chain_id, "bcrt1qsender", 1))
+            .await
+            .unwrap();
+
+        let err = storage
+            .pegins_dal()
+            .update_pegin_status(id, PeginStatus::Created, PeginStatus::Minted)
+            .await
+            .unwrap_err();
+        assert_matches!(err, DalError::InvalidTransition(_));
+
+        // Only the first of two workers seeing the peg-in as `created` advances it.
+        for advanced in [true, false] {
+            let updated = storage
+                .pegins_dal()
+                .update_pegin_status(id, PeginStatus::Created, PeginStatus::Deposited)
+                .await
+                .unwrap();
+            assert_eq!(updated, advanced);
+        }
+
+        for (expected, next) in [
+            (PeginStatus::Deposited, PeginStatus::Confirmed),
+            (PeginStatus::Confirmed, PeginStatus::PresignCollected),
+            (PeginStatus::PresignCollected, PeginStatus::Minted),
+        ] {
+            assert!(storage
+                .pegins_dal()
+                .update_pegin_status(id, expected, next)
+                .await
+                .unwrap());
+        }
+        let pegin = storage
+            .pegins_dal()
+            .get_pegin_by_id(id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pegin.status, PeginStatus::Minted);
+        assert!(pegin.status.is_final());
+    }
+
+    #[tokio::test]
+    async fn paginating_pegins_by_sender() {
+        let pool = ConnectionPool::test_pool().await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let chain_id = insert_bridge(&mut storage).await;
+
+        let mut ids = vec![];
+        for amount in [1, 2, 3] {
+            let id = storage
+                .pegins_dal()
+                .insert_pegin(&new_pegin(chain_id, "bcrt1qsender", amount))
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+        storage
+            .pegins_dal()
+            .insert_pegin(&new_pegin(chain_id, "bcrt1qother", 4))
+            .await
+            .unwrap();
+
+        let first_page = storage
+            .pegins_dal()
+            .get_pegins_by_sender_address("bcrt1qsender", Pagination::first(2))
+            .await
+            .unwrap();
+        // All rows share `created_at` within a transaction, so `id` breaks the tie.
+        assert_eq!(
+            first_page.iter().map(|pegin| pegin.id).collect::<Vec<_>>(),
+            [ids[2], ids[1]]
+        );
+
+        let last = first_page.last().unwrap();
+        let next = Pagination {
+            before: Some(PageCursor {
+                created_at: last.created_at,
+                id: last.id,
+            }),
+            limit: 2,
+        };
+        let second_page = storage
+            .pegins_dal()
+            .get_pegins_by_sender_address("bcrt1qsender", next)
+            .await
+            .unwrap();
+        assert_eq!(
+            second_page.iter().map(|pegin| pegin.id).collect::<Vec<_>>(),
+            [ids[0]]
+        );
+    }
+
+    #[tokio::test]
+    async fn rolled_back_pegins_are_not_visible() {
+        let pool = ConnectionPool::test_pool().await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let chain_id = insert_bridge(&mut storage).await;
+
+        let mut transaction = storage.start_transaction().await.unwrap();
+        let id = transaction
+            .pegins_dal()
+            .insert_pegin(&new_pegin(chain_id, "bcrt1qsender", 1))
+            .await
+            .unwrap();
+        assert!(transaction
+            .pegins_dal()
+            .get_pegin_by_id(id)
+            .await
+            .unwrap()
+            .is_some());
+        transaction.rollback().await.unwrap();
+
+        assert!(storage
+            .pegins_dal()
+            .get_pegin_by_id(id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn locking_pegin_utxos_for_pegouts() {
+        let pool = ConnectionPool::test_pool().await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let chain_id = insert_bridge(&mut storage).await;
+        let operator_id = storage
+            .operators_dal()
+            .insert_operator(&new_operator(chain_id, &random_public_key()))
+            .await
+            .unwrap();
+
+        let mut pegin_ids = vec![];
+        for amount in [100, 200, 200] {
+            let id = insert_minted_pegin(&mut storage, chain_id, amount).await;
+            storage
+                .pegins_dal()
+                .insert_pegin_operation(id, operator_id, "0200", "pending")
+                .await
+                .unwrap();
+            pegin_ids.push(id);
+        }
+        // Not minted yet, so it can't pay a peg-out.
+        storage
+            .pegins_dal()
+            .insert_pegin(&new_pegin(chain_id, "bcrt1qsender", 300))
+            .await
+            .unwrap();
+
+        let amounts = storage
+            .pegins_dal()
+            .get_available_pegin_amounts(150, 10)
+            .await
+            .unwrap();
+        assert_eq!(amounts, [200]);
+
+        let operation = storage
+            .pegins_dal()
+            .lock_available_pegin(200, operator_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(operation.pegin_id, pegin_ids[1]);
+        let pegout_id = storage
+            .pegouts_dal()
+            .insert_pegout(&new_pegout(chain_id, &random_hash(), 200))
+            .await
+            .unwrap();
+        storage
+            .pegouts_dal()
+            .assign_operator(pegout_id, operation.pegin_id, operator_id)
+            .await
+            .unwrap();
+
+        let operation = storage
+            .pegins_dal()
+            .lock_available_pegin(200, operator_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(operation.pegin_id, pegin_ids[2]);
+        assert!(storage
+            .pegins_dal()
+            .lock_available_pegin(200, operator_id + 1)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(storage
+            .pegins_dal()
+            .lock_available_pegin(300, operator_id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
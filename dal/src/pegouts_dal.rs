@@ -0,0 +1,460 @@
+use types::{
+    pagination::Pagination,
+    pegout::{NewPegout, PegoutDetail, PegoutStatus, PegoutStatusTransition, PegoutTransition},
+};
+
+use crate::{error::DalResult, StorageProcessor};
+
+#[derive(Debug)]
+pub struct PegoutsDal<'a, 'c> {
+    pub(crate) storage: &'a mut StorageProcessor<'c>,
+}
+
+impl PegoutsDal<'_, '_> {
+    pub async fn insert_pegout(&mut self, pegout: &NewPegout) -> DalResult<i32> {
+        Ok(sqlx::query_scalar(
+            "INSERT INTO pegouts \
+             (chain_id, burn_tx_hash, event_idx, sender_address, receive_address, amount, status) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7) \
+             RETURNING id",
+        )
+        .bind(pegout.chain_id)
+        .bind(&pegout.burn_tx_hash)
+        .bind(pegout.event_idx)
+        .bind(&pegout.sender_address)
+        .bind(&pegout.receive_address)
+        .bind(pegout.amount)
+        .bind(pegout.status.as_ref())
+        .fetch_one(self.storage.conn())
+        .await?)
+    }
+
+    pub async fn get_pegout_by_id(&mut self, id: i32) -> DalResult<Option<PegoutDetail>> {
+        Ok(sqlx::query_as("SELECT * FROM pegouts WHERE id = $1")
+            .bind(id)
+            .fetch_optional(self.storage.conn())
+            .await?)
+    }
+
+    /// Returns the peg-out started by the burn at `event_idx` of `burn_tx_hash` on `chain_id`.
+    pub async fn get_pegout_by_burn_event(
+        &mut self,
+        chain_id: i32,
+        burn_tx_hash: &str,
+        event_idx: i32,
+    ) -> DalResult<Option<PegoutDetail>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM pegouts WHERE chain_id = $1 AND burn_tx_hash = $2 AND event_idx = $3",
+        )
+        .bind(chain_id)
+        .bind(burn_tx_hash)
+        .bind(event_idx)
+        .fetch_optional(self.storage.conn())
+        .await?)
+    }
+
+    /// Returns the peg-outs started by the burns of `burn_tx_hash`, in the order of the events.
+    pub async fn get_pegouts_by_burn_tx_hash(
+        &mut self,
+        burn_tx_hash: &str,
+    ) -> DalResult<Vec<PegoutDetail>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM pegouts WHERE burn_tx_hash = $1 ORDER BY chain_id, event_idx",
+        )
+        .bind(burn_tx_hash)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    pub async fn get_pegout_by_payout_tx_hash(
+        &mut self,
+        payout_tx_hash: &str,
+    ) -> DalResult<Option<PegoutDetail>> {
+        Ok(
+            sqlx::query_as("SELECT * FROM pegouts WHERE payout_tx_hash = $1")
+                .bind(payout_tx_hash)
+                .fetch_optional(self.storage.conn())
+                .await?,
+        )
+    }
+
+    /// Returns the oldest peg-outs in the given status first, so that workers process them in order.
+    pub async fn get_pegouts_by_status(
+        &mut self,
+        status: PegoutStatus,
+        limit: u32,
+    ) -> DalResult<Vec<PegoutDetail>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM pegouts WHERE status = $1 ORDER BY created_at, id LIMIT $2",
+        )
+        .bind(status.as_ref())
+        .bind(limit as i64)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    /// Returns peg-outs in the given status whose deadline is below `height`.
+    pub async fn get_expired_pegouts(
+        &mut self,
+        status: PegoutStatus,
+        height: i64,
+        limit: u32,
+    ) -> DalResult<Vec<PegoutDetail>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM pegouts \
+             WHERE status = $1 AND deadline_height < $2 \
+             ORDER BY deadline_height, id \
+             LIMIT $3",
+        )
+        .bind(status.as_ref())
+        .bind(height)
+        .bind(limit as i64)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    /// Returns peg-outs with an assigned operator, starting at `from_id` in ascending id order.
+    pub async fn get_operated_pegouts(
+        &mut self,
+        from_id: i32,
+        limit: u32,
+    ) -> DalResult<Vec<PegoutDetail>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM pegouts \
+             WHERE id >= $1 AND operator_id IS NOT NULL \
+             ORDER BY id \
+             LIMIT $2",
+        )
+        .bind(from_id)
+        .bind(limit as i64)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    pub async fn get_pegouts_by_sender_address(
+        &mut self,
+        sender_address: &str,
+        pagination: Pagination,
+    ) -> DalResult<Vec<PegoutDetail>> {
+        let before = pagination.before;
+        Ok(sqlx::query_as(
+            "SELECT * FROM pegouts \
+             WHERE sender_address = $1 \
+             AND ($2::TIMESTAMP IS NULL OR (created_at, id) < ($2, $3)) \
+             ORDER BY created_at DESC, id DESC \
+             LIMIT $4",
+        )
+        .bind(sender_address)
+        .bind(before.map(|cursor| cursor.created_at))
+        .bind(before.map(|cursor| cursor.id))
+        .bind(pagination.limit())
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    pub async fn get_pegouts(&mut self, pagination: Pagination) -> DalResult<Vec<PegoutDetail>> {
+        let before = pagination.before;
+        Ok(sqlx::query_as(
+            "SELECT * FROM pegouts \
+             WHERE ($1::TIMESTAMP IS NULL OR (created_at, id) < ($1, $2)) \
+             ORDER BY created_at DESC, id DESC \
+             LIMIT $3",
+        )
+        .bind(before.map(|cursor| cursor.created_at))
+        .bind(before.map(|cursor| cursor.id))
+        .bind(pagination.limit())
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    /// Applies `transition` and records it in `pegout_status_transitions`, failing if the
+    /// lifecycle doesn't allow it. Returns `false` if the peg-out isn't in `transition.from`
+    /// (anymore), e.g. because a concurrent worker advanced it first.
+    pub async fn update_pegout_status(
+        &mut self,
+        id: i32,
+        transition: PegoutTransition,
+    ) -> DalResult<bool> {
+        transition.from.transition(transition.to)?;
+        let result = sqlx::query(
+            "WITH updated AS ( \
+                 UPDATE pegouts SET status = $3, deadline_height = $5, updated_at = now() \
+                 WHERE id = $1 AND status = $2 \
+                 RETURNING id \
+             ) \
+             INSERT INTO pegout_status_transitions (pegout_id, from_status, to_status, block_height) \
+             SELECT id, $2, $3, $4 FROM updated",
+        )
+        .bind(id)
+        .bind(transition.from.as_ref())
+        .bind(transition.to.as_ref())
+        .bind(transition.block_height)
+        .bind(transition.deadline_height)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Returns the status changes of a peg-out, oldest first.
+    pub async fn get_status_transitions(
+        &mut self,
+        pegout_id: i32,
+    ) -> DalResult<Vec<PegoutStatusTransition>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM pegout_status_transitions WHERE pegout_id = $1 ORDER BY id",
+        )
+        .bind(pegout_id)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    /// Assigns the operator that pays the peg-out out of the UTXO of `pegin_id`.
+    /// Fails with a unique violation if the peg-in already pays another peg-out.
+    pub async fn assign_operator(
+        &mut self,
+        id: i32,
+        pegin_id: i32,
+        operator_id: i32,
+    ) -> DalResult<bool> {
+        let result = sqlx::query(
+            "UPDATE pegouts \
+             SET pegin_id = $2, operator_id = $3, updated_at = now() \
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(pegin_id)
+        .bind(operator_id)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Releases the peg-in UTXO locked for the peg-out, so that another operator can take it.
+    pub async fn unassign_operator(&mut self, id: i32) -> DalResult<bool> {
+        let result = sqlx::query(
+            "UPDATE pegouts \
+             SET pegin_id = NULL, operator_id = NULL, updated_at = now() \
+             WHERE id = $1",
+        )
+        .bind(id)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn set_payout_tx_hash(&mut self, id: i32, payout_tx_hash: &str) -> DalResult<bool> {
+        let result =
+            sqlx::query("UPDATE pegouts SET payout_tx_hash = $2, updated_at = now() WHERE id = $1")
+                .bind(id)
+                .bind(payout_tx_hash)
+                .execute(self.storage.conn())
+                .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn set_kickoff_tx_hash(&mut self, id: i32, kickoff_tx_hash: &str) -> DalResult<bool> {
+        let result = sqlx::query(
+            "UPDATE pegouts SET kickoff_tx_hash = $2, updated_at = now() WHERE id = $1",
+        )
+        .bind(id)
+        .bind(kickoff_tx_hash)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use types::pegout::{
+        NewPegout,
+        PegoutStatus::{self, *},
+        PegoutTransition,
+    };
+
+    use crate::{
+        connection::ConnectionPool,
+        error::DalError,
+        tests::{
+            insert_bridge, new_operator, new_pegin, new_pegout, random_hash, random_public_key,
+        },
+    };
+
+    fn transition(
+        from: PegoutStatus,
+        to: PegoutStatus,
+        deadline_height: Option<i64>,
+    ) -> PegoutTransition {
+        PegoutTransition {
+            from,
+            to,
+            block_height: Some(100),
+            deadline_height,
+        }
+    }
+
+    #[tokio::test]
+    async fn assigning_and_paying_out_pegouts() {
+        let pool = ConnectionPool::test_pool().await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let chain_id = insert_bridge(&mut storage).await;
+        let pegin_id = storage
+            .pegins_dal()
+            .insert_pegin(&new_pegin(chain_id, "bcrt1qsender", 100_000))
+            .await
+            .unwrap();
+        let operator_id = storage
+            .operators_dal()
+            .insert_operator(&new_operator(chain_id, &random_public_key()))
+            .await
+            .unwrap();
+
+        let burn_tx_hash = format!("0x{}", random_hash());
+        let id = storage
+            .pegouts_dal()
+            .insert_pegout(&new_pegout(chain_id, &burn_tx_hash, 100_000))
+            .await
+            .unwrap();
+        let pegout = storage
+            .pegouts_dal()
+            .get_pegout_by_burn_event(chain_id, &burn_tx_hash, 0)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pegout.id, id);
+        assert_eq!((pegout.pegin_id, pegout.operator_id), (None, None));
+
+        // Every burn of a transaction starts a peg-out of its own, but only one.
+        let second_burn = NewPegout {
+            event_idx: 1,
+            ..new_pegout(chain_id, &burn_tx_hash, 100_000)
+        };
+        let other_id = storage
+            .pegouts_dal()
+            .insert_pegout(&second_burn)
+            .await
+            .unwrap();
+        let mut transaction = storage.start_transaction().await.unwrap();
+        let err = transaction
+            .pegouts_dal()
+            .insert_pegout(&second_burn)
+            .await
+            .unwrap_err();
+        assert!(err.is_unique_violation());
+        drop(transaction);
+        let pegouts = storage
+            .pegouts_dal()
+            .get_pegouts_by_burn_tx_hash(&burn_tx_hash)
+            .await
+            .unwrap();
+        assert_eq!(
+            pegouts.iter().map(|pegout| pegout.id).collect::<Vec<_>>(),
+            [id, other_id]
+        );
+
+        assert!(storage
+            .pegouts_dal()
+            .assign_operator(id, pegin_id, operator_id)
+            .await
+            .unwrap());
+        let payout_tx_hash = random_hash();
+        assert!(storage
+            .pegouts_dal()
+            .set_payout_tx_hash(id, &payout_tx_hash)
+            .await
+            .unwrap());
+        let pegout = storage
+            .pegouts_dal()
+            .get_pegout_by_payout_tx_hash(&payout_tx_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pegout.pegin_id, Some(pegin_id));
+        assert_eq!(pegout.operator_id, Some(operator_id));
+
+        // The peg-in can't pay another peg-out, even if both were locked concurrently.
+        let mut transaction = storage.start_transaction().await.unwrap();
+        let err = transaction
+            .pegouts_dal()
+            .assign_operator(other_id, pegin_id, operator_id)
+            .await
+            .unwrap_err();
+        assert!(err.is_unique_violation());
+        drop(transaction);
+        assert!(storage.pegouts_dal().unassign_operator(id).await.unwrap());
+        assert!(storage
+            .pegouts_dal()
+            .assign_operator(other_id, pegin_id, operator_id)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn recording_status_transitions() {
+        let pool = ConnectionPool::test_pool().await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let chain_id = insert_bridge(&mut storage).await;
+        let id = storage
+            .pegouts_dal()
+            .insert_pegout(&new_pegout(chain_id, &random_hash(), 100_000))
+            .await
+            .unwrap();
+
+        let err = storage
+            .pegouts_dal()
+            .update_pegout_status(id, transition(BurnObserved, Reimbursed, None))
+            .await
+            .unwrap_err();
+        assert_matches!(err, DalError::InvalidTransition(_));
+
+        let assign = transition(BurnObserved, OperatorAssigned, Some(110));
+        assert!(storage
+            .pegouts_dal()
+            .update_pegout_status(id, assign)
+            .await
+            .unwrap());
+        // A stale worker can't apply the same transition twice.
+        assert!(!storage
+            .pegouts_dal()
+            .update_pegout_status(id, assign)
+            .await
+            .unwrap());
+
+        let expired = storage
+            .pegouts_dal()
+            .get_expired_pegouts(OperatorAssigned, 110, 10)
+            .await
+            .unwrap();
+        assert!(expired.is_empty());
+        let expired = storage
+            .pegouts_dal()
+            .get_expired_pegouts(OperatorAssigned, 111, 10)
+            .await
+            .unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].deadline_height, Some(110));
+
+        assert!(storage
+            .pegouts_dal()
+            .update_pegout_status(id, transition(OperatorAssigned, BurnObserved, None))
+            .await
+            .unwrap());
+        let transitions = storage
+            .pegouts_dal()
+            .get_status_transitions(id)
+            .await
+            .unwrap();
+        assert_eq!(
+            transitions
+                .iter()
+                .map(|transition| (transition.from_status, transition.to_status))
+                .collect::<Vec<_>>(),
+            [
+                (BurnObserved, OperatorAssigned),
+                (OperatorAssigned, BurnObserved)
+            ]
+        );
+        assert_eq!(transitions[0].block_height, Some(100));
+    }
+}
@@ -0,0 +1,261 @@
+use types::{
+    pegin::PeginStatus,
+    presigned_tx::{
+        PresignedTransaction, PresignedTxType, PRESIGNED_TX_SIGNED, PRESIGNED_TX_UNSIGNED,
+    },
+};
+
+use crate::{error::DalResult, StorageProcessor};
+
+#[derive(Debug)]
+pub struct PresignedTransactionsDal<'a, 'c> {
+    pub(crate) storage: &'a mut StorageProcessor<'c>,
+}
+
+impl PresignedTransactionsDal<'_, '_> {
+    /// Stores an unsigned transaction. Returns `false` if it is already stored, so that
+    /// operators can resubmit their transactions safely.
+    pub async fn insert_presigned_transaction(
+        &mut self,
+        txid: &str,
+        tx_type: PresignedTxType,
+        pegin_id: i32,
+        operator_id: Option<i32>,
+        raw_hex: &str,
+    ) -> DalResult<bool> {
+        let result = sqlx::query(
+            "INSERT INTO presigned_transactions \
+             (txid, tx_type, pegin_id, operator_id, status, raw_hex) \
+             VALUES ($1, $2, $3, $4, $5, $6) \
+             ON CONFLICT (txid) DO NOTHING",
+        )
+        .bind(txid)
+        .bind(tx_type.as_ref())
+        .bind(pegin_id)
+        .bind(operator_id)
+        .bind(PRESIGNED_TX_UNSIGNED)
+        .bind(raw_hex)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Lists the presigned transactions of a peg-in, optionally only those of one operator and
+    /// type.
+    pub async fn get_presigned_transactions(
+        &mut self,
+        pegin_id: i32,
+        operator_id: Option<i32>,
+        tx_type: Option<PresignedTxType>,
+    ) -> DalResult<Vec<PresignedTransaction>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM presigned_transactions \
+             WHERE pegin_id = $1 \
+             AND ($2::INTEGER IS NULL OR operator_id = $2) \
+             AND ($3::TEXT IS NULL OR tx_type = $3) \
+             ORDER BY created_at, txid",
+        )
+        .bind(pegin_id)
+        .bind(operator_id)
+        .bind(tx_type.map(|tx_type| tx_type.as_ref().to_string()))
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    /// Lists the operator transactions still missing committee signatures of peg-ins in
+    /// `pegin_status`, grouped by peg-in and operator.
+    pub async fn get_unsigned_transactions(
+        &mut self,
+        pegin_status: PeginStatus,
+        limit: u32,
+    ) -> DalResult<Vec<PresignedTransaction>> {
+        Ok(sqlx::query_as(
+            "SELECT t.* FROM presigned_transactions t \
+             JOIN pegins p ON p.id = t.pegin_id \
+             WHERE p.status = $1 AND t.status <> $2 AND t.operator_id IS NOT NULL \
+             ORDER BY t.pegin_id, t.operator_id, t.created_at, t.txid \
+             LIMIT $3",
+        )
+        .bind(pegin_status.as_ref())
+        .bind(PRESIGNED_TX_SIGNED)
+        .bind(limit as i64)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    /// Records the signature of a committee member, marking the transaction as signed once
+    /// `committee_size` members signed it. Returns `false` if the member already signed it.
+    pub async fn add_committee_signature(
+        &mut self,
+        txid: &str,
+        public_key: &str,
+        signature: &str,
+        committee_size: i32,
+    ) -> DalResult<bool> {
+        let result = sqlx::query(
+            "WITH inserted AS ( \
+                 INSERT INTO presigned_transaction_signatures (txid, public_key, signature) \
+                 VALUES ($1, $2, $3) \
+                 ON CONFLICT (txid, public_key) DO NOTHING \
+                 RETURNING txid \
+             ) \
+             UPDATE presigned_transactions t \
+             SET signed_committee_cnt = t.signed_committee_cnt + 1, \
+                 status = CASE WHEN t.signed_committee_cnt + 1 >= $4 THEN $5 ELSE t.status END, \
+                 updated_at = now() \
+             FROM inserted WHERE t.txid = inserted.txid",
+        )
+        .bind(txid)
+        .bind(public_key)
+        .bind(signature)
+        .bind(committee_size)
+        .bind(PRESIGNED_TX_SIGNED)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Number of presigned transactions of a peg-in still missing committee signatures.
+    pub async fn count_unsigned(&mut self, pegin_id: i32) -> DalResult<i64> {
+        Ok(sqlx::query_scalar(
+            "SELECT COUNT(*) FROM presigned_transactions WHERE pegin_id = $1 AND status <> $2",
+        )
+        .bind(pegin_id)
+        .bind(PRESIGNED_TX_SIGNED)
+        .fetch_one(self.storage.conn())
+        .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use types::{
+        pegin::PeginStatus,
+        presigned_tx::{PresignedTxType, PRESIGNED_TX_SIGNED, PRESIGNED_TX_UNSIGNED},
+    };
+
+    use crate::{
+        connection::ConnectionPool,
+        tests::{insert_bridge, new_operator, new_pegin, random_hash, random_public_key},
+    };
+
+    #[tokio::test]
+    async fn collecting_committee_signatures() {
+        let pool = ConnectionPool::test_pool().await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let chain_id = insert_bridge(&mut storage).await;
+        let pegin_id = storage
+            .pegins_dal()
+            .insert_pegin(&new_pegin(chain_id, "bcrt1qsender", 1))
+            .await
+            .unwrap();
+
+        // Transactions of a peg-in are listed by txid.
+        let mut txids = [random_hash(), random_hash()];
+        txids.sort();
+        let [aa, bb] = &txids;
+        let dal = &mut storage.presigned_transactions_dal();
+        for txid in [aa, bb] {
+            assert!(dal
+                .insert_presigned_transaction(txid, PresignedTxType::Take, pegin_id, None, "0200")
+                .await
+                .unwrap());
+        }
+        assert!(!dal
+            .insert_presigned_transaction(aa, PresignedTxType::Take, pegin_id, None, "0200")
+            .await
+            .unwrap());
+        assert_eq!(dal.count_unsigned(pegin_id).await.unwrap(), 2);
+
+        // Signing twice with the same key counts once.
+        for (public_key, added) in [("02aa", true), ("02aa", false), ("02bb", true)] {
+            let signed = dal
+                .add_committee_signature(aa, public_key, "sig", 2)
+                .await
+                .unwrap();
+            assert_eq!(signed, added);
+        }
+        let txs = dal
+            .get_presigned_transactions(pegin_id, None, Some(PresignedTxType::Take))
+            .await
+            .unwrap();
+        let statuses: Vec<_> = txs
+            .iter()
+            .map(|tx| {
+                (
+                    tx.txid.as_str(),
+                    tx.signed_committee_cnt,
+                    tx.status.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                (aa.as_str(), 2, PRESIGNED_TX_SIGNED),
+                (bb.as_str(), 0, PRESIGNED_TX_UNSIGNED)
+            ]
+        );
+        assert_eq!(dal.count_unsigned(pegin_id).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn listing_unsigned_transactions_of_confirmed_pegins() {
+        let pool = ConnectionPool::test_pool().await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let chain_id = insert_bridge(&mut storage).await;
+        let operator_id = storage
+            .operators_dal()
+            .insert_operator(&new_operator(chain_id, &random_public_key()))
+            .await
+            .unwrap();
+        let mut pegin_ids = vec![];
+        for sender in ["bcrt1qconfirmed", "bcrt1qcreated"] {
+            let pegin_id = storage
+                .pegins_dal()
+                .insert_pegin(&new_pegin(chain_id, sender, 1))
+                .await
+                .unwrap();
+            pegin_ids.push(pegin_id);
+        }
+        for (from, to) in [
+            (PeginStatus::Created, PeginStatus::Deposited),
+            (PeginStatus::Deposited, PeginStatus::Confirmed),
+        ] {
+            storage
+                .pegins_dal()
+                .update_pegin_status(pegin_ids[0], from, to)
+                .await
+                .unwrap();
+        }
+
+        let [aa, bb, cc, dd] = [(); 4].map(|()| random_hash());
+        let dal = &mut storage.presigned_transactions_dal();
+        for (txid, pegin_id, operator_id) in [
+            (&aa, pegin_ids[0], Some(operator_id)),
+            (&bb, pegin_ids[0], Some(operator_id)),
+            (&cc, pegin_ids[0], None),
+            (&dd, pegin_ids[1], Some(operator_id)),
+        ] {
+            dal.insert_presigned_transaction(
+                txid,
+                PresignedTxType::Take,
+                pegin_id,
+                operator_id,
+                "0200",
+            )
+            .await
+            .unwrap();
+        }
+        dal.add_committee_signature(&bb, "02aa", "sig", 1)
+            .await
+            .unwrap();
+
+        let txs = dal
+            .get_unsigned_transactions(PeginStatus::Confirmed, 10)
+            .await
+            .unwrap();
+        let txids: Vec<_> = txs.iter().map(|tx| tx.txid.as_str()).collect();
+        assert_eq!(txids, [aa.as_str()]);
+    }
+}
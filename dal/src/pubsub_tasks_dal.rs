@@ -0,0 +1,113 @@
+use sqlx::types::Json;
+use types::pubsub::{StoredTask, Task};
+
+use crate::{error::DalResult, StorageProcessor};
+
+#[derive(Debug)]
+pub struct PubSubTasksDal<'a, 'c> {
+    pub(crate) storage: &'a mut StorageProcessor<'c>,
+}
+
+impl PubSubTasksDal<'_, '_> {
+    /// Stores tasks pushed to the subscribers of `topic` and returns their ids, in order.
+    pub async fn insert_tasks(&mut self, topic: &str, tasks: &[Task]) -> DalResult<Vec<i64>> {
+        let mut ids = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            let id = sqlx::query_scalar(
+                "INSERT INTO pubsub_tasks (topic, payload) VALUES ($1, $2) RETURNING id",
+            )
+            .bind(topic)
+            .bind(Json(task))
+            .fetch_one(self.storage.conn())
+            .await?;
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    /// Lists the tasks of `topic` stored after `after_id`, in ascending id order.
+    pub async fn get_tasks_after(
+        &mut self,
+        topic: &str,
+        after_id: i64,
+        limit: u32,
+    ) -> DalResult<Vec<StoredTask>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM pubsub_tasks WHERE topic = $1 AND id > $2 ORDER BY id LIMIT $3",
+        )
+        .bind(topic)
+        .bind(after_id)
+        .bind(limit as i64)
+        .fetch_all(self.storage.conn())
+        .await?)
+    }
+
+    /// Lists the latest tasks of `topic`, in ascending id order.
+    pub async fn get_latest_tasks(
+        &mut self,
+        topic: &str,
+        limit: u32,
+    ) -> DalResult<Vec<StoredTask>> {
+        let mut tasks: Vec<StoredTask> =
+            sqlx::query_as("SELECT * FROM pubsub_tasks WHERE topic = $1 ORDER BY id DESC LIMIT $2")
+                .bind(topic)
+                .bind(limit as i64)
+                .fetch_all(self.storage.conn())
+                .await?;
+        tasks.reverse();
+        Ok(tasks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use types::pubsub::{ChallengerTask, CommitteeTask, Task};
+
+    use crate::connection::ConnectionPool;
+
+    fn accept_pegin(pegin_id: i32) -> Task {
+        Task::Committee(CommitteeTask::AcceptPegin {
+            pegin_id,
+            target_chain_id: 31337,
+            pegin_tx_hash: format!("pegin{pegin_id}"),
+            amount: 100_000,
+        })
+    }
+
+    #[tokio::test]
+    async fn replaying_tasks_after_a_cursor() {
+        let pool = ConnectionPool::test_pool().await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let dal = &mut storage.pubsub_tasks_dal();
+
+        let tasks: Vec<_> = (1..=3).map(accept_pegin).collect();
+        let ids = dal.insert_tasks("committeeTasks", &tasks).await.unwrap();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        let challenge = Task::Challenger(ChallengerTask::VerifyKickoff {
+            pegout_id: 1,
+            operator_id: 1,
+            kickoff_tx_hash: "kickoff".to_string(),
+            deadline_height: 150,
+        });
+        dal.insert_tasks("challengerTasks", &[challenge])
+            .await
+            .unwrap();
+
+        let replayed = dal
+            .get_tasks_after("committeeTasks", ids[0], 10)
+            .await
+            .unwrap();
+        let replayed: Vec<_> = replayed
+            .into_iter()
+            .map(|stored| (stored.id, stored.payload.0))
+            .collect();
+        assert_eq!(
+            replayed,
+            [(ids[1], tasks[1].clone()), (ids[2], tasks[2].clone())]
+        );
+
+        let latest = dal.get_latest_tasks("committeeTasks", 2).await.unwrap();
+        let latest_ids: Vec<_> = latest.iter().map(|stored| stored.id).collect();
+        assert_eq!(latest_ids, ids[1..]);
+    }
+}
@@ -0,0 +1,79 @@
+use types::sync_cursor::SyncCursor;
+
+use crate::{error::DalResult, StorageProcessor};
+
+#[derive(Debug)]
+pub struct SyncCursorsDal<'a, 'c> {
+    pub(crate) storage: &'a mut StorageProcessor<'c>,
+}
+
+impl SyncCursorsDal<'_, '_> {
+    pub async fn get_cursor(&mut self, cursor_name: &str) -> DalResult<Option<SyncCursor>> {
+        Ok(
+            sqlx::query_as("SELECT * FROM sync_cursors WHERE cursor_name = $1")
+                .bind(cursor_name)
+                .fetch_optional(self.storage.conn())
+                .await?,
+        )
+    }
+
+    pub async fn set_cursor(
+        &mut self,
+        cursor_name: &str,
+        block_number: i64,
+        block_hash: &str,
+    ) -> DalResult<()> {
+        sqlx::query(
+            "INSERT INTO sync_cursors (cursor_name, block_number, block_hash) \
+             VALUES ($1, $2, $3) \
+             ON CONFLICT (cursor_name) DO UPDATE SET \
+             block_number = EXCLUDED.block_number, block_hash = EXCLUDED.block_hash, \
+             updated_at = now()",
+        )
+        .bind(cursor_name)
+        .bind(block_number)
+        .bind(block_hash)
+        .execute(self.storage.conn())
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{connection::ConnectionPool, tests::random_hash};
+
+    #[tokio::test]
+    async fn moving_cursors() {
+        let pool = ConnectionPool::test_pool().await;
+        let mut storage = pool.access_storage().await.unwrap();
+        let name = format!("bitcoin-{}", random_hash());
+        assert!(storage
+            .sync_cursors_dal()
+            .get_cursor(&name)
+            .await
+            .unwrap()
+            .is_none());
+
+        storage
+            .sync_cursors_dal()
+            .set_cursor(&name, 100, "aa")
+            .await
+            .unwrap();
+        storage
+            .sync_cursors_dal()
+            .set_cursor(&name, 101, "bb")
+            .await
+            .unwrap();
+        let cursor = storage
+            .sync_cursors_dal()
+            .get_cursor(&name)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (cursor.block_number, cursor.block_hash.as_str()),
+            (101, "bb")
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! Fixtures shared by the DAL tests. Tests need a Postgres instance reachable at
+//! `TEST_DATABASE_URL` (see `ConnectionPool::test_pool`).
+//!
+//! Tests run concurrently, and an uncommitted row of one test blocks the others from inserting
+//! the same unique value, so fixtures draw chain ids, hashes and keys at random.
+
+use chrono::Utc;
+use rand::Rng;
+use types::{
+    operator::NewOperator,
+    pegin::{NewPegin, PeginStatus},
+    pegout::{NewPegout, PegoutStatus},
+};
+
+use crate::StorageProcessor;
+
+/// Returns a random 32-byte hash in hex.
+pub(crate) fn random_hash() -> String {
+    let [high, low]: [u128; 2] = rand::random();
+    format!("{high:032x}{low:032x}")
+}
+
+/// Returns a random compressed public key in hex. It's not necessarily a valid curve point.
+pub(crate) fn random_public_key() -> String {
+    format!("02{}", random_hash())
+}
+
+/// Inserts a bridge on a random chain, returning the chain id.
+pub(crate) async fn insert_bridge(storage: &mut StorageProcessor<'_>) -> i32 {
+    let chain_id = rand::thread_rng().gen_range(1..i32::MAX);
+    sqlx::query(
+        "INSERT INTO bridges \
+         (chain_id, chain_name, operator_manager_address, assertion_taproot_address, status) \
+         VALUES ($1, 'test', '0x0000000000000000000000000000000000000000', 'bcrt1q', 'active')",
+    )
+    .bind(chain_id)
+    .execute(storage.conn())
+    .await
+    .unwrap();
+    chain_id
+}
+
+pub(crate) fn new_pegin(chain_id: i32, sender_address: &str, amount: i64) -> NewPegin {
+    NewPegin {
+        target_chain_id: chain_id,
+        public_key: "02".repeat(33),
+        sender_address: sender_address.to_string(),
+        status: PeginStatus::Created,
+        receive_address: "0x0000000000000000000000000000000000000001".to_string(),
+        amount,
+    }
+}
+
+pub(crate) fn new_pegout(chain_id: i32, burn_tx_hash: &str, amount: i64) -> NewPegout {
+    NewPegout {
+        chain_id,
+        burn_tx_hash: burn_tx_hash.to_string(),
+        event_idx: 0,
+        sender_address: "0x0000000000000000000000000000000000000001".to_string(),
+        receive_address: "bcrt1qtest".to_string(),
+        amount,
+        status: PegoutStatus::BurnObserved,
+    }
+}
+
+pub(crate) fn new_operator(chain_id: i32, public_key: &str) -> NewOperator {
+    NewOperator {
+        address: "0x0000000000000000000000000000000000000002".to_string(),
+        chain_id,
+        public_key: public_key.to_string(),
+        fee: 1_000,
+        status: "active".to_string(),
+        stake_amount: 10_000_000,
+        max_support_amount: 100_000_000,
+        min_support_amount: 100_000,
+        max_pegin_cnt: 10,
+        max_pegout_cnt: 10,
+        register_at: Utc::now().naive_utc(),
+    }
+}
@@ -1,7 +1,196 @@
+//! Errors of the bridge JSON-RPC API.
+//!
+//! Every error carries a stable code that clients can branch on, and most carry a `data` object
+//! with the details of the failure:
+//!
+//! | Code     | Variant                                   | `data`                         |
+//! |----------|-------------------------------------------|--------------------------------|
+//! | `-32603` | `InternalError`                           | none                           |
+//! | `-32602` | `InvalidParams`                           | `{ reason }`                   |
+//! | `-32001` | `NotFound`                                | `{ resource, id }`             |
+//! | `-32002` | `Unavailable`                             | `{ upstream }`                 |
+//! | `-32003` | `TransactionRejected`                     | `{ reason }`                   |
+//! | `-32005` | `RateLimited`                             | `{ retry_after_ms }`           |
+//! | `-32005` | `LimitExceeded`                           | `{ resource, limit }`          |
+//! | `-32010` | `UnsupportedChain`                        | `{ chain_id }`                 |
+//! | `-32011` | `Conflict`                                | `{ reason }`                   |
+//! | `-32011` | `InvalidTransition`                       | `{ entity, from, to }`         |
+//! | `-32012` | `ChallengeError`                          | `{ reason }`                   |
+//! | `-32013` | `Unauthorized`                            | `{ reason }`                   |
+//!
+//! Codes `-32001` to `-32005` follow EIP-1474; codes from `-32010` on are specific to the bridge.
+
+use serde_json::{json, Value};
 use thiserror::Error;
+use types::error::InvalidTransition;
+
+pub mod codes {
+    pub const INTERNAL_ERROR: i32 = -32603;
+    pub const INVALID_PARAMS: i32 = -32602;
+    pub const NOT_FOUND: i32 = -32001;
+    pub const UNAVAILABLE: i32 = -32002;
+    pub const TRANSACTION_REJECTED: i32 = -32003;
+    pub const RATE_LIMITED: i32 = -32005;
+    pub const UNSUPPORTED_CHAIN: i32 = -32010;
+    pub const CONFLICT: i32 = -32011;
+    pub const CHALLENGE_ERROR: i32 = -32012;
+    pub const UNAUTHORIZED: i32 = -32013;
+}
 
 #[derive(Debug, Error)]
 pub enum Web3Error {
     #[error("Internal error")]
     InternalError,
+    #[error("Invalid params: {0}")]
+    InvalidParams(String),
+    #[error("{resource} {id} not found")]
+    NotFound { resource: &'static str, id: String },
+    /// A service the node depends on, such as the database or the Bitcoin node, can't be reached.
+    #[error("{upstream} is unavailable")]
+    Unavailable { upstream: &'static str },
+    /// The Bitcoin node refused to relay a submitted transaction.
+    #[error("Transaction rejected: {0}")]
+    TransactionRejected(String),
+    #[error("Too many requests")]
+    RateLimited { retry_after_ms: Option<u64> },
+    /// The caller holds as many connections or subscriptions as it is allowed to.
+    #[error("Too many {resource}, the limit is {limit}")]
+    LimitExceeded {
+        resource: &'static str,
+        limit: usize,
+    },
+    #[error("Chain {0} is not supported")]
+    UnsupportedChain(i32),
+    /// The request is valid, but not in the current state of the entities it refers to.
+    #[error("{0}")]
+    Conflict(String),
+    #[error(transparent)]
+    InvalidTransition(#[from] InvalidTransition),
+    #[error("Challenge error: {0}")]
+    ChallengeError(String),
+    /// The caller didn't prove it holds a key the request requires.
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+}
+
+impl Web3Error {
+    pub fn code(&self) -> i32 {
+        match self {
+            Self::InternalError => codes::INTERNAL_ERROR,
+            Self::InvalidParams(_) => codes::INVALID_PARAMS,
+            Self::NotFound { .. } => codes::NOT_FOUND,
+            Self::Unavailable { .. } => codes::UNAVAILABLE,
+            Self::TransactionRejected(_) => codes::TRANSACTION_REJECTED,
+            Self::RateLimited { .. } | Self::LimitExceeded { .. } => codes::RATE_LIMITED,
+            Self::UnsupportedChain(_) => codes::UNSUPPORTED_CHAIN,
+            Self::Conflict(_) | Self::InvalidTransition(_) => codes::CONFLICT,
+            Self::ChallengeError(_) => codes::CHALLENGE_ERROR,
+            Self::Unauthorized(_) => codes::UNAUTHORIZED,
+        }
+    }
+
+    /// Structured details of the error, sent as the `data` of the JSON-RPC error.
+    pub fn data(&self) -> Option<Value> {
+        match self {
+            Self::InternalError => None,
+            Self::InvalidParams(reason)
+            | Self::TransactionRejected(reason)
+            | Self::Conflict(reason)
+            | Self::ChallengeError(reason)
+            | Self::Unauthorized(reason) => Some(json!({ "reason": reason })),
+            Self::NotFound { resource, id } => Some(json!({ "resource": resource, "id": id })),
+            Self::Unavailable { upstream } => Some(json!({ "upstream": upstream })),
+            Self::RateLimited { retry_after_ms } => {
+                Some(json!({ "retry_after_ms": retry_after_ms }))
+            }
+            Self::LimitExceeded { resource, limit } => {
+                Some(json!({ "resource": resource, "limit": limit }))
+            }
+            Self::UnsupportedChain(chain_id) => Some(json!({ "chain_id": chain_id })),
+            Self::InvalidTransition(err) => Some(json!({
+                "entity": err.entity,
+                "from": err.from,
+                "to": err.to,
+            })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_and_data_are_stable() {
+        let cases = [
+            (Web3Error::InternalError, -32603, Value::Null),
+            (
+                Web3Error::InvalidParams("bad amount".to_string()),
+                -32602,
+                json!({ "reason": "bad amount" }),
+            ),
+            (
+                Web3Error::NotFound {
+                    resource: "pegin",
+                    id: "3".to_string(),
+                },
+                -32001,
+                json!({ "resource": "pegin", "id": "3" }),
+            ),
+            (
+                Web3Error::Unavailable {
+                    upstream: "database",
+                },
+                -32002,
+                json!({ "upstream": "database" }),
+            ),
+            (
+                Web3Error::RateLimited {
+                    retry_after_ms: Some(250),
+                },
+                -32005,
+                json!({ "retry_after_ms": 250 }),
+            ),
+            (
+                Web3Error::LimitExceeded {
+                    resource: "subscriptions",
+                    limit: 8,
+                },
+                -32005,
+                json!({ "resource": "subscriptions", "limit": 8 }),
+            ),
+            (
+                Web3Error::Unauthorized("unknown nonce".to_string()),
+                -32013,
+                json!({ "reason": "unknown nonce" }),
+            ),
+            (
+                Web3Error::UnsupportedChain(1),
+                -32010,
+                json!({ "chain_id": 1 }),
+            ),
+            (
+                InvalidTransition {
+                    entity: "peg-out",
+                    from: "burn_observed".to_string(),
+                    to: "reimbursed".to_string(),
+                }
+                .into(),
+                -32011,
+                json!({ "entity": "peg-out", "from": "burn_observed", "to": "reimbursed" }),
+            ),
+        ];
+        for (err, code, data) in cases {
+            assert_eq!(err.code(), code, "{err}");
+            assert_eq!(err.data().unwrap_or_default(), data, "{err}");
+        }
+        assert_eq!(
+            Web3Error::NotFound {
+                resource: "pegin",
+                id: "3".to_string()
+            }
+            .to_string(),
+            "pegin 3 not found"
+        );
+    }
 }
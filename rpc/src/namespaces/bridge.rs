@@ -0,0 +1,184 @@
+use bitcoin::ScriptBuf;
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+use types::{
+    challenger::{
+        ChallengeStatus, QueryChallengeDataReponse, QueryChallengeDataRequest,
+        StartChallengeResponse,
+    },
+    operator::{Operator, OperatorFilter},
+    pegin::{PeginDetails, PeginEventDetails, PeginOperation},
+    pegout::{
+        PegoutDetail, PegoutEventDetail, PegoutInfo, PegoutOperationRangeResponse,
+        PegoutOperationRangerRequest,
+    },
+    pubsub::{
+        ChallengeCommitteeTaskResponse, OperatorPegoutTxRequest, PeginCommitteeTaskResponse,
+        PeginOperatorTaskResponse, PeginReceivedRequest, PegoutOperatorTaskResponse,
+        PegoutReceivedRequest, SignedRequest,
+    },
+    rpc::{
+        LPPeginRequest, LPPegoutRequest, PeginCreateRequest, PeginCreateResponse,
+        PeginEventRangeRequest, PeginRequest, PeginTakeTxMsgReponse, PeginTakeTxMsgRequest,
+        PegoutRequest, PegouttEventRangeRequest,
+    },
+};
+
+#[cfg_attr(
+    all(feature = "client", feature = "server"),
+    rpc(server, client, namespace = "bridge")
+)]
+#[cfg_attr(
+    all(feature = "client", not(feature = "server")),
+    rpc(client, namespace = "bridge")
+)]
+#[cfg_attr(
+    all(not(feature = "client"), feature = "server"),
+    rpc(server, namespace = "bridge")
+)]
+pub trait BridgeNamespace {
+    /// N-of-N script of the committee `pubkey` is a member of.
+    #[method(name = "getPeginMultiSigScript")]
+    async fn get_pegin_multi_sig_script(&self, pubkey: &str) -> RpcResult<ScriptBuf>;
+
+    #[method(name = "queryOperators")]
+    async fn query_operators(&self, filter: OperatorFilter) -> RpcResult<Vec<Operator>>;
+
+    #[method(name = "queryPeginTakeTxSignMsg")]
+    async fn query_pegin_take_tx_sign_msg(
+        &self,
+        request: PeginTakeTxMsgRequest,
+    ) -> RpcResult<PeginTakeTxMsgReponse>;
+
+    #[method(name = "createPegin")]
+    async fn create_pegin(
+        &self,
+        pegin_create_req: PeginCreateRequest,
+    ) -> RpcResult<PeginCreateResponse>;
+
+    /// Broadcasts the deposit of a created peg-in, returning the peg-in id.
+    #[method(name = "submitPegin")]
+    async fn submit_pegin(&self, pegin_req: PeginRequest) -> RpcResult<u32>;
+
+    #[method(name = "submitLpPegin")]
+    async fn submit_lp_pegin(&self, pegin_req: LPPeginRequest) -> RpcResult<u32>;
+
+    #[method(name = "queryPeginDetails")]
+    async fn query_pegin_details(&self, pegin_id: u32) -> RpcResult<PeginDetails>;
+
+    #[method(name = "queryPegoutDetail")]
+    async fn query_pegout_detail(&self, pegout_id: u32) -> RpcResult<PegoutDetail>;
+
+    /// A transaction may burn several times; `event_idx` defaults to its first burn.
+    #[method(name = "getPegoutDetailByBurnTx")]
+    async fn get_pegout_detail_by_burn_tx(
+        &self,
+        burn_tx_hash: String,
+        event_idx: Option<u32>,
+    ) -> RpcResult<PegoutDetail>;
+
+    #[method(name = "getPegoutHistory")]
+    async fn get_pegout_history(&self, request: PegoutRequest) -> RpcResult<Vec<PegoutInfo>>;
+
+    #[method(name = "getPeginHistory")]
+    async fn get_pegin_history(&self, address: &str) -> RpcResult<Vec<PeginDetails>>;
+
+    /// Up to `n` distinct amounts of at least `amount` that a peg-out can currently be paid
+    /// from, smallest first.
+    #[method(name = "getAvaliablePegoutAmount")]
+    async fn get_avaliable_pegout_amount(&self, amount: u64, n: u32) -> RpcResult<Vec<u64>>;
+
+    #[method(name = "queryLpPendingPegouts")]
+    async fn query_lp_pending_pegouts(&self) -> RpcResult<Vec<PegoutInfo>>;
+
+    /// Assigns the peg-out to the operator and locks a peg-in UTXO to reimburse it from.
+    /// Returns `None` if the peg-out is taken or no UTXO is available to the operator.
+    #[method(name = "tryLockPeginUtxo")]
+    async fn try_lock_pegin_utxo(
+        &self,
+        request: SignedRequest<LPPegoutRequest>,
+    ) -> RpcResult<Option<PeginOperation>>;
+
+    #[method(name = "queryPeginEventsByRange")]
+    async fn query_pegin_events_by_range(
+        &self,
+        request: PeginEventRangeRequest,
+    ) -> RpcResult<Vec<PeginEventDetails>>;
+
+    #[method(name = "queryPegoutEventsByRange")]
+    async fn query_pegout_events_by_range(
+        &self,
+        request: PegouttEventRangeRequest,
+    ) -> RpcResult<Vec<PegoutEventDetail>>;
+
+    #[method(name = "queryPegoutOperations")]
+    async fn query_pegout_operations(
+        &self,
+        request: PegoutOperationRangerRequest,
+    ) -> RpcResult<Vec<PegoutOperationRangeResponse>>;
+
+    #[method(name = "queryChallengeData")]
+    async fn query_challenge_data(
+        &self,
+        request: QueryChallengeDataRequest,
+    ) -> RpcResult<QueryChallengeDataReponse>;
+
+    /// Records a broadcast challenge, returning the challenge id.
+    #[method(name = "startChallenge")]
+    async fn start_challenge(&self, request: StartChallengeResponse) -> RpcResult<u32>;
+
+    #[method(name = "queryChallengeStatus")]
+    async fn query_challenge_status(&self, challenge_id: u32) -> RpcResult<ChallengeStatus>;
+
+    #[method(name = "submitPeginOperatorPresignedTransactions")]
+    async fn submit_pegin_operator_presigned_transactions(
+        &self,
+        presigned_txs: SignedRequest<PeginOperatorTaskResponse>,
+    ) -> RpcResult<()>;
+
+    #[method(name = "submitPegoutOperatorResponse")]
+    async fn submit_pegout_operator_response(
+        &self,
+        pegout_response: SignedRequest<PegoutOperatorTaskResponse>,
+    ) -> RpcResult<()>;
+
+    #[method(name = "submitOperatorPegoutTx")]
+    async fn submit_operator_pegout_tx(
+        &self,
+        pegout_request: SignedRequest<OperatorPegoutTxRequest>,
+    ) -> RpcResult<()>;
+
+    #[method(name = "submitPegoutReceived")]
+    async fn submit_pegout_received(
+        &self,
+        request: SignedRequest<PegoutReceivedRequest>,
+    ) -> RpcResult<()>;
+
+    #[method(name = "submitPeginReceived")]
+    async fn submit_pegin_received(
+        &self,
+        request: SignedRequest<PeginReceivedRequest>,
+    ) -> RpcResult<()>;
+
+    #[method(name = "submitPeginCommitteePresignedTransactions")]
+    async fn submit_pegin_committee_presigned_transactions(
+        &self,
+        presigned_txs: PeginCommitteeTaskResponse,
+    ) -> RpcResult<()>;
+
+    #[method(name = "submitChallengeCommitteePresignedTransactions")]
+    async fn submit_challenge_committee_presigned_transactions(
+        &self,
+        response: ChallengeCommitteeTaskResponse,
+    ) -> RpcResult<()>;
+}
+
+/// Issues the nonces operators sign requests with. Apart from [`BridgeNamespace`], since it needs
+/// the connection of a call, which client-only builds can't declare.
+#[cfg(feature = "server")]
+#[rpc(server, namespace = "bridge")]
+pub trait NonceNamespace {
+    /// Returns a single-use nonce to sign an operator request with, valid for a minute. Each
+    /// client IP address can hold a few unused nonces at a time.
+    #[method(name = "getNonce", raw_method)]
+    async fn get_nonce(&self) -> RpcResult<String>;
+}
@@ -0,0 +1,23 @@
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+use types::bridge::Bridge;
+
+#[cfg_attr(
+    all(feature = "client", feature = "server"),
+    rpc(server, client, namespace = "chain")
+)]
+#[cfg_attr(
+    all(feature = "client", not(feature = "server")),
+    rpc(client, namespace = "chain")
+)]
+#[cfg_attr(
+    all(not(feature = "client"), feature = "server"),
+    rpc(server, namespace = "chain")
+)]
+pub trait ChainNamespace {
+    /// Bridges of all target chains served by the node.
+    #[method(name = "supportedChains")]
+    async fn supported_chains(&self) -> RpcResult<Vec<Bridge>>;
+
+    #[method(name = "getBridge")]
+    async fn get_bridge(&self, chain_id: i32) -> RpcResult<Bridge>;
+}
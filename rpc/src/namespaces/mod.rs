@@ -1,2 +1,4 @@
+pub mod bridge;
+pub mod chain;
 pub mod pubsub;
 pub mod test;
@@ -2,11 +2,30 @@ use jsonrpsee::{
     core::{RpcResult, SubscriptionResult},
     proc_macros::rpc,
 };
+use types::pubsub::SubscriptionAuth;
 
 #[rpc(server, namespace = "test")]
 pub trait TestPubSub {
+    /// Subscribes to `sub_type`: `bitcoinReorgs`, or the tasks of a role in `committeeTasks`,
+    /// `operatorTasks` and `challengerTasks`. Task subscribers first receive the tasks already
+    /// pending, then new ones as they come up. Passing the `id` of the last task received as
+    /// `from_cursor` replays the tasks sent after it instead, e.g. after reconnecting.
+    ///
+    /// `committeeTasks` and `operatorTasks` require `auth`: a nonce from `getChallenge` signed
+    /// by a registered committee member or operator key. Only the tasks of that member's chain
+    /// or that operator are delivered.
     #[subscription(name = "subscribe" => "subscription", unsubscribe = "unsubscribe", item = PubSubResult)]
-    async fn subscribe(&self, sub_type: String) -> SubscriptionResult;
+    async fn subscribe(
+        &self,
+        sub_type: String,
+        from_cursor: Option<i64>,
+        auth: Option<SubscriptionAuth>,
+    ) -> SubscriptionResult;
+
+    /// Returns a single-use nonce to sign in `subscribe`, valid for a minute. Each client IP
+    /// address can hold a few unused nonces at a time.
+    #[method(name = "getChallenge", raw_method)]
+    async fn get_challenge(&self) -> RpcResult<String>;
 
     #[method(name = "sendMessage")]
     async fn send_message(&self, message: String) -> RpcResult<()>;
@@ -0,0 +1,46 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// `tx_type` of transactions paying a bridge's taproot address.
+pub const PEGIN_DEPOSIT_TX_TYPE: &str = "pegin_deposit";
+/// `status` of transactions with fewer confirmations than required.
+pub const BITCOIN_TX_PENDING: &str = "pending";
+pub const BITCOIN_TX_CONFIRMED: &str = "confirmed";
+
+/// A row of the `bitcoin_transactions` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BitcoinTransaction {
+    pub tx_hash: String,
+    pub tx_type: String,
+    pub status: String,
+    /// Hex-encoded transaction.
+    pub data: String,
+    pub extra_data: Option<String>,
+    pub block_height: Option<i64>,
+    pub block_hash: Option<String>,
+    pub confirmations: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Fields required to insert a new Bitcoin transaction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewBitcoinTransaction {
+    pub tx_hash: String,
+    pub tx_type: String,
+    pub status: String,
+    pub data: String,
+    pub extra_data: Option<String>,
+    pub block_height: Option<i64>,
+    pub block_hash: Option<String>,
+    pub confirmations: i32,
+}
+
+/// Output of a peg-in deposit transaction paying a bridge, stored as a JSON array in
+/// `extra_data` of the transaction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeginDeposit {
+    pub chain_id: i32,
+    pub vout: u32,
+    pub amount: i64,
+}
@@ -0,0 +1,41 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// A row of the `bridges` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Bridge {
+    pub id: i32,
+    pub chain_id: i32,
+    pub chain_name: String,
+    pub operator_manager_address: String,
+    /// Address peg-in deposits to this bridge are paid to.
+    pub assertion_taproot_address: String,
+    pub status: String,
+    /// Contract emitting mint and burn events, once deployed.
+    pub bridge_contract_address: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Fields required to insert a new bridge.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewBridge {
+    pub chain_id: i32,
+    pub chain_name: String,
+    pub operator_manager_address: String,
+    pub assertion_taproot_address: String,
+    pub status: String,
+    pub bridge_contract_address: Option<String>,
+}
+
+/// A row of the `committees` table: a member of the committee guarding the deposits of a bridge.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CommitteeMember {
+    pub bridge_id: i32,
+    pub public_key: String,
+    pub address: String,
+    /// Position of the member's key in the committee scripts.
+    pub index: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
@@ -0,0 +1,122 @@
+use bitcoin::Network;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, Display, EnumIter, EnumString};
+
+use crate::{
+    error::ValidationError,
+    validation::{parse_bitcoin_address, parse_txid},
+};
+
+/// Outcome of a challenge, stored in `challenges.status`.
+///
+/// A challenge is `Submitted` once the challenger reports its challenge transaction. It succeeds,
+/// slashing the operator, once that transaction spends the kickoff within the challenge window,
+/// and fails when the window closes without it.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    AsRefStr,
+    Display,
+    EnumIter,
+    EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ChallengeStatus {
+    Submitted,
+    Succeeded,
+    Failed,
+}
+
+impl TryFrom<String> for ChallengeStatus {
+    type Error = strum::ParseError;
+
+    fn try_from(status: String) -> Result<Self, Self::Error> {
+        status.parse()
+    }
+}
+
+/// A row of the `challenges` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Challenge {
+    pub id: i32,
+    pub pegout_id: i32,
+    pub challenger_address: String,
+    pub challenge_tx_hash: String,
+    #[sqlx(try_from = "String")]
+    pub status: ChallengeStatus,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryChallengeDataRequest {
+    pub pegout_id: i32,
+}
+
+/// What a challenger needs to challenge the kickoff of a peg-out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryChallengeDataReponse {
+    pub pegout_id: i32,
+    pub operator_id: i32,
+    pub kickoff_tx_hash: String,
+    /// Challenge transaction presigned by the committee, spending the kickoff.
+    pub raw_challenge_tx: String,
+    /// Bitcoin height the challenge window closes at.
+    pub deadline_height: i64,
+}
+
+/// A challenge transaction broadcast by a challenger.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StartChallengeResponse {
+    pub pegout_id: i32,
+    pub challenger_address: String,
+    pub challenge_tx_hash: String,
+}
+
+impl StartChallengeResponse {
+    /// Checks that the challenger is rewarded on `network` and reported a Bitcoin transaction.
+    pub fn validate(&self, network: Network) -> Result<(), ValidationError> {
+        parse_bitcoin_address(&self.challenger_address, network)?;
+        parse_txid(&self.challenge_tx_hash)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validating_challenges() {
+        let challenge = StartChallengeResponse {
+            pegout_id: 1,
+            challenger_address: "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080".to_string(),
+            challenge_tx_hash: "ab".repeat(32),
+        };
+        assert_eq!(challenge.validate(Network::Regtest), Ok(()));
+        assert!(challenge.validate(Network::Bitcoin).is_err());
+        let evm_hash = StartChallengeResponse {
+            challenge_tx_hash: format!("0x{}", "ab".repeat(32)),
+            ..challenge
+        };
+        assert!(evm_hash.validate(Network::Regtest).is_err());
+
+        let json = serde_json::to_string(&evm_hash).unwrap();
+        assert_eq!(
+            serde_json::from_str::<StartChallengeResponse>(&json).unwrap(),
+            evm_hash
+        );
+        assert_eq!(
+            serde_json::to_value(ChallengeStatus::Succeeded).unwrap(),
+            "succeeded"
+        );
+    }
+}
@@ -21,3 +21,34 @@ pub enum ProjectError {
     #[error("invalid params with {0}")]
     InvalidParams(String),
 }
+
+/// A status change that the lifecycle of `entity` doesn't allow.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{entity} can't move from `{from}` to `{to}`")]
+pub struct InvalidTransition {
+    pub entity: &'static str,
+    pub from: String,
+    pub to: String,
+}
+
+/// A field of a request or a new row that the bridge can't accept.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationError {
+    #[error("amount {amount} is outside of [{min}, {max}]")]
+    AmountOutOfRange { amount: i64, min: i64, max: i64 },
+    #[error("`{address}` is not a {network} address")]
+    InvalidBitcoinAddress {
+        address: String,
+        network: bitcoin::Network,
+    },
+    #[error("`{0}` is not an EVM address")]
+    InvalidEvmAddress(String),
+    #[error("`{0}` is not a public key")]
+    InvalidPublicKey(String),
+    #[error("`{0}` is not a transaction hash")]
+    InvalidTxHash(String),
+    #[error("invalid signature by {0}")]
+    InvalidSignature(String),
+    #[error("{0}")]
+    Inconsistent(String),
+}
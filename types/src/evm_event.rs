@@ -0,0 +1,98 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// `status` of events that no worker has acted upon yet.
+pub const EVM_EVENT_UNPROCESSED: &str = "unprocessed";
+/// `status` of events a worker has acted upon.
+pub const EVM_EVENT_PROCESSED: &str = "processed";
+/// `status` of events no worker can act upon, e.g. burns paying out to an invalid address.
+pub const EVM_EVENT_INVALID: &str = "invalid";
+/// `status` of transactions indexed at the configured confirmation depth.
+pub const EVM_TX_CONFIRMED: &str = "confirmed";
+
+/// Decoded event of a bridge or operator manager contract, stored as JSON in `events.data`.
+/// Amounts are decimal strings, as they don't fit into 64 bits in general.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BridgeEvent {
+    Mint {
+        to: String,
+        amount: String,
+        pegin_tx_hash: String,
+    },
+    Burn {
+        from: String,
+        amount: String,
+        btc_address: String,
+    },
+    OperatorRegistered {
+        operator: String,
+        public_key: String,
+    },
+    OperatorRemoved {
+        operator: String,
+    },
+}
+
+impl BridgeEvent {
+    /// Value of `events.event_type` for this event.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Self::Mint { .. } => "mint",
+            Self::Burn { .. } => "burn",
+            Self::OperatorRegistered { .. } => "operator_registered",
+            Self::OperatorRemoved { .. } => "operator_removed",
+        }
+    }
+}
+
+/// A row of the `events` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct EvmEvent {
+    pub chain_id: i32,
+    pub tx_hash: String,
+    /// Index of the log in its block.
+    pub event_idx: i32,
+    pub event_type: String,
+    pub data: String,
+    pub status: String,
+    pub block_number: Option<i64>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Fields required to insert a new event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewEvmEvent {
+    pub chain_id: i32,
+    pub tx_hash: String,
+    pub event_idx: i32,
+    pub event_type: String,
+    pub data: String,
+    pub status: String,
+    pub block_number: i64,
+}
+
+/// A row of the `evm_transactions` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct EvmTransaction {
+    pub chain_id: i32,
+    pub tx_hash: String,
+    pub tx_type: String,
+    pub status: String,
+    pub data: String,
+    pub extra_data: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Fields required to insert a new EVM transaction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewEvmTransaction {
+    pub chain_id: i32,
+    pub tx_hash: String,
+    pub tx_type: String,
+    pub status: String,
+    pub data: String,
+    pub extra_data: Option<String>,
+}
@@ -1,3 +1,14 @@
+pub mod bitcoin_tx;
+pub mod bridge;
+pub mod challenger;
 pub mod error;
+pub mod evm_event;
+pub mod operator;
+pub mod pagination;
+pub mod pegin;
+pub mod pegout;
+pub mod presigned_tx;
 pub mod pubsub;
 pub mod rpc;
+pub mod sync_cursor;
+pub mod validation;
@@ -0,0 +1,129 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::ValidationError,
+    validation::{check_amount, check_evm_address, parse_x_only_key, MAX_AMOUNT},
+};
+
+/// `status` of operators that are assigned peg-outs.
+pub const OPERATOR_ACTIVE: &str = "active";
+/// `status` of operators a challenge proved to have claimed a reimbursement they weren't owed.
+pub const OPERATOR_SLASHED: &str = "slashed";
+
+/// A row of the `operators` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Operator {
+    pub id: i32,
+    pub address: String,
+    pub chain_id: i32,
+    pub public_key: String,
+    pub fee: i64,
+    pub status: String,
+    pub initial_stake_amount: i64,
+    pub remaining_stake_amount: i64,
+    pub max_support_amount: i64,
+    pub min_support_amount: i64,
+    pub max_pegin_cnt: i32,
+    pub max_pegout_cnt: i32,
+    pub pegin_cnt: i32,
+    pub pegout_cnt: i32,
+    pub slash_cnt: i32,
+    pub register_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Fields required to register a new operator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewOperator {
+    pub address: String,
+    pub chain_id: i32,
+    pub public_key: String,
+    pub fee: i64,
+    pub status: String,
+    pub stake_amount: i64,
+    pub max_support_amount: i64,
+    pub min_support_amount: i64,
+    pub max_pegin_cnt: i32,
+    pub max_pegout_cnt: i32,
+    pub register_at: NaiveDateTime,
+}
+
+impl NewOperator {
+    /// Checks the registration of an operator before it's stored.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_evm_address(&self.address)?;
+        parse_x_only_key(&self.public_key)?;
+        check_amount(self.fee, 0, MAX_AMOUNT)?;
+        check_amount(self.stake_amount, 1, MAX_AMOUNT)?;
+        check_amount(self.max_support_amount, 1, MAX_AMOUNT)?;
+        check_amount(self.min_support_amount, 1, self.max_support_amount)?;
+        if self.max_pegin_cnt < 0 || self.max_pegout_cnt < 0 {
+            return Err(ValidationError::Inconsistent(
+                "peg-in and peg-out limits can't be negative".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Optional constraints for listing operators; unset fields match every operator.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OperatorFilter {
+    pub chain_id: Option<i32>,
+    pub status: Option<String>,
+    /// Only operators able to serve this amount.
+    pub amount: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operator() -> NewOperator {
+        NewOperator {
+            address: "0x0000000000000000000000000000000000000002".to_string(),
+            chain_id: 31337,
+            public_key: "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+                .to_string(),
+            fee: 1_000,
+            status: "active".to_string(),
+            stake_amount: 10_000_000,
+            max_support_amount: 100_000_000,
+            min_support_amount: 100_000,
+            max_pegin_cnt: 10,
+            max_pegout_cnt: 10,
+            register_at: chrono::DateTime::from_timestamp(1_700_000_000, 0)
+                .unwrap()
+                .naive_utc(),
+        }
+    }
+
+    #[test]
+    fn validating_new_operators() {
+        assert_eq!(operator().validate(), Ok(()));
+
+        let inverted = NewOperator {
+            min_support_amount: 200_000_000,
+            ..operator()
+        };
+        assert!(matches!(
+            inverted.validate(),
+            Err(ValidationError::AmountOutOfRange { .. })
+        ));
+        let unstaked = NewOperator {
+            stake_amount: 0,
+            ..operator()
+        };
+        assert!(unstaked.validate().is_err());
+        let unkeyed = NewOperator {
+            public_key: "operator".to_string(),
+            ..operator()
+        };
+        assert!(matches!(
+            unkeyed.validate(),
+            Err(ValidationError::InvalidPublicKey(_))
+        ));
+    }
+}
@@ -0,0 +1,39 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// Position of a row in a `(created_at, id)` ordered listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageCursor {
+    pub created_at: NaiveDateTime,
+    pub id: i32,
+}
+
+/// Keyset pagination over `(created_at, id)`, newest rows first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pagination {
+    /// Only rows strictly older than this cursor are returned; `None` starts from the newest row.
+    pub before: Option<PageCursor>,
+    pub limit: u32,
+}
+
+impl Pagination {
+    pub const MAX_LIMIT: u32 = 100;
+
+    pub fn first(limit: u32) -> Self {
+        Self {
+            before: None,
+            limit,
+        }
+    }
+
+    /// Page size clamped to [`Self::MAX_LIMIT`].
+    pub fn limit(&self) -> i64 {
+        self.limit.min(Self::MAX_LIMIT) as i64
+    }
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self::first(20)
+    }
+}
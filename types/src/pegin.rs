@@ -0,0 +1,250 @@
+use bitcoin::Network;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, Display, EnumIter, EnumString};
+
+use crate::{
+    error::{InvalidTransition, ValidationError},
+    rpc::PeginCreateRequest,
+    validation::{
+        check_amount, check_evm_address, parse_bitcoin_address, parse_x_only_key, MAX_AMOUNT,
+    },
+};
+
+/// Smallest deposit accepted, leaving room for the fees of the take transaction. Peg-outs are
+/// paid from a single peg-in UTXO, so this bounds peg-outs as well.
+pub const MIN_PEGIN_AMOUNT: i64 = 10_000;
+
+/// Lifecycle of a peg-in, stored in `pegins.status`.
+///
+/// A peg-in is `Deposited` once its deposit is confirmed on Bitcoin, `Confirmed` once the
+/// committee accepted it and `PresignCollected` once all presigned transactions are in. A reorg
+/// can move a `Deposited` peg-in back to `Created`, and a deposit can be refunded until the
+/// wrapped tokens are minted.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    AsRefStr,
+    Display,
+    EnumIter,
+    EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum PeginStatus {
+    Created,
+    Deposited,
+    Confirmed,
+    PresignCollected,
+    Minted,
+    Refunded,
+}
+
+impl PeginStatus {
+    pub fn can_transition_to(self, next: PeginStatus) -> bool {
+        use PeginStatus::*;
+
+        matches!(
+            (self, next),
+            (Created, Deposited)
+                | (Deposited, Created | Confirmed | Refunded)
+                | (Confirmed, PresignCollected | Refunded)
+                | (PresignCollected, Minted | Refunded)
+        )
+    }
+
+    pub fn transition(self, next: PeginStatus) -> Result<PeginStatus, InvalidTransition> {
+        if self.can_transition_to(next) {
+            Ok(next)
+        } else {
+            Err(InvalidTransition {
+                entity: "peg-in",
+                from: self.to_string(),
+                to: next.to_string(),
+            })
+        }
+    }
+
+    pub fn is_final(self) -> bool {
+        matches!(self, PeginStatus::Minted | PeginStatus::Refunded)
+    }
+}
+
+impl TryFrom<String> for PeginStatus {
+    type Error = strum::ParseError;
+
+    fn try_from(status: String) -> Result<Self, Self::Error> {
+        status.parse()
+    }
+}
+
+/// A row of the `pegins` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PeginDetails {
+    pub id: i32,
+    pub target_chain_id: i32,
+    pub public_key: String,
+    pub sender_address: String,
+    #[sqlx(try_from = "String")]
+    pub status: PeginStatus,
+    pub pegin_tx_hash: Option<String>,
+    pub receive_address: String,
+    pub amount: i64,
+    pub raw_pegin_hex: Option<String>,
+    /// Set while the deposit of a peg-in the committee already accepted is no longer confirmed
+    /// after a reorg.
+    pub deposit_reorged_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Fields required to insert a new peg-in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewPegin {
+    pub target_chain_id: i32,
+    pub public_key: String,
+    pub sender_address: String,
+    pub status: PeginStatus,
+    pub receive_address: String,
+    pub amount: i64,
+}
+
+impl NewPegin {
+    /// Validates a `bridge_createPegin` request for a deposit on `network`.
+    pub fn new(request: &PeginCreateRequest, network: Network) -> Result<Self, ValidationError> {
+        check_amount(request.amount, MIN_PEGIN_AMOUNT, MAX_AMOUNT)?;
+        parse_x_only_key(&request.public_key)?;
+        parse_bitcoin_address(&request.sender_address, network)?;
+        check_evm_address(&request.receive_address)?;
+        Ok(Self {
+            target_chain_id: request.target_chain_id,
+            public_key: request.public_key.clone(),
+            sender_address: request.sender_address.clone(),
+            status: PeginStatus::Created,
+            receive_address: request.receive_address.clone(),
+            amount: request.amount,
+        })
+    }
+}
+
+/// A row of the `pegin_operations` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PeginOperation {
+    pub id: i32,
+    pub pegin_id: i32,
+    pub operator_id: i32,
+    pub raw_take_tx: String,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// A `Mint` event of a bridge contract, completing a peg-in on the target chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeginEventDetails {
+    pub chain_id: i32,
+    pub tx_hash: String,
+    pub event_idx: i32,
+    pub block_number: Option<i64>,
+    pub to: String,
+    pub amount: String,
+    pub pegin_tx_hash: String,
+    pub status: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use super::*;
+
+    fn request() -> PeginCreateRequest {
+        PeginCreateRequest {
+            target_chain_id: 31337,
+            public_key: "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+                .to_string(),
+            sender_address: "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080".to_string(),
+            receive_address: "0x0000000000000000000000000000000000000001".to_string(),
+            amount: MIN_PEGIN_AMOUNT,
+        }
+    }
+
+    #[test]
+    fn validating_new_pegins() {
+        let pegin = NewPegin::new(&request(), Network::Regtest).unwrap();
+        assert_eq!(pegin.status, PeginStatus::Created);
+        assert_eq!(pegin.amount, MIN_PEGIN_AMOUNT);
+
+        let err = NewPegin::new(&request(), Network::Bitcoin).unwrap_err();
+        assert!(matches!(err, ValidationError::InvalidBitcoinAddress { .. }));
+        let small = PeginCreateRequest {
+            amount: MIN_PEGIN_AMOUNT - 1,
+            ..request()
+        };
+        let err = NewPegin::new(&small, Network::Regtest).unwrap_err();
+        assert!(matches!(err, ValidationError::AmountOutOfRange { .. }));
+        let no_receiver = PeginCreateRequest {
+            receive_address: "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080".to_string(),
+            ..request()
+        };
+        let err = NewPegin::new(&no_receiver, Network::Regtest).unwrap_err();
+        assert!(matches!(err, ValidationError::InvalidEvmAddress(_)));
+    }
+
+    #[test]
+    fn statuses_round_trip_through_the_database_and_json() {
+        for status in PeginStatus::iter() {
+            let stored = status.as_ref().to_string();
+            assert_eq!(PeginStatus::try_from(stored.clone()), Ok(status));
+            assert_eq!(
+                serde_json::to_value(status).unwrap(),
+                serde_json::Value::String(stored)
+            );
+        }
+        assert_eq!(PeginStatus::PresignCollected.as_ref(), "presign_collected");
+        assert!(PeginStatus::try_from("PresignCollected".to_string()).is_err());
+    }
+
+    #[test]
+    fn pegin_json_shape() {
+        let timestamp = chrono::DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .naive_utc();
+        let pegin = PeginDetails {
+            id: 1,
+            target_chain_id: 31337,
+            public_key: "02aa".to_string(),
+            sender_address: "bcrt1qsender".to_string(),
+            status: PeginStatus::PresignCollected,
+            pegin_tx_hash: None,
+            receive_address: "0x01".to_string(),
+            amount: 100_000,
+            raw_pegin_hex: None,
+            deposit_reorged_at: None,
+            created_at: timestamp,
+            updated_at: timestamp,
+        };
+        let json = serde_json::json!({
+            "id": 1,
+            "target_chain_id": 31337,
+            "public_key": "02aa",
+            "sender_address": "bcrt1qsender",
+            "status": "presign_collected",
+            "pegin_tx_hash": null,
+            "receive_address": "0x01",
+            "amount": 100_000,
+            "raw_pegin_hex": null,
+            "deposit_reorged_at": null,
+            "created_at": "2023-11-14T22:13:20",
+            "updated_at": "2023-11-14T22:13:20",
+        });
+        assert_eq!(serde_json::to_value(&pegin).unwrap(), json);
+        assert_eq!(serde_json::from_value::<PeginDetails>(json).unwrap(), pegin);
+    }
+}
@@ -0,0 +1,340 @@
+use bitcoin::Network;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, Display, EnumIter, EnumString};
+
+use crate::{
+    error::{InvalidTransition, ValidationError},
+    pegin::MIN_PEGIN_AMOUNT,
+    validation::{
+        check_amount, check_evm_address, check_evm_tx_hash, parse_bitcoin_address, MAX_AMOUNT,
+    },
+};
+
+/// Lifecycle of a peg-out, stored in `pegouts.status`.
+///
+/// After the burn is observed on the EVM chain, an operator fronts the payout on Bitcoin and then
+/// claims reimbursement from the peg-in UTXO with a kickoff transaction. The claim can be
+/// challenged until the challenge window closes; a successful challenge slashes the operator.
+/// An operator whose payout isn't on its way to the chain by its deadline loses the assignment.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    AsRefStr,
+    Display,
+    EnumIter,
+    EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum PegoutStatus {
+    BurnObserved,
+    OperatorAssigned,
+    PayoutBroadcast,
+    PayoutConfirmed,
+    KickedOff,
+    ChallengeWindowOpen,
+    ChallengeWindowClosed,
+    Reimbursed,
+    Slashed,
+}
+
+impl PegoutStatus {
+    pub fn can_transition_to(self, next: PegoutStatus) -> bool {
+        use PegoutStatus::*;
+
+        matches!(
+            (self, next),
+            (BurnObserved, OperatorAssigned)
+                | (OperatorAssigned, BurnObserved | PayoutBroadcast)
+                // Back when the node refuses the transaction recorded as broadcast.
+                | (PayoutBroadcast, OperatorAssigned | PayoutConfirmed)
+                | (PayoutConfirmed, KickedOff)
+                | (KickedOff, PayoutConfirmed | ChallengeWindowOpen)
+                | (ChallengeWindowOpen, ChallengeWindowClosed | Slashed)
+                | (ChallengeWindowClosed, Reimbursed)
+        )
+    }
+
+    pub fn transition(self, next: PegoutStatus) -> Result<PegoutStatus, InvalidTransition> {
+        if self.can_transition_to(next) {
+            Ok(next)
+        } else {
+            Err(InvalidTransition {
+                entity: "peg-out",
+                from: self.to_string(),
+                to: next.to_string(),
+            })
+        }
+    }
+
+    pub fn is_final(self) -> bool {
+        matches!(self, PegoutStatus::Reimbursed | PegoutStatus::Slashed)
+    }
+}
+
+impl TryFrom<String> for PegoutStatus {
+    type Error = strum::ParseError;
+
+    fn try_from(status: String) -> Result<Self, Self::Error> {
+        status.parse()
+    }
+}
+
+/// A row of the `pegouts` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PegoutDetail {
+    pub id: i32,
+    /// Peg-in whose UTXO pays this peg-out, assigned once an operator locks it.
+    pub pegin_id: Option<i32>,
+    pub operator_id: Option<i32>,
+    pub chain_id: i32,
+    pub burn_tx_hash: String,
+    /// Index of the burn event in its transaction, which may burn several times.
+    pub event_idx: i32,
+    pub sender_address: String,
+    pub receive_address: String,
+    pub amount: i64,
+    pub payout_tx_hash: Option<String>,
+    #[sqlx(try_from = "String")]
+    pub status: PegoutStatus,
+    pub kickoff_tx_hash: Option<String>,
+    /// Bitcoin height the current step has to be finished by: the payout while
+    /// `OperatorAssigned` or `PayoutBroadcast`, and the end of the challenge window while
+    /// `ChallengeWindowOpen`.
+    pub deadline_height: Option<i64>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Fields required to insert a new peg-out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewPegout {
+    pub chain_id: i32,
+    pub burn_tx_hash: String,
+    pub event_idx: i32,
+    pub sender_address: String,
+    pub receive_address: String,
+    pub amount: i64,
+    pub status: PegoutStatus,
+}
+
+impl NewPegout {
+    /// Validates a peg-out for the burn at `event_idx` of `burn_tx_hash` observed on `chain_id`,
+    /// paying out on `network`.
+    pub fn new(
+        chain_id: i32,
+        burn_tx_hash: &str,
+        event_idx: i32,
+        sender_address: &str,
+        receive_address: &str,
+        amount: i64,
+        network: Network,
+    ) -> Result<Self, ValidationError> {
+        check_evm_tx_hash(burn_tx_hash)?;
+        check_evm_address(sender_address)?;
+        parse_bitcoin_address(receive_address, network)?;
+        check_amount(amount, MIN_PEGIN_AMOUNT, MAX_AMOUNT)?;
+        Ok(Self {
+            chain_id,
+            burn_tx_hash: burn_tx_hash.to_string(),
+            event_idx,
+            sender_address: sender_address.to_string(),
+            receive_address: receive_address.to_string(),
+            amount,
+            status: PegoutStatus::BurnObserved,
+        })
+    }
+}
+
+/// A status change of a peg-out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PegoutTransition {
+    pub from: PegoutStatus,
+    pub to: PegoutStatus,
+    /// Bitcoin height the change was observed at, if known.
+    pub block_height: Option<i64>,
+    /// New [`PegoutDetail::deadline_height`].
+    pub deadline_height: Option<i64>,
+}
+
+/// A row of the `pegout_status_transitions` table, recording every status change of a peg-out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PegoutStatusTransition {
+    pub id: i64,
+    pub pegout_id: i32,
+    #[sqlx(try_from = "String")]
+    pub from_status: PegoutStatus,
+    #[sqlx(try_from = "String")]
+    pub to_status: PegoutStatus,
+    pub block_height: Option<i64>,
+    pub created_at: NaiveDateTime,
+}
+
+/// A `Burn` event of a bridge contract, starting a peg-out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PegoutEventDetail {
+    pub chain_id: i32,
+    pub tx_hash: String,
+    pub event_idx: i32,
+    pub block_number: Option<i64>,
+    pub from: String,
+    pub amount: String,
+    pub btc_address: String,
+    pub status: String,
+}
+
+/// Summary of a peg-out as shown to its sender and to liquidity providers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PegoutInfo {
+    pub id: i32,
+    pub chain_id: i32,
+    pub burn_tx_hash: String,
+    pub event_idx: i32,
+    pub sender_address: String,
+    pub receive_address: String,
+    pub amount: i64,
+    pub status: PegoutStatus,
+    pub payout_tx_hash: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<PegoutDetail> for PegoutInfo {
+    fn from(pegout: PegoutDetail) -> Self {
+        Self {
+            id: pegout.id,
+            chain_id: pegout.chain_id,
+            burn_tx_hash: pegout.burn_tx_hash,
+            event_idx: pegout.event_idx,
+            sender_address: pegout.sender_address,
+            receive_address: pegout.receive_address,
+            amount: pegout.amount,
+            status: pegout.status,
+            payout_tx_hash: pegout.payout_tx_hash,
+            created_at: pegout.created_at,
+        }
+    }
+}
+
+/// Peg-outs with an assigned operator, starting at `from_pegout_id` in ascending id order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PegoutOperationRangerRequest {
+    pub from_pegout_id: i32,
+    pub len: u32,
+}
+
+impl PegoutOperationRangerRequest {
+    pub const MAX_LEN: u32 = 50;
+}
+
+/// The operator side of a peg-out, watched by challengers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PegoutOperationRangeResponse {
+    pub pegout_id: i32,
+    pub pegin_id: i32,
+    pub operator_id: i32,
+    pub status: PegoutStatus,
+    pub payout_tx_hash: Option<String>,
+    pub kickoff_tx_hash: Option<String>,
+    pub deadline_height: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BURN_TX_HASH: &str = "0xcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcd";
+    const SENDER_ADDRESS: &str = "0x0000000000000000000000000000000000000002";
+    const RECEIVE_ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    #[test]
+    fn validating_new_pegouts() {
+        let pegout = NewPegout::new(
+            1,
+            BURN_TX_HASH,
+            0,
+            SENDER_ADDRESS,
+            RECEIVE_ADDRESS,
+            100_000,
+            Network::Regtest,
+        )
+        .unwrap();
+        assert_eq!(pegout.status, PegoutStatus::BurnObserved);
+
+        let cases = [
+            (
+                "cdcd",
+                SENDER_ADDRESS,
+                RECEIVE_ADDRESS,
+                100_000,
+                Network::Regtest,
+            ),
+            (
+                BURN_TX_HASH,
+                RECEIVE_ADDRESS,
+                RECEIVE_ADDRESS,
+                100_000,
+                Network::Regtest,
+            ),
+            (
+                BURN_TX_HASH,
+                SENDER_ADDRESS,
+                RECEIVE_ADDRESS,
+                100_000,
+                Network::Testnet,
+            ),
+            (
+                BURN_TX_HASH,
+                SENDER_ADDRESS,
+                RECEIVE_ADDRESS,
+                0,
+                Network::Regtest,
+            ),
+        ];
+        for (burn_tx_hash, sender, receiver, amount, network) in cases {
+            let result = NewPegout::new(1, burn_tx_hash, 0, sender, receiver, amount, network);
+            assert!(
+                result.is_err(),
+                "{burn_tx_hash} {sender} {receiver} {amount}"
+            );
+        }
+    }
+
+    #[test]
+    fn pegout_info_json_shape() {
+        let info = PegoutInfo {
+            id: 3,
+            chain_id: 1,
+            burn_tx_hash: BURN_TX_HASH.to_string(),
+            event_idx: 2,
+            sender_address: SENDER_ADDRESS.to_string(),
+            receive_address: RECEIVE_ADDRESS.to_string(),
+            amount: 100_000,
+            status: PegoutStatus::ChallengeWindowOpen,
+            payout_tx_hash: Some("ab".repeat(32)),
+            created_at: chrono::DateTime::from_timestamp(1_700_000_000, 0)
+                .unwrap()
+                .naive_utc(),
+        };
+        let json = serde_json::json!({
+            "id": 3,
+            "chain_id": 1,
+            "burn_tx_hash": BURN_TX_HASH,
+            "event_idx": 2,
+            "sender_address": SENDER_ADDRESS,
+            "receive_address": RECEIVE_ADDRESS,
+            "amount": 100_000,
+            "status": "challenge_window_open",
+            "payout_tx_hash": "ab".repeat(32),
+            "created_at": "2023-11-14T22:13:20",
+        });
+        assert_eq!(serde_json::to_value(&info).unwrap(), json);
+        assert_eq!(serde_json::from_value::<PegoutInfo>(json).unwrap(), info);
+    }
+}
@@ -0,0 +1,117 @@
+use bitcoin::{
+    hashes::Hash,
+    secp256k1::{schnorr, Message, Secp256k1, XOnlyPublicKey},
+    sighash::{Prevouts, SighashCache, TapSighashType},
+    taproot::{LeafVersion, TapLeafHash},
+    Script, Transaction, TxOut,
+};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, Display, EnumIter, EnumString};
+
+use crate::error::ValidationError;
+
+/// Role of a presigned transaction in the peg-in graph of an operator, stored in
+/// `presigned_transactions.tx_type`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    AsRefStr,
+    Display,
+    EnumIter,
+    EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum PresignedTxType {
+    Kickoff,
+    Take,
+    Challenge,
+    Assert,
+    Disprove,
+}
+
+impl TryFrom<String> for PresignedTxType {
+    type Error = strum::ParseError;
+
+    fn try_from(tx_type: String) -> Result<Self, Self::Error> {
+        tx_type.parse()
+    }
+}
+
+/// `status` of presigned transactions still missing committee signatures.
+pub const PRESIGNED_TX_UNSIGNED: &str = "unsigned";
+/// `status` of presigned transactions signed by the whole committee.
+pub const PRESIGNED_TX_SIGNED: &str = "signed";
+
+/// A row of the `presigned_transactions` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PresignedTransaction {
+    pub txid: String,
+    #[sqlx(try_from = "String")]
+    pub tx_type: PresignedTxType,
+    pub pegin_id: i32,
+    pub operator_id: Option<i32>,
+    pub status: String,
+    pub raw_hex: String,
+    /// Number of committee members that signed the transaction.
+    pub signed_committee_cnt: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// An unsigned transaction an operator submits for the committee to sign.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewPresignedTransaction {
+    pub tx_type: PresignedTxType,
+    pub raw_hex: String,
+}
+
+/// Signature of a committee member over a presigned transaction, see
+/// [`CommitteeSignature::message`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommitteeSignature {
+    pub txid: String,
+    /// Hex-encoded Schnorr signature.
+    pub signature: String,
+}
+
+impl CommitteeSignature {
+    /// Message committee members sign for `tx`: the BIP-341 sighash of its first input, spent
+    /// through the committee's N-of-N `leaf_script` with `SIGHASH_DEFAULT`. `prevouts` are the
+    /// outputs spent by the inputs of `tx`, in input order.
+    pub fn message(
+        tx: &Transaction,
+        prevouts: &[TxOut],
+        leaf_script: &Script,
+    ) -> Result<Message, ValidationError> {
+        let leaf_hash = TapLeafHash::from_script(leaf_script, LeafVersion::TapScript);
+        let sighash = SighashCache::new(tx)
+            .taproot_script_spend_signature_hash(
+                0,
+                &Prevouts::All(prevouts),
+                leaf_hash,
+                TapSighashType::Default,
+            )
+            .map_err(|err| ValidationError::Inconsistent(err.to_string()))?;
+        Ok(Message::from_digest(sighash.to_byte_array()))
+    }
+
+    /// Checks that `key` made the signature over `message`.
+    pub fn verify(&self, key: &XOnlyPublicKey, message: &Message) -> Result<(), ValidationError> {
+        let invalid = || ValidationError::InvalidSignature(key.to_string());
+        let signature = hex::decode(&self.signature)
+            .ok()
+            .and_then(|bytes| schnorr::Signature::from_slice(&bytes).ok())
+            .ok_or_else(invalid)?;
+        Secp256k1::verification_only()
+            .verify_schnorr(&signature, message, key)
+            .map_err(|_| invalid())
+    }
+}
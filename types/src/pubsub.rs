@@ -1,14 +1,337 @@
 use std::{fmt, marker::PhantomData};
 
+use bitcoin::{
+    hashes::{sha256, Hash},
+    secp256k1::{schnorr, Message, Secp256k1},
+};
+use chrono::NaiveDateTime;
 use itertools::unfold;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::types::Json;
 use tokio::sync::oneshot;
 
+use crate::{
+    error::ValidationError,
+    pegin::PeginStatus,
+    presigned_tx::{CommitteeSignature, NewPresignedTransaction, PresignedTxType},
+    validation::parse_x_only_key,
+};
+
 #[allow(clippy::large_enum_variant)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum PubSubResult {
     Syncing(bool),
+    BitcoinReorg(BitcoinReorg),
+    CommitteeTask(TaskItem<CommitteeTask>),
+    OperatorTask(TaskItem<OperatorTask>),
+    ChallengerTask(TaskItem<ChallengerTask>),
+}
+
+impl PubSubResult {
+    /// Id of the task, if the result is one.
+    pub fn task_id(&self) -> Option<i64> {
+        match self {
+            Self::Syncing(_) | Self::BitcoinReorg(_) => None,
+            Self::CommitteeTask(item) => Some(item.id),
+            Self::OperatorTask(item) => Some(item.id),
+            Self::ChallengerTask(item) => Some(item.id),
+        }
+    }
+}
+
+/// A task pushed to subscribers. Ids increase with every task of a topic, and a subscription
+/// passing the id of the last task it received as `from_cursor` resumes right after it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TaskItem<T> {
+    pub id: i64,
+    #[serde(flatten)]
+    pub task: T,
+}
+
+/// Work for one of the roles, as stored in `pubsub_tasks.payload`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Task {
+    Committee(CommitteeTask),
+    Operator(OperatorTask),
+    Challenger(ChallengerTask),
+}
+
+impl Task {
+    pub fn into_result(self, id: i64) -> PubSubResult {
+        match self {
+            Self::Committee(task) => PubSubResult::CommitteeTask(TaskItem { id, task }),
+            Self::Operator(task) => PubSubResult::OperatorTask(TaskItem { id, task }),
+            Self::Challenger(task) => PubSubResult::ChallengerTask(TaskItem { id, task }),
+        }
+    }
+}
+
+/// A row of the `pubsub_tasks` table: a task as it was pushed to the subscribers of `topic`.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct StoredTask {
+    pub id: i64,
+    pub topic: String,
+    pub payload: Json<Task>,
+    pub created_at: NaiveDateTime,
+}
+
+/// The Bitcoin chain reorganized below blocks the bridge had already processed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BitcoinReorg {
+    /// Last block shared by the old and the new chain.
+    pub fork_height: u64,
+    pub fork_hash: String,
+    /// Number of processed blocks that were disconnected.
+    pub depth: u64,
+    /// Transactions that are no longer confirmed.
+    pub reverted_tx_hashes: Vec<String>,
+    /// Peg-ins moved back to `created` because their deposit is no longer confirmed.
+    pub reverted_pegin_ids: Vec<i32>,
+    /// Accepted peg-ins whose deposit is no longer confirmed, see
+    /// [`CommitteeTask::DepositReorged`].
+    pub flagged_pegin_ids: Vec<i32>,
+}
+
+/// Work pushed to committee members over the `committeeTasks` subscription.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CommitteeTask {
+    /// A peg-in was deposited; answered with a [`PeginReceivedRequest`].
+    AcceptPegin {
+        pegin_id: i32,
+        target_chain_id: i32,
+        pegin_tx_hash: String,
+        amount: i64,
+    },
+    /// An operator prepared transactions for a confirmed peg-in; answered with a
+    /// [`PeginCommitteeTaskResponse`].
+    SignPeginTransactions {
+        pegin_id: i32,
+        target_chain_id: i32,
+        operator_id: i32,
+        transactions: Vec<UnsignedTransaction>,
+    },
+    /// The deposit of an accepted peg-in was reorged out and isn't confirmed again yet. Until it
+    /// is, the peg-in takes no transactions or signatures and no peg-out is paid from it.
+    DepositReorged {
+        pegin_id: i32,
+        target_chain_id: i32,
+        pegin_tx_hash: String,
+        status: PeginStatus,
+    },
+}
+
+/// A presigned transaction still missing committee signatures.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct UnsignedTransaction {
+    pub txid: String,
+    pub tx_type: PresignedTxType,
+    pub raw_hex: String,
+}
+
+/// Work pushed to operators over the `operatorTasks` subscription. Peg-out tasks are only meant
+/// for the operator identified by `operator_id`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OperatorTask {
+    /// A peg-in was confirmed; answered with a [`PeginOperatorTaskResponse`].
+    PreparePegin {
+        pegin_id: i32,
+        target_chain_id: i32,
+        pegin_tx_hash: String,
+        amount: i64,
+    },
+    /// The operator locked a peg-out and has to pay it; answered with an
+    /// [`OperatorPegoutTxRequest`].
+    PayPegout {
+        pegout_id: i32,
+        operator_id: i32,
+        receive_address: String,
+        amount: i64,
+        deadline_height: Option<i64>,
+    },
+    /// The payout is confirmed; answered with a [`PegoutOperatorTaskResponse`].
+    KickOff {
+        pegout_id: i32,
+        operator_id: i32,
+        payout_tx_hash: String,
+    },
+    /// The challenge window closed without a successful challenge; answered with a
+    /// [`PegoutReceivedRequest`] once the take transaction confirms.
+    Take { pegout_id: i32, operator_id: i32 },
+}
+
+/// Work pushed to challengers over the `challengerTasks` subscription.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChallengerTask {
+    /// A kickoff can be challenged until `deadline_height`, see `queryChallengeData`.
+    VerifyKickoff {
+        pegout_id: i32,
+        operator_id: i32,
+        kickoff_tx_hash: String,
+        deadline_height: i64,
+    },
+}
+
+/// Proof that a subscriber holds the key of a committee member or an operator: a BIP-340
+/// signature over a nonce issued by `test_getChallenge`. Nonces are single-use.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionAuth {
+    /// Key as registered in `committees.public_key` or `operators.public_key`.
+    pub public_key: String,
+    pub nonce: String,
+    /// Hex-encoded Schnorr signature over [`SubscriptionAuth::message`].
+    pub signature: String,
+}
+
+impl SubscriptionAuth {
+    const DOMAIN: &'static [u8] = b"bitvm-bridge/subscribe";
+
+    /// Message signed to subscribe to `sub_type`. The domain prefix keeps the server from having
+    /// a subscriber sign anything but a subscription, e.g. a transaction sighash.
+    pub fn message(sub_type: &str, nonce: &str) -> Message {
+        let preimage = [
+            Self::DOMAIN,
+            b"/",
+            sub_type.as_bytes(),
+            b"/",
+            nonce.as_bytes(),
+        ]
+        .concat();
+        Message::from_digest(sha256::Hash::hash(&preimage).to_byte_array())
+    }
+
+    /// Checks the signature of the subscription to `sub_type`, leaving the nonce and whether the
+    /// key is registered to the caller.
+    pub fn verify(&self, sub_type: &str) -> Result<(), ValidationError> {
+        verify_signature(
+            &self.public_key,
+            &self.signature,
+            &Self::message(sub_type, &self.nonce),
+        )
+    }
+}
+
+/// Request of an operator or committee member, signed with the key it names to prove who sent it:
+/// a BIP-340 signature over the request and a nonce issued by `bridge_getNonce`. Nonces are
+/// single-use, so a signed request can't be replayed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedRequest<T> {
+    #[serde(flatten)]
+    pub request: T,
+    pub nonce: String,
+    /// Hex-encoded Schnorr signature over [`SignedRequest::message`].
+    pub signature: String,
+}
+
+impl<T: Serialize> SignedRequest<T> {
+    const DOMAIN: &'static [u8] = b"bitvm-bridge/request";
+
+    /// Message signed to call `method`, e.g. `bridge_tryLockPeginUtxo`, with `request`. The
+    /// request is serialized as compact JSON with fields in declaration order, as `serde_json`
+    /// does.
+    pub fn message(method: &str, nonce: &str, request: &T) -> Message {
+        let request = serde_json::to_vec(request).expect("requests serialize to JSON");
+        let preimage = [
+            Self::DOMAIN,
+            b"/",
+            method.as_bytes(),
+            b"/",
+            nonce.as_bytes(),
+            b"/",
+            &request,
+        ]
+        .concat();
+        Message::from_digest(sha256::Hash::hash(&preimage).to_byte_array())
+    }
+
+    /// Checks that `public_key` signed the call of `method` with the request, leaving the nonce
+    /// and whether the key is registered to the caller.
+    pub fn verify(&self, method: &str, public_key: &str) -> Result<(), ValidationError> {
+        verify_signature(
+            public_key,
+            &self.signature,
+            &Self::message(method, &self.nonce, &self.request),
+        )
+    }
+}
+
+/// Checks a hex-encoded BIP-340 `signature` of `message` by `public_key`.
+fn verify_signature(
+    public_key: &str,
+    signature: &str,
+    message: &Message,
+) -> Result<(), ValidationError> {
+    let key = parse_x_only_key(public_key)?;
+    let invalid = || ValidationError::InvalidSignature(public_key.to_string());
+    let signature = hex::decode(signature)
+        .ok()
+        .and_then(|bytes| schnorr::Signature::from_slice(&bytes).ok())
+        .ok_or_else(invalid)?;
+    Secp256k1::verification_only()
+        .verify_schnorr(&signature, message, &key)
+        .map_err(|_| invalid())
+}
+
+/// Transactions an operator prepared for a peg-in, to be signed by the committee.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeginOperatorTaskResponse {
+    pub pegin_id: i32,
+    pub operator_public_key: String,
+    /// Take transaction claiming the peg-in UTXO, for the committee to sign.
+    pub raw_take_tx: String,
+    pub presigned_txs: Vec<NewPresignedTransaction>,
+}
+
+/// Kickoff transaction an operator broadcast to claim reimbursement for a confirmed payout.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PegoutOperatorTaskResponse {
+    pub pegout_id: i32,
+    pub operator_public_key: String,
+    pub raw_kickoff_tx: String,
+}
+
+/// Payout transaction of the operator assigned to a peg-out, broadcast by the node. It has to pay
+/// at least the peg-out amount minus the operator fee to the receive address.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperatorPegoutTxRequest {
+    pub pegout_id: i32,
+    pub operator_public_key: String,
+    pub raw_payout_tx: String,
+}
+
+/// An operator reporting the take transaction that reimbursed it after the challenge window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PegoutReceivedRequest {
+    pub pegout_id: i32,
+    pub operator_public_key: String,
+    pub take_tx_hash: String,
+}
+
+/// A committee member accepting a deposited peg-in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeginReceivedRequest {
+    pub pegin_id: i32,
+    pub committee_public_key: String,
+}
+
+/// Signatures of a committee member over the presigned transactions of a peg-in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeginCommitteeTaskResponse {
+    pub pegin_id: i32,
+    pub committee_public_key: String,
+    pub signatures: Vec<CommitteeSignature>,
+}
+
+/// Signatures of a committee member over the challenge transactions of a peg-out's operator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChallengeCommitteeTaskResponse {
+    pub pegout_id: i32,
+    pub committee_public_key: String,
+    pub signatures: Vec<CommitteeSignature>,
 }
 
 /// Either value or array of values.
@@ -79,3 +402,167 @@ pub struct Completable<T> {
     pub command: T,
     pub completion_sender: oneshot::Sender<()>,
 }
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::secp256k1::Keypair;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn tasks_are_tagged_by_type() {
+        let task = Task::Committee(CommitteeTask::SignPeginTransactions {
+            pegin_id: 1,
+            target_chain_id: 31337,
+            operator_id: 2,
+            transactions: vec![UnsignedTransaction {
+                txid: "aa".to_string(),
+                tx_type: PresignedTxType::Take,
+                raw_hex: "0200".to_string(),
+            }],
+        })
+        .into_result(7);
+        assert_eq!(task.task_id(), Some(7));
+        let value = serde_json::to_value(&task).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "id": 7,
+                "type": "sign_pegin_transactions",
+                "pegin_id": 1,
+                "target_chain_id": 31337,
+                "operator_id": 2,
+                "transactions": [{ "txid": "aa", "tx_type": "take", "raw_hex": "0200" }],
+            })
+        );
+        assert_eq!(serde_json::from_value::<PubSubResult>(value).unwrap(), task);
+
+        // Untagged results must not be mistaken for one another.
+        let results = [
+            Task::Operator(OperatorTask::Take {
+                pegout_id: 3,
+                operator_id: 2,
+            })
+            .into_result(8),
+            Task::Challenger(ChallengerTask::VerifyKickoff {
+                pegout_id: 3,
+                operator_id: 2,
+                kickoff_tx_hash: "bb".to_string(),
+                deadline_height: 150,
+            })
+            .into_result(9),
+            PubSubResult::Syncing(false),
+        ];
+        for result in results {
+            let value = serde_json::to_value(&result).unwrap();
+            assert_eq!(
+                serde_json::from_value::<PubSubResult>(value).unwrap(),
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn stored_tasks_keep_their_role() {
+        let task = Task::Operator(OperatorTask::KickOff {
+            pegout_id: 3,
+            operator_id: 2,
+            payout_tx_hash: "cc".to_string(),
+        });
+        let value = serde_json::to_value(&task).unwrap();
+        assert_eq!(
+            value,
+            json!({ "type": "kick_off", "pegout_id": 3, "operator_id": 2, "payout_tx_hash": "cc" })
+        );
+        assert_eq!(serde_json::from_value::<Task>(value).unwrap(), task);
+    }
+
+    #[test]
+    fn verifying_subscription_signatures() {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_seckey_slice(&secp, &[7; 32]).unwrap();
+        let sign = |sub_type: &str, nonce: &str| {
+            let message = SubscriptionAuth::message(sub_type, nonce);
+            hex::encode(secp.sign_schnorr_no_aux_rand(&message, &keypair).as_ref())
+        };
+        let auth = SubscriptionAuth {
+            public_key: keypair.x_only_public_key().0.to_string(),
+            nonce: "aa".repeat(32),
+            signature: sign("operatorTasks", &"aa".repeat(32)),
+        };
+        auth.verify("operatorTasks").unwrap();
+
+        // Signatures are bound to the subscription and the nonce.
+        assert!(matches!(
+            auth.verify("committeeTasks"),
+            Err(ValidationError::InvalidSignature(_))
+        ));
+        let other_nonce = SubscriptionAuth {
+            nonce: "bb".repeat(32),
+            ..auth.clone()
+        };
+        assert!(matches!(
+            other_nonce.verify("operatorTasks"),
+            Err(ValidationError::InvalidSignature(_))
+        ));
+        let unknown_key = SubscriptionAuth {
+            public_key: "operator".to_string(),
+            ..auth
+        };
+        assert!(matches!(
+            unknown_key.verify("operatorTasks"),
+            Err(ValidationError::InvalidPublicKey(_))
+        ));
+    }
+
+    #[test]
+    fn verifying_signed_requests() {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_seckey_slice(&secp, &[7; 32]).unwrap();
+        let public_key = keypair.x_only_public_key().0.to_string();
+        let request = PegoutReceivedRequest {
+            pegout_id: 3,
+            operator_public_key: public_key.clone(),
+            take_tx_hash: "cc".repeat(32),
+        };
+        let message = SignedRequest::message("bridge_submitPegoutReceived", "aa", &request);
+        let signed = SignedRequest {
+            request,
+            nonce: "aa".to_string(),
+            signature: hex::encode(secp.sign_schnorr_no_aux_rand(&message, &keypair).as_ref()),
+        };
+        signed
+            .verify("bridge_submitPegoutReceived", &public_key)
+            .unwrap();
+
+        // The nonce and signature sit next to the fields of the request.
+        let value = serde_json::to_value(&signed).unwrap();
+        assert_eq!(value["pegout_id"], 3);
+        assert_eq!(value["nonce"], "aa");
+        assert_eq!(
+            serde_json::from_value::<SignedRequest<PegoutReceivedRequest>>(value).unwrap(),
+            signed
+        );
+
+        // Signatures are bound to the method, the request and the key.
+        assert!(matches!(
+            signed.verify("bridge_submitOperatorPegoutTx", &public_key),
+            Err(ValidationError::InvalidSignature(_))
+        ));
+        let mut other_request = signed.clone();
+        other_request.request.pegout_id = 4;
+        assert!(matches!(
+            other_request.verify("bridge_submitPegoutReceived", &public_key),
+            Err(ValidationError::InvalidSignature(_))
+        ));
+        let other_key = Keypair::from_seckey_slice(&secp, &[8; 32]).unwrap();
+        assert!(matches!(
+            signed.verify(
+                "bridge_submitPegoutReceived",
+                &other_key.x_only_public_key().0.to_string()
+            ),
+            Err(ValidationError::InvalidSignature(_))
+        ));
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+use crate::pagination::Pagination;
+
+/// Parameters of `bridge_createPegin`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeginCreateRequest {
+    pub target_chain_id: i32,
+    /// Key the depositor refunds the deposit with.
+    pub public_key: String,
+    /// Bitcoin address the deposit is sent from.
+    pub sender_address: String,
+    /// EVM address the wrapped tokens are minted to.
+    pub receive_address: String,
+    /// Deposit in satoshis.
+    pub amount: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeginCreateResponse {
+    pub pegin_id: i32,
+    /// Bitcoin address the deposit has to pay.
+    pub deposit_address: String,
+    pub amount: i64,
+}
+
+/// Signed deposit transaction of a peg-in created with `bridge_createPegin`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeginRequest {
+    pub pegin_id: i32,
+    pub raw_pegin_hex: String,
+}
+
+/// Peg-in of a liquidity provider, who builds the deposit transaction itself and submits it
+/// together with the peg-in parameters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LPPeginRequest {
+    #[serde(flatten)]
+    pub pegin: PeginCreateRequest,
+    pub raw_pegin_hex: String,
+}
+
+/// An operator offering to pay out a peg-out, see `bridge_tryLockPeginUtxo`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LPPegoutRequest {
+    pub pegout_id: i32,
+    pub operator_public_key: String,
+}
+
+/// Inclusive range of EVM blocks to list `Mint` events of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeginEventRangeRequest {
+    pub chain_id: i32,
+    pub from_block: i64,
+    pub to_block: i64,
+}
+
+/// Inclusive range of EVM blocks to list `Burn` events of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PegouttEventRangeRequest {
+    pub chain_id: i32,
+    pub from_block: i64,
+    pub to_block: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeginTakeTxMsgRequest {
+    pub pegin_id: i32,
+    pub operator_public_key: String,
+}
+
+/// Take transaction the committee signs to let the operator claim the peg-in UTXO.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeginTakeTxMsgReponse {
+    pub pegin_id: i32,
+    pub operator_id: i32,
+    pub txid: String,
+    pub raw_take_tx: String,
+}
+
+/// Peg-outs burnt by `sender_address`, newest first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PegoutRequest {
+    pub sender_address: String,
+    #[serde(default)]
+    pub pagination: Pagination,
+}
@@ -0,0 +1,11 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// A row of the `sync_cursors` table: the last block processed by a chain follower.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SyncCursor {
+    pub cursor_name: String,
+    pub block_number: i64,
+    pub block_hash: String,
+    pub updated_at: NaiveDateTime,
+}
@@ -0,0 +1,121 @@
+//! Checks shared by the validating constructors of the domain types.
+
+use std::str::FromStr;
+
+use bitcoin::{key::XOnlyPublicKey, Address, Network, PublicKey, Txid};
+
+use crate::error::ValidationError;
+
+/// Largest amount of satoshis that can exist, `Amount::MAX_MONEY`.
+pub const MAX_AMOUNT: i64 = 21_000_000 * 100_000_000;
+
+pub fn check_amount(amount: i64, min: i64, max: i64) -> Result<i64, ValidationError> {
+    if (min..=max).contains(&amount) {
+        Ok(amount)
+    } else {
+        Err(ValidationError::AmountOutOfRange { amount, min, max })
+    }
+}
+
+/// Parses a Bitcoin address, requiring it to belong to `network`.
+pub fn parse_bitcoin_address(address: &str, network: Network) -> Result<Address, ValidationError> {
+    Address::from_str(address)
+        .ok()
+        .and_then(|parsed| parsed.require_network(network).ok())
+        .ok_or_else(|| ValidationError::InvalidBitcoinAddress {
+            address: address.to_string(),
+            network,
+        })
+}
+
+/// Checks for a `0x`-prefixed, 20-byte hex address.
+pub fn check_evm_address(address: &str) -> Result<(), ValidationError> {
+    match address.strip_prefix("0x") {
+        Some(hex) if hex.len() == 40 && hex.bytes().all(|b| b.is_ascii_hexdigit()) => Ok(()),
+        _ => Err(ValidationError::InvalidEvmAddress(address.to_string())),
+    }
+}
+
+/// Parses a key given either x-only or compressed.
+pub fn parse_x_only_key(key: &str) -> Result<XOnlyPublicKey, ValidationError> {
+    XOnlyPublicKey::from_str(key)
+        .ok()
+        .or_else(|| PublicKey::from_str(key).ok().map(|key| key.inner.into()))
+        .ok_or_else(|| ValidationError::InvalidPublicKey(key.to_string()))
+}
+
+pub fn parse_txid(tx_hash: &str) -> Result<Txid, ValidationError> {
+    Txid::from_str(tx_hash).map_err(|_| ValidationError::InvalidTxHash(tx_hash.to_string()))
+}
+
+/// Checks for a `0x`-prefixed, 32-byte hex EVM transaction hash.
+pub fn check_evm_tx_hash(tx_hash: &str) -> Result<(), ValidationError> {
+    match tx_hash.strip_prefix("0x") {
+        Some(hex) if hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()) => Ok(()),
+        _ => Err(ValidationError::InvalidTxHash(tx_hash.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REGTEST_ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+    const MAINNET_ADDRESS: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+    const X_ONLY_KEY: &str = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    #[test]
+    fn checking_amounts() {
+        assert_eq!(MAX_AMOUNT as u64, bitcoin::Amount::MAX_MONEY.to_sat());
+        assert_eq!(check_amount(10, 10, 20), Ok(10));
+        assert_eq!(check_amount(20, 10, 20), Ok(20));
+        assert_eq!(
+            check_amount(21, 10, 20),
+            Err(ValidationError::AmountOutOfRange {
+                amount: 21,
+                min: 10,
+                max: 20
+            })
+        );
+        assert!(check_amount(MAX_AMOUNT + 1, 0, MAX_AMOUNT).is_err());
+    }
+
+    #[test]
+    fn bitcoin_addresses_must_match_the_network() {
+        assert!(parse_bitcoin_address(REGTEST_ADDRESS, Network::Regtest).is_ok());
+        assert!(parse_bitcoin_address(MAINNET_ADDRESS, Network::Bitcoin).is_ok());
+        assert_eq!(
+            parse_bitcoin_address(MAINNET_ADDRESS, Network::Regtest),
+            Err(ValidationError::InvalidBitcoinAddress {
+                address: MAINNET_ADDRESS.to_string(),
+                network: Network::Regtest,
+            })
+        );
+        assert!(parse_bitcoin_address("bcrt1qnope", Network::Regtest).is_err());
+    }
+
+    #[test]
+    fn checking_evm_values() {
+        assert!(check_evm_address("0x52908400098527886E0F7030069857D2E4169EE7").is_ok());
+        for address in [
+            "",
+            "0x",
+            "52908400098527886e0f7030069857d2e4169ee7",
+            "0x1234",
+        ] {
+            assert!(check_evm_address(address).is_err(), "{address}");
+        }
+        assert!(check_evm_address("0x52908400098527886e0f7030069857d2e4169eeg").is_err());
+
+        assert!(check_evm_tx_hash(&format!("0x{}", "ab".repeat(32))).is_ok());
+        assert!(check_evm_tx_hash(&"ab".repeat(32)).is_err());
+    }
+
+    #[test]
+    fn keys_are_accepted_x_only_or_compressed() {
+        let key = parse_x_only_key(X_ONLY_KEY).unwrap();
+        assert_eq!(parse_x_only_key(&format!("02{X_ONLY_KEY}")), Ok(key));
+        assert_eq!(parse_x_only_key(&format!("03{X_ONLY_KEY}")), Ok(key));
+        assert!(parse_x_only_key("operator").is_err());
+    }
+}